@@ -0,0 +1,375 @@
+//! Host-side processing of benchmark results.
+//!
+//! Benchmarks built with the `benchmark` feature report the probe table of
+//! `rtic_edf_pass::scheduler::benchmark` as
+//! `[PROBE] <operation>: count=<n> min=<n> max=<n> total=<n>` lines. Individual
+//! measurements can also be logged as `[TIMING] <operation>: <n> cycles`, or
+//! `[TIMING]: <n> cycles` for unnamed measurements, which additionally gives
+//! percentiles. The log captured from defmt/RTT or semihosting is parsed into a
+//! [`Summary`] of per-operation statistics, which can be exported to CSV or
+//! JSON and compared against a stored baseline.
+
+use std::{collections::BTreeMap, fmt};
+
+use serde::{Deserialize, Serialize};
+
+/// Operation name given to measurements logged without one
+pub const UNNAMED: &str = "unnamed";
+
+const TIMING_TAG: &str = "[TIMING]";
+const PROBE_TAG: &str = "[PROBE]";
+
+/// A single measurement
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sample {
+    pub operation: String,
+    pub cycles: u32,
+}
+
+/// Extract every measurement from a captured log. Lines which aren't
+/// measurements are ignored, so that the whole output of `probe-rs run` or
+/// QEMU can be fed as is. Any prefix added by the log printer (timestamp, log
+/// level) and location suffix are skipped.
+pub fn parse_log(log: &str) -> Vec<Sample> {
+    log.lines().filter_map(parse_line).collect()
+}
+
+fn parse_line(line: &str) -> Option<Sample> {
+    let (_, rest) = line.split_once(TIMING_TAG)?;
+    let (operation, rest) = rest.split_once(':')?;
+    let cycles = rest.split_whitespace().next()?.parse().ok()?;
+
+    let operation = match operation.trim() {
+        "" => UNNAMED,
+        name => name,
+    };
+
+    Some(Sample {
+        operation: operation.to_owned(),
+        cycles,
+    })
+}
+
+/// Measurements accumulated on the target by a timing probe
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProbeReport {
+    pub operation: String,
+    pub count: usize,
+    pub min: u32,
+    pub max: u32,
+    pub total: u64,
+}
+
+/// Extract every probe report from a captured log
+pub fn parse_probe_reports(log: &str) -> Vec<ProbeReport> {
+    log.lines().filter_map(parse_probe_line).collect()
+}
+
+fn parse_probe_line(line: &str) -> Option<ProbeReport> {
+    let (_, rest) = line.split_once(PROBE_TAG)?;
+    let (operation, rest) = rest.split_once(':')?;
+
+    let (mut count, mut min, mut max, mut total) = (None, None, None, None);
+    for field in rest.split_whitespace() {
+        let Some((key, value)) = field.split_once('=') else {
+            continue;
+        };
+
+        match key {
+            "count" => count = value.parse().ok(),
+            "min" => min = value.parse().ok(),
+            "max" => max = value.parse().ok(),
+            "total" => total = value.parse().ok(),
+            _ => {}
+        }
+    }
+
+    Some(ProbeReport {
+        operation: operation.trim().to_owned(),
+        count: count?,
+        min: min?,
+        max: max?,
+        total: total?,
+    })
+}
+
+/// Statistics of one operation, in CPU cycles. Percentiles are only known when
+/// the individual measurements were logged.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OperationStats {
+    pub count: usize,
+    pub min: u32,
+    pub avg: f64,
+    pub max: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub p50: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub p90: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub p99: Option<u32>,
+}
+
+impl OperationStats {
+    /// Returns `None` if there are no samples
+    pub fn new(samples: &[u32]) -> Option<Self> {
+        let mut sorted = samples.to_vec();
+        sorted.sort_unstable();
+
+        Some(Self {
+            count: sorted.len(),
+            min: *sorted.first()?,
+            avg: sorted.iter().map(|&c| c as f64).sum::<f64>() / sorted.len() as f64,
+            max: *sorted.last()?,
+            p50: Some(percentile(&sorted, 50)),
+            p90: Some(percentile(&sorted, 90)),
+            p99: Some(percentile(&sorted, 99)),
+        })
+    }
+
+    /// Returns `None` if the probe has no samples
+    pub fn from_probe_report(report: &ProbeReport) -> Option<Self> {
+        (report.count > 0).then(|| Self {
+            count: report.count,
+            min: report.min,
+            avg: report.total as f64 / report.count as f64,
+            max: report.max,
+            p50: None,
+            p90: None,
+            p99: None,
+        })
+    }
+}
+
+/// Nearest-rank percentile of a sorted, non-empty slice
+fn percentile(sorted: &[u32], p: usize) -> u32 {
+    let rank = (p * sorted.len()).div_ceil(100).max(1);
+    sorted[rank - 1]
+}
+
+/// Statistics of every operation found in a log, sorted by operation name
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Summary {
+    pub operations: BTreeMap<String, OperationStats>,
+}
+
+impl Summary {
+    pub fn new(samples: &[Sample]) -> Self {
+        let mut by_operation: BTreeMap<&str, Vec<u32>> = BTreeMap::new();
+        for sample in samples {
+            by_operation
+                .entry(&sample.operation)
+                .or_default()
+                .push(sample.cycles);
+        }
+
+        let operations = by_operation
+            .into_iter()
+            .filter_map(|(op, cycles)| Some((op.to_owned(), OperationStats::new(&cycles)?)))
+            .collect();
+
+        Self { operations }
+    }
+
+    /// Summarize a log. Probe reports take precedence over individual
+    /// measurements of the same operation, and the last report of a probe
+    /// supersedes the previous ones, since probes accumulate measurements.
+    pub fn from_log(log: &str) -> Self {
+        let mut summary = Self::new(&parse_log(log));
+
+        for report in parse_probe_reports(log) {
+            if let Some(stats) = OperationStats::from_probe_report(&report) {
+                summary.operations.insert(report.operation, stats);
+            }
+        }
+
+        summary
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("Failed to serialize the summary")
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("operation,count,min,avg,max,p50,p90,p99\n");
+
+        for (op, s) in self.operations.iter() {
+            let [p50, p90, p99] = [s.p50, s.p90, s.p99].map(|p| fmt_optional(p, ""));
+            csv += &format!(
+                "{op},{},{},{:.1},{},{p50},{p90},{p99}\n",
+                s.count, s.min, s.avg, s.max
+            );
+        }
+
+        csv
+    }
+
+    /// Compare against a baseline. An operation regresses if its average,
+    /// maximum or 99th percentile exceeds the baseline's by more than
+    /// `threshold` (for example `0.05` for 5%). The 99th percentile is only
+    /// compared if it is known on both sides.
+    pub fn compare(&self, baseline: &Summary, threshold: f64) -> Comparison {
+        let mut operations = BTreeMap::new();
+
+        for (op, current) in self.operations.iter() {
+            let Some(base) = baseline.operations.get(op) else {
+                operations.insert(op.clone(), OperationComparison::New);
+                continue;
+            };
+
+            let avg_change = relative_change(base.avg, current.avg);
+            let max_change = relative_change(base.max as f64, current.max as f64);
+            let p99_change = base
+                .p99
+                .zip(current.p99)
+                .map(|(base, current)| relative_change(base as f64, current as f64));
+
+            operations.insert(
+                op.clone(),
+                OperationComparison::Compared {
+                    avg_change,
+                    max_change,
+                    p99_change,
+                    regressed: avg_change > threshold
+                        || max_change > threshold
+                        || p99_change.is_some_and(|c| c > threshold),
+                },
+            );
+        }
+
+        for op in baseline.operations.keys() {
+            if !self.operations.contains_key(op) {
+                operations.insert(op.clone(), OperationComparison::Missing);
+            }
+        }
+
+        Comparison {
+            operations,
+            threshold,
+        }
+    }
+}
+
+fn fmt_optional(value: Option<u32>, none: &str) -> String {
+    value.map_or_else(|| none.to_owned(), |v| v.to_string())
+}
+
+fn relative_change(base: f64, current: f64) -> f64 {
+    if base == 0.0 {
+        if current == 0.0 { 0.0 } else { f64::INFINITY }
+    } else {
+        (current - base) / base
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<20} {:>7} {:>8} {:>10} {:>8} {:>8} {:>8} {:>8}",
+            "operation", "count", "min", "avg", "max", "p50", "p90", "p99"
+        )?;
+
+        for (op, s) in self.operations.iter() {
+            let [p50, p90, p99] = [s.p50, s.p90, s.p99].map(|p| fmt_optional(p, "-"));
+            writeln!(
+                f,
+                "{op:<20} {:>7} {:>8} {:>10.1} {:>8} {p50:>8} {p90:>8} {p99:>8}",
+                s.count, s.min, s.avg, s.max
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum OperationComparison {
+    /// Relative changes of the average, maximum and 99th percentile, `0.1`
+    /// meaning 10% slower than the baseline
+    Compared {
+        avg_change: f64,
+        max_change: f64,
+        p99_change: Option<f64>,
+        regressed: bool,
+    },
+    /// The operation isn't in the baseline
+    New,
+    /// The operation is in the baseline, but wasn't measured
+    Missing,
+}
+
+/// Result of comparing a [`Summary`] against a baseline
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison {
+    pub operations: BTreeMap<String, OperationComparison>,
+    pub threshold: f64,
+}
+
+impl Comparison {
+    /// Returns the operations which regressed
+    pub fn regressions(&self) -> Vec<&str> {
+        self.operations
+            .iter()
+            .filter(|(_, c)| {
+                matches!(
+                    c,
+                    OperationComparison::Compared {
+                        regressed: true,
+                        ..
+                    }
+                )
+            })
+            .map(|(op, _)| op.as_str())
+            .collect()
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<20} {:>10} {:>10} {:>10}  (threshold {:.1}%)",
+            "operation",
+            "avg",
+            "max",
+            "p99",
+            self.threshold * 100.0
+        )?;
+
+        for (op, comparison) in self.operations.iter() {
+            match comparison {
+                OperationComparison::Compared {
+                    avg_change,
+                    max_change,
+                    p99_change,
+                    regressed,
+                } => {
+                    let p99_change = p99_change
+                        .map_or_else(|| "-".to_owned(), |c| format!("{:+.1}%", c * 100.0));
+                    writeln!(
+                        f,
+                        "{op:<20} {:>+9.1}% {:>+9.1}% {p99_change:>10}{}",
+                        avg_change * 100.0,
+                        max_change * 100.0,
+                        if *regressed { "  REGRESSION" } else { "" }
+                    )?
+                }
+                OperationComparison::New => {
+                    writeln!(f, "{op:<20} {:>10} {:>10} {:>10}", "new", "-", "-")?
+                }
+                OperationComparison::Missing => {
+                    writeln!(f, "{op:<20} {:>10} {:>10} {:>10}", "missing", "-", "-")?
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,127 @@
+//! Summarize the timing measurements of a captured benchmark log.
+//!
+//! ```text
+//! bench-runner <log|-> [--csv <out.csv>] [--json <out.json>] [--baseline <baseline.json>] [--threshold <percent>]
+//! ```
+//!
+//! The log is the output of `probe-rs run` (defmt/RTT) or QEMU (semihosting),
+//! read from stdin if `-` is given. It may contain probe reports, individual
+//! measurements, or both. `--json` writes the summary in the format
+//! expected by `--baseline`. With `--baseline`, the exit status is non-zero if
+//! any operation is more than `--threshold` percent (5 by default) slower than
+//! the baseline.
+
+use std::{
+    fs,
+    io::{self, Read},
+    process::ExitCode,
+};
+
+use bench_runner::Summary;
+
+const USAGE: &str = "usage: bench-runner <log|-> [--csv <out.csv>] [--json <out.json>] [--baseline <baseline.json>] [--threshold <percent>]";
+
+const DEFAULT_THRESHOLD_PERCENT: f64 = 5.0;
+
+struct Args {
+    log: String,
+    csv: Option<String>,
+    json: Option<String>,
+    baseline: Option<String>,
+    threshold_percent: f64,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = std::env::args().skip(1);
+
+    let mut log = None;
+    let mut csv = None;
+    let mut json = None;
+    let mut baseline = None;
+    let mut threshold_percent = DEFAULT_THRESHOLD_PERCENT;
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("missing value for {arg}"));
+
+        match arg.as_str() {
+            "--csv" => csv = Some(value()?),
+            "--json" => json = Some(value()?),
+            "--baseline" => baseline = Some(value()?),
+            "--threshold" => {
+                threshold_percent = value()?
+                    .trim_end_matches('%')
+                    .parse()
+                    .map_err(|e| format!("invalid --threshold: {e}"))?
+            }
+            _ if log.is_none() && (arg == "-" || !arg.starts_with("--")) => log = Some(arg),
+            _ => return Err(format!("unexpected argument {arg}")),
+        }
+    }
+
+    Ok(Args {
+        log: log.ok_or("missing log file")?,
+        csv,
+        json,
+        baseline,
+        threshold_percent,
+    })
+}
+
+/// Returns `Ok(false)` if a regression was found
+fn run(args: Args) -> Result<bool, String> {
+    let log = if args.log == "-" {
+        let mut log = String::new();
+        io::stdin()
+            .read_to_string(&mut log)
+            .map_err(|e| format!("failed to read stdin: {e}"))?;
+        log
+    } else {
+        fs::read_to_string(&args.log).map_err(|e| format!("failed to read {}: {e}", args.log))?
+    };
+
+    let summary = Summary::from_log(&log);
+    if summary.operations.is_empty() {
+        return Err("no timing measurements found in the log".into());
+    }
+    print!("{summary}");
+
+    if let Some(path) = &args.csv {
+        fs::write(path, summary.to_csv()).map_err(|e| format!("failed to write {path}: {e}"))?;
+    }
+
+    if let Some(path) = &args.json {
+        fs::write(path, summary.to_json()).map_err(|e| format!("failed to write {path}: {e}"))?;
+    }
+
+    let Some(path) = &args.baseline else {
+        return Ok(true);
+    };
+
+    let baseline = fs::read_to_string(path).map_err(|e| format!("failed to read {path}: {e}"))?;
+    let baseline =
+        Summary::from_json(&baseline).map_err(|e| format!("invalid baseline {path}: {e}"))?;
+
+    let comparison = summary.compare(&baseline, args.threshold_percent / 100.0);
+    println!();
+    print!("{comparison}");
+
+    Ok(comparison.regressions().is_empty())
+}
+
+fn main() -> ExitCode {
+    let result = parse_args()
+        .map_err(|e| format!("{e}\n{USAGE}"))
+        .and_then(run);
+
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => {
+            eprintln!("error: performance regression against the baseline");
+            ExitCode::FAILURE
+        }
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use crate::{
+    OperationComparison, OperationStats, ProbeReport, Sample, Summary, UNNAMED, parse_log,
+    parse_probe_reports,
+};
+
+fn sample(operation: &str, cycles: u32) -> Sample {
+    Sample {
+        operation: operation.into(),
+        cycles,
+    }
+}
+
+#[test]
+fn parse_lines() {
+    let log = "\
+INFO  Beginning test...
+DEBUG [TIMING] schedule: 312 cycles
+└─ rtic_edf_pass::scheduler::benchmark @ src/scheduler/benchmark.rs:18
+0.000123 DEBUG [TIMING] dispatcher_exit: 45 cycles (rtic_edf_pass src/scheduler/benchmark.rs:18)
+[TIMING]: 7 cycles
+[TIMING] schedule: not a number cycles
+[TIMING] missing separator
+";
+
+    assert_eq!(
+        parse_log(log),
+        [
+            sample("schedule", 312),
+            sample("dispatcher_exit", 45),
+            sample(UNNAMED, 7),
+        ]
+    );
+}
+
+#[test]
+fn operation_stats() {
+    let samples: Vec<u32> = (1..=100).rev().collect();
+    let stats = OperationStats::new(&samples).unwrap();
+
+    assert_eq!(
+        stats,
+        OperationStats {
+            count: 100,
+            min: 1,
+            avg: 50.5,
+            max: 100,
+            p50: Some(50),
+            p90: Some(90),
+            p99: Some(99),
+        }
+    );
+
+    let stats = OperationStats::new(&[42]).unwrap();
+    assert_eq!((stats.p50, stats.p99), (Some(42), Some(42)));
+
+    assert_eq!(OperationStats::new(&[]), None);
+}
+
+#[test]
+fn compare_against_baseline() {
+    let baseline = Summary::new(&[sample("a", 100), sample("b", 100), sample("c", 100)]);
+    let current = Summary::new(&[sample("a", 104), sample("b", 110), sample("d", 1)]);
+
+    let comparison = current.compare(&baseline, 0.05);
+    assert_eq!(comparison.regressions(), ["b"]);
+
+    assert!(matches!(
+        comparison.operations["a"],
+        OperationComparison::Compared {
+            regressed: false,
+            ..
+        }
+    ));
+    assert_eq!(comparison.operations["c"], OperationComparison::Missing);
+    assert_eq!(comparison.operations["d"], OperationComparison::New);
+
+    // Getting faster is never a regression
+    let faster = Summary::new(&[sample("a", 50)]);
+    assert!(faster.compare(&baseline, 0.0).regressions().is_empty());
+}
+
+#[test]
+fn parse_probe_report_lines() {
+    let log = "\
+INFO  [PROBE] schedule: count=3 min=280 max=330 total=910
+└─ rtic_edf_pass::scheduler::benchmark::report @ src/scheduler/benchmark.rs:151
+[PROBE] pop: count=2 min=1
+";
+
+    assert_eq!(
+        parse_probe_reports(log),
+        [ProbeReport {
+            operation: "schedule".into(),
+            count: 3,
+            min: 280,
+            max: 330,
+            total: 910,
+        }]
+    );
+}
+
+#[test]
+fn probe_reports_supersede_samples() {
+    let log = "\
+[TIMING] schedule: 100 cycles
+[TIMING] pop: 40 cycles
+[PROBE] schedule: count=1 min=100 max=100 total=100
+[PROBE] schedule: count=4 min=100 max=400 total=1000
+[PROBE] enqueue: count=0 min=4294967295 max=0 total=0
+";
+
+    let summary = Summary::from_log(log);
+    assert_eq!(
+        summary.operations["schedule"],
+        OperationStats {
+            count: 4,
+            min: 100,
+            avg: 250.0,
+            max: 400,
+            p50: None,
+            p90: None,
+            p99: None,
+        }
+    );
+    assert_eq!(summary.operations["pop"].p99, Some(40));
+    assert!(!summary.operations.contains_key("enqueue"));
+
+    // Without percentiles, the average and the maximum are compared: the
+    // maximum regresses although the average got faster
+    let baseline =
+        Summary::from_log("[TIMING] schedule: 240 cycles\n[TIMING] schedule: 380 cycles");
+    let comparison = summary.compare(&baseline, 0.05);
+    assert_eq!(
+        comparison.operations["schedule"],
+        OperationComparison::Compared {
+            avg_change: (250.0 - 310.0) / 310.0,
+            max_change: (400.0 - 380.0) / 380.0,
+            p99_change: None,
+            regressed: true,
+        }
+    );
+}
@@ -0,0 +1,62 @@
+//! Summarize captured benchmark logs and compare the result with the expected
+//! outputs stored next to them in `tests/fixtures`.
+
+use std::{fs, path::PathBuf};
+
+use bench_runner::{OperationComparison, Summary, UNNAMED};
+
+fn read_to_string(name: &str) -> String {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join(name);
+    fs::read_to_string(&path).unwrap_or_else(|e| panic!("Failed to read {}: {e}", path.display()))
+}
+
+#[test]
+fn rtt_log() {
+    let summary = Summary::from_log(&read_to_string("rtt.log"));
+
+    assert_eq!(
+        summary.operations.keys().collect::<Vec<_>>(),
+        ["dispatcher_exit", "enqueue", "pop", "schedule"]
+    );
+    assert_eq!(summary.to_json(), read_to_string("rtt.summary.json"));
+    assert_eq!(summary.to_csv(), read_to_string("rtt.csv"));
+}
+
+#[test]
+fn probe_log() {
+    let summary = Summary::from_log(&read_to_string("probes.log"));
+
+    assert_eq!(summary.operations.len(), 4);
+    assert_eq!(summary.to_csv(), read_to_string("probes.csv"));
+}
+
+#[test]
+fn semihosting_log() {
+    let summary = Summary::from_log(&read_to_string("semihosting.log"));
+
+    assert_eq!(summary.operations.len(), 1);
+    assert_eq!(summary.operations[UNNAMED].count, 12);
+}
+
+#[test]
+fn json_round_trip() {
+    let summary = Summary::from_log(&read_to_string("rtt.log"));
+    assert_eq!(Summary::from_json(&summary.to_json()).unwrap(), summary);
+}
+
+#[test]
+fn baseline_comparison() {
+    let summary = Summary::from_log(&read_to_string("rtt.log"));
+    let baseline = Summary::from_json(&read_to_string("baseline.json")).unwrap();
+
+    let comparison = summary.compare(&baseline, 0.05);
+    assert_eq!(comparison.regressions(), ["enqueue"]);
+    assert_eq!(comparison.operations["idle"], OperationComparison::Missing);
+    assert_eq!(comparison.to_string(), read_to_string("rtt.comparison.txt"));
+
+    // Within a 15% threshold
+    assert!(summary.compare(&baseline, 0.15).regressions().is_empty());
+}
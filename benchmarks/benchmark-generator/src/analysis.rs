@@ -0,0 +1,152 @@
+//! Analytic EDF schedulability test of periodic task sets.
+//!
+//! Task sets with implicit deadlines are schedulable if and only if their
+//! utilization doesn't exceed 1. Otherwise, the processor demand criterion
+//! (Baruah et al., 1990) is checked at every absolute deadline up to the
+//! smaller of the synchronous busy period and the Baruah bound. Scheduler
+//! overhead is not taken into account, and neither is the blocking on shared
+//! resources, so task sets with resources aren't analyzed.
+
+use std::fmt;
+
+use crate::task_set::{Arrival, TaskSet};
+
+/// Longest interval checked by the processor demand test, in CPU cycles
+const MAX_HORIZON: u64 = 1 << 36;
+
+/// A periodic task, with times expressed in CPU cycles
+#[derive(Debug, Clone, Copy)]
+struct PeriodicTask {
+    wcet: u64,
+    period: u64,
+    deadline: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Verdict {
+    pub utilization: f64,
+    pub schedulable: bool,
+    /// First absolute deadline, in microseconds after a synchronous release,
+    /// at which the processor demand exceeds the available time
+    pub first_miss_us: Option<u64>,
+}
+
+impl Verdict {
+    /// Analyze a task set running on a CPU clocked at `cpu_hz`. Returns `None`
+    /// if some task isn't periodic, pends other tasks or locks a resource,
+    /// since the test only applies to independent periodic tasks.
+    ///
+    /// The critical sections of the generated tasks aren't bounded in cycles,
+    /// so their SRP blocking can't be accounted for.
+    pub fn analyze(task_set: &TaskSet, cpu_hz: u32) -> Option<Self> {
+        let us_to_cycles = |us: u32| us as u64 * cpu_hz as u64 / 1_000_000;
+
+        let tasks = task_set
+            .tasks
+            .iter()
+            .map(|t| match t.arrival {
+                Arrival::Periodic { period_us, .. }
+                    if t.pends.is_empty() && t.shared.is_empty() =>
+                {
+                    Some(PeriodicTask {
+                        wcet: t.wcet_cycles as u64,
+                        period: us_to_cycles(period_us),
+                        deadline: us_to_cycles(t.deadline_us),
+                    })
+                }
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        let utilization: f64 = tasks.iter().map(|t| t.wcet as f64 / t.period as f64).sum();
+
+        let first_miss = if utilization > 1.0 {
+            Some(0)
+        } else if tasks.iter().all(|t| t.deadline >= t.period) {
+            None
+        } else {
+            first_demand_overflow(&tasks, utilization)
+        };
+
+        Some(Self {
+            utilization,
+            schedulable: first_miss.is_none(),
+            first_miss_us: first_miss.map(|t| t * 1_000_000 / cpu_hz as u64),
+        })
+    }
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let verdict = if self.schedulable {
+            "schedulable"
+        } else {
+            "unschedulable"
+        };
+        write!(f, "{verdict} (utilization {:.3}", self.utilization)?;
+
+        match self.first_miss_us {
+            Some(0) | None => write!(f, ")"),
+            Some(t) => write!(f, ", demand exceeds supply at {t} us)"),
+        }
+    }
+}
+
+/// Returns the first absolute deadline at which the processor demand exceeds
+/// the elapsed time, if any. `utilization` must not exceed 1.
+fn first_demand_overflow(tasks: &[PeriodicTask], utilization: f64) -> Option<u64> {
+    let mut horizon = busy_period(tasks);
+    if utilization < 1.0 {
+        let max_deadline = tasks.iter().map(|t| t.deadline).max().unwrap_or(0);
+        let baruah_bound = tasks
+            .iter()
+            .map(|t| t.period.saturating_sub(t.deadline) as f64 * t.wcet as f64 / t.period as f64)
+            .sum::<f64>()
+            / (1.0 - utilization);
+        horizon = horizon.min(max_deadline.max(baruah_bound.ceil() as u64));
+    }
+
+    let mut deadlines: Vec<u64> = tasks
+        .iter()
+        .flat_map(|t| {
+            (0..)
+                .map(move |k| k * t.period + t.deadline)
+                .take_while(move |&d| d <= horizon)
+        })
+        .collect();
+    deadlines.sort_unstable();
+    deadlines.dedup();
+
+    deadlines.into_iter().find(|&t| demand_bound(tasks, t) > t)
+}
+
+/// Length of the synchronous busy period, which always ends if the
+/// utilization doesn't exceed 1. Capped to [`MAX_HORIZON`], since a
+/// utilization of exactly 1 makes it as long as the hyperperiod.
+fn busy_period(tasks: &[PeriodicTask]) -> u64 {
+    let mut length: u64 = tasks.iter().map(|t| t.wcet).sum();
+
+    while length < MAX_HORIZON {
+        let next = tasks
+            .iter()
+            .map(|t| length.div_ceil(t.period) * t.wcet)
+            .sum();
+
+        if next == length {
+            return length;
+        }
+
+        length = next;
+    }
+
+    MAX_HORIZON
+}
+
+/// Cumulative execution time of the jobs released and due within `[0, t]`
+fn demand_bound(tasks: &[PeriodicTask], t: u64) -> u64 {
+    tasks
+        .iter()
+        .filter(|task| task.deadline <= t)
+        .map(|task| ((t - task.deadline) / task.period + 1) * task.wcet)
+        .sum()
+}
@@ -0,0 +1,229 @@
+//! Target chip description.
+//!
+//! The interrupt vectors available to bind timestampers and dispatchers to are
+//! read from the chip's SVD file. Without a `chip(...)` argument, the generator
+//! targets the ATSAMD51J19A of the benchmark board.
+//!
+//! The app's time source is the DWT cycle counter by default. Chips without
+//! one, such as ARMv6-M parts, select another time source with
+//! `monotonic = cortex_m_edf_rtic::monotonic::Systick<48_000_000>`. Its
+//! interrupt handler, if any, must be defined next to the generated app.
+
+use syn::{
+    Error, Ident, LitInt, LitStr, Path, Token, Type, bracketed, parenthesized,
+    parse::{Parse, ParseStream},
+    parse_quote,
+    punctuated::Punctuated,
+};
+
+use crate::handlers::Handler;
+
+pub(crate) struct Chip {
+    /// SVD file, relative to the crate's manifest directory
+    pub svd: String,
+    /// Path of the device's PAC
+    pub device: Path,
+    pub cpu_hz: u32,
+    /// Time source of the app, see [`Chip::monotonic`]
+    pub monotonic: Option<Type>,
+    pub init: BoardInit,
+    /// Vectors to bind tasks to. Defaults to the first half of the available
+    /// vectors, or to the ones not used as dispatchers.
+    pub timestampers: Option<Vec<Ident>>,
+    /// Vectors to use as dispatchers. Defaults to the second half of the
+    /// available vectors, or to the ones not used as timestampers.
+    pub dispatchers: Option<Vec<Ident>>,
+    /// Vectors which must not be used, for example because they are used by
+    /// the board's peripherals
+    pub exclude: Vec<Ident>,
+}
+
+/// Board initialization run at the top of `init`
+pub(crate) enum BoardInit {
+    /// Start the ATSAMD51 clocks at 120 MHz
+    Atsamd51Clocks,
+    /// Call a user-provided `fn()`
+    Custom(Path),
+    None,
+}
+
+impl Default for Chip {
+    fn default() -> Self {
+        Self {
+            svd: "atsamd51j19a.svd".into(),
+            device: parse_quote!(atsamd_hal::pac),
+            cpu_hz: 120_000_000,
+            monotonic: None,
+            init: BoardInit::Atsamd51Clocks,
+            timestampers: None,
+            dispatchers: None,
+            exclude: vec![],
+        }
+    }
+}
+
+impl Chip {
+    /// Returns the time source of the app, the DWT cycle counter at `cpu_hz`
+    /// unless another one was selected
+    pub fn monotonic(&self) -> Type {
+        let cpu_hz = proc_macro2::Literal::u32_unsuffixed(self.cpu_hz);
+        self.monotonic
+            .clone()
+            .unwrap_or_else(|| parse_quote!(::cortex_m_edf_rtic::monotonic::Dwt<#cpu_hz>))
+    }
+
+    /// Split the vectors found in `svd` into timestamper and dispatcher
+    /// vectors, sorted by vector number
+    pub fn vectors(&self, svd: &str) -> Result<(Vec<Handler>, Vec<Handler>), String> {
+        let available: Vec<Handler> = interrupts_from_svd(svd)?
+            .into_iter()
+            .filter(|h| !self.exclude.contains(&h.ident))
+            .collect();
+
+        let select = |names: &[Ident]| {
+            names
+                .iter()
+                .map(|name| {
+                    available
+                        .iter()
+                        .find(|h| h.ident == *name)
+                        .cloned()
+                        .ok_or_else(|| format!("interrupt `{name}` is not available on this chip"))
+                })
+                .collect::<Result<Vec<_>, _>>()
+        };
+
+        let complement = |used: &[Handler]| {
+            available
+                .iter()
+                .filter(|h| !used.iter().any(|u| u.ident == h.ident))
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+
+        let (timestampers, dispatchers) = match (&self.timestampers, &self.dispatchers) {
+            (Some(t), Some(d)) => (select(t)?, select(d)?),
+            (Some(t), None) => {
+                let t = select(t)?;
+                let d = complement(&t);
+                (t, d)
+            }
+            (None, Some(d)) => {
+                let d = select(d)?;
+                (complement(&d), d)
+            }
+            (None, None) => {
+                let (t, d) = available.split_at(available.len() / 2);
+                (t.to_vec(), d.to_vec())
+            }
+        };
+
+        if let Some(h) = timestampers
+            .iter()
+            .find(|t| dispatchers.iter().any(|d| d.ident == t.ident))
+        {
+            return Err(format!(
+                "interrupt `{}` can't be both a timestamper and a dispatcher",
+                h.ident
+            ));
+        }
+
+        Ok((timestampers, dispatchers))
+    }
+}
+
+/// Returns the interrupt vectors declared in an SVD file, sorted by vector
+/// number. Vectors shared by several peripherals are only listed once, and
+/// reserved vectors are skipped.
+pub(crate) fn interrupts_from_svd(svd: &str) -> Result<Vec<Handler>, String> {
+    let doc = roxmltree::Document::parse(svd).map_err(|e| format!("invalid SVD: {e}"))?;
+
+    let mut vectors = Vec::new();
+    for interrupt in doc.descendants().filter(|n| n.has_tag_name("interrupt")) {
+        let (Some(name), Some(value)) = (
+            child_text(interrupt, "name"),
+            child_text(interrupt, "value"),
+        ) else {
+            return Err("SVD interrupt without a name or value".into());
+        };
+
+        if name.to_ascii_uppercase().starts_with("RESERVED") {
+            continue;
+        }
+
+        let vector_idx = value
+            .parse()
+            .map_err(|e| format!("invalid value for interrupt `{name}`: {e}"))?;
+        let ident = syn::parse_str(name)
+            .map_err(|_| format!("interrupt name `{name}` is not a valid identifier"))?;
+
+        vectors.push(Handler { vector_idx, ident });
+    }
+
+    // The sort is stable, so the first peripheral to declare a shared vector
+    // names it
+    vectors.sort_by_key(|h| h.vector_idx);
+    vectors.dedup_by_key(|h| h.vector_idx);
+
+    Ok(vectors)
+}
+
+/// Parse `chip(svd = "...", device = path, cpu_freq = 48_000_000,
+/// init = path, timestampers = [...], dispatchers = [...], exclude = [...])`.
+/// Omitted keys keep their default value, except `init` which defaults to no
+/// board initialization.
+impl Parse for Chip {
+    fn parse(input: ParseStream) -> Result<Self, Error> {
+        let ident: Ident = input.parse()?;
+        if ident != "chip" {
+            return Err(Error::new(ident.span(), "expected `chip(...)`"));
+        }
+
+        let content;
+        parenthesized!(content in input);
+
+        let mut chip = Chip {
+            init: BoardInit::None,
+            ..Default::default()
+        };
+
+        while !content.is_empty() {
+            let key: Ident = content.parse()?;
+            content.parse::<Token![=]>()?;
+
+            match key.to_string().as_str() {
+                "svd" => chip.svd = content.parse::<LitStr>()?.value(),
+                "device" => chip.device = content.parse()?,
+                "cpu_freq" => chip.cpu_hz = content.parse::<LitInt>()?.base10_parse()?,
+                "monotonic" => chip.monotonic = Some(content.parse()?),
+                "init" => chip.init = BoardInit::Custom(content.parse()?),
+                "timestampers" => chip.timestampers = Some(parse_idents(&content)?),
+                "dispatchers" => chip.dispatchers = Some(parse_idents(&content)?),
+                "exclude" => chip.exclude = parse_idents(&content)?,
+                _ => return Err(Error::new(key.span(), format!("unknown setting `{key}`"))),
+            }
+
+            if !content.is_empty() {
+                content.parse::<Token![,]>()?;
+            }
+        }
+
+        Ok(chip)
+    }
+}
+
+fn child_text<'a>(node: roxmltree::Node<'a, '_>, tag: &str) -> Option<&'a str> {
+    node.children()
+        .find(|c| c.has_tag_name(tag))
+        .and_then(|c| c.text())
+        .map(str::trim)
+}
+
+fn parse_idents(input: ParseStream) -> Result<Vec<Ident>, Error> {
+    let content;
+    bracketed!(content in input);
+
+    Ok(Punctuated::<Ident, Token![,]>::parse_terminated(&content)?
+        .into_iter()
+        .collect())
+}
@@ -1,47 +1,49 @@
-use proc_macro2::TokenStream as TokenStream2;
+use proc_macro2::{Literal, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
 use syn::parse_quote;
 
-use crate::{Handler, Settings, handlers::Task};
-
-pub(crate) fn generate_app(handlers: Vec<Handler>, app_settings: Settings) -> TokenStream2 {
-    let mut task_tokens = vec![];
-    let mut pend_chain = vec![];
-
-    let num_deadlines = app_settings.deadline_timings.len();
-
-    let (task_handlers, dispatcher_handlers) = handlers.split_at(handlers.len() / 2);
-
-    let mut deadline_idx = 0;
-    let mut deadline_count = 0;
-    let max_deadline_count =
-        (app_settings.tasks_per_priority as usize).min(task_handlers.len() / num_deadlines);
-
-    let mut tasks_dl = Vec::with_capacity(task_handlers.len());
-
-    // Equally distribute tasks among available deadlines
-    for (i, handler) in task_handlers.iter().enumerate() {
-        let deadline = app_settings.deadline_timings[deadline_idx];
-        deadline_count += 1;
-
-        tasks_dl.push(Task {
-            deadline,
+use crate::{
+    chip::{BoardInit, Chip},
+    handlers::{Handler, Task},
+    task_set::{Arrival, DEFAULT_DURATION_US, TaskSet},
+};
+
+pub(crate) fn generate_app(
+    chip: &Chip,
+    task_handlers: &[Handler],
+    dispatcher_handlers: &[Handler],
+    task_set: &TaskSet,
+) -> TokenStream2 {
+    assert!(
+        task_set.tasks.len() <= task_handlers.len(),
+        "The task set has {} tasks, but only {} interrupts are available to bind tasks to",
+        task_set.tasks.len(),
+        task_handlers.len()
+    );
+
+    let tasks: Vec<Task> = task_set
+        .tasks
+        .iter()
+        .zip(task_handlers)
+        .map(|(spec, handler)| Task {
+            spec,
             handler: handler.clone(),
-            idx: i,
-            num_delay_cycles: app_settings.task_delay,
-        });
-
-        if deadline_count >= max_deadline_count && deadline_idx < num_deadlines - 1 {
-            deadline_idx += 1;
-            deadline_count = 0;
-        } else if deadline_count >= max_deadline_count && deadline_idx >= num_deadlines - 1 {
-            break;
-        }
-    }
+            pends: spec
+                .pends
+                .iter()
+                .map(|name| {
+                    task_handlers[task_set.task_idx(name).unwrap()]
+                        .ident
+                        .clone()
+                })
+                .collect(),
+        })
+        .collect();
 
     // Count the number of tasks per unique deadline for logging
     let mut deadline_counts = std::collections::HashMap::new();
-    for task in &tasks_dl {
-        *deadline_counts.entry(task.deadline).or_insert(0) += 1;
+    for task in &tasks {
+        *deadline_counts.entry(task.spec.deadline_us).or_insert(0) += 1;
     }
 
     let mut deadline_counts = deadline_counts.into_iter().collect::<Vec<_>>();
@@ -51,69 +53,72 @@ pub(crate) fn generate_app(handlers: Vec<Handler>, app_settings: Settings) -> To
         eprintln!("{} tasks with deadline: {} ", count, deadline);
     }
 
-    for task in tasks_dl.iter() {
-        let timestamper = &task.handler.ident;
-
-        // dispatchers.push(task.dispatcher.ident.clone());
-        let task_stream = task.generate();
-        task_tokens.push(task_stream);
-
-        let pend_timestamper: TokenStream2 = parse_quote! {
-            ::cortex_m::peripheral::NVIC::pend(crate::app::Interrupt::#timestamper);
-        };
-
-        pend_chain.push(pend_timestamper);
-    }
+    let task_tokens = tasks.iter().map(Task::generate);
+
+    // Tasks released once are released from init, unless another task pends them
+    let pended: Vec<&str> = task_set
+        .tasks
+        .iter()
+        .flat_map(|t| t.pends.iter().map(String::as_str))
+        .collect();
+
+    let pend_chain = tasks
+        .iter()
+        .filter(|t| t.spec.arrival == Arrival::Once && !pended.contains(&t.spec.name.as_str()))
+        .map(|t| {
+            let timestamper = &t.handler.ident;
+            quote! {
+                ::cortex_m::peripheral::NVIC::pend(crate::app::Interrupt::#timestamper);
+            }
+        });
 
     let dispatcher_handlers = dispatcher_handlers.iter().map(|h| {
         let ident = &h.ident;
         quote::quote!(#ident)
     });
 
+    let resources: Vec<_> = task_set
+        .resources
+        .iter()
+        .map(|r| format_ident!("{r}"))
+        .collect();
+
+    let idle_exec = generate_idle(chip, task_set, &tasks);
+    let board_init = generate_board_init(chip);
+    let device = &chip.device;
+    let cpu_hz = Literal::u32_unsuffixed(chip.cpu_hz);
+    let monotonic = chip.monotonic();
+
     parse_quote! {
         use defmt_rtt as _;
         use panic_probe as _;
 
         #[cortex_m_edf_rtic::app(
-            device = atsamd_hal::pac,
+            device = #device,
             dispatchers = [#(#dispatcher_handlers,)*],
-            cpu_freq = 120_000_000,
+            cpu_freq = #cpu_hz,
+            monotonic = #monotonic,
         )]
         mod app {
-            use atsamd_hal::{
-                clock::GenericClockController,
-                fugit::ExtU32,
-                pac::{CorePeripherals, Interrupt, NVIC, Peripherals},
-                prelude::InterruptDrivenTimer,
-                timer::TimerCounter,
-            };
+            use #device::Interrupt;
 
             #[shared]
             struct Shared {
-                x: u32,
+                #(#resources: u32,)*
             }
 
             #[init]
             fn system_init() -> Shared {
-                let mut peripherals = Peripherals::take().unwrap();
-                let mut core = CorePeripherals::take().unwrap();
-
-                // Start CPU clock at 120 MHz
-                let mut clocks = GenericClockController::with_external_32kosc(
-                    peripherals.gclk,
-                    &mut peripherals.mclk,
-                    &mut peripherals.osc32kctrl,
-                    &mut peripherals.oscctrl,
-                    &mut peripherals.nvmctrl,
-                );
+                #board_init
 
                 // cortex_m::asm::delay(1_000_000);
                 defmt::info!("Beginning test...");
 
                 #(#pend_chain)*
 
-
-                Shared { x: 0 }
+                Shared {
+                    #(#resources: 0,)*
+                }
             }
 
             #[idle]
@@ -125,12 +130,7 @@ pub(crate) fn generate_app(handlers: Vec<Handler>, app_settings: Settings) -> To
                     Self { _count: 0 }
                 }
 
-                fn exec(&mut self) -> ! {
-                    defmt::info!("Test completed!");
-                    loop {
-                        ::cortex_m::asm::wfi();
-                    }
-                }
+                #idle_exec
             }
 
             #(#task_tokens)*
@@ -138,3 +138,99 @@ pub(crate) fn generate_app(handlers: Vec<Handler>, app_settings: Settings) -> To
         }
     }
 }
+
+/// Generate the idle task's `exec` method. If the task set contains periodic
+/// tasks, idle releases them until the task set's duration has elapsed.
+fn generate_idle(chip: &Chip, task_set: &TaskSet, tasks: &[Task]) -> TokenStream2 {
+    if !task_set.has_periodic_tasks() {
+        return quote! {
+            fn exec(&mut self) -> ! {
+                ::rtic_edf_pass::scheduler::benchmark::report();
+                defmt::info!("Test completed!");
+                loop {
+                    ::cortex_m::asm::wfi();
+                }
+            }
+        };
+    }
+
+    let (periodic, (periods, offsets)): (Vec<_>, (Vec<_>, Vec<_>)) = tasks
+        .iter()
+        .filter_map(|t| match t.spec.arrival {
+            Arrival::Periodic {
+                period_us,
+                offset_us,
+            } => Some((&t.handler.ident, (period_us, offset_us))),
+            Arrival::Once => None,
+        })
+        .unzip();
+    let num_periodic = periodic.len();
+    let duration_us = task_set.duration_us.unwrap_or(DEFAULT_DURATION_US);
+    let monotonic = chip.monotonic();
+
+    quote! {
+        fn exec(&mut self) -> ! {
+            use ::rtic_edf_pass::time::{TimeSource, us_to_ticks};
+
+            type Mono = #monotonic;
+
+            const INTERRUPTS: [crate::app::Interrupt; #num_periodic] =
+                [#(crate::app::Interrupt::#periodic),*];
+            const PERIODS: [u32; #num_periodic] = [#(us_to_ticks::<Mono>(#periods)),*];
+            const OFFSETS: [u32; #num_periodic] = [#(us_to_ticks::<Mono>(#offsets)),*];
+            const DURATION: u32 = us_to_ticks::<Mono>(#duration_us);
+            const {
+                assert!(
+                    DURATION <= i32::MAX as u32,
+                    "The task set duration must be less than half the time source's range"
+                );
+            }
+
+            let start = Mono::now();
+            let mut next_release = OFFSETS.map(|offset| start.wrapping_add(offset));
+
+            // Timestamps wrap around, so compare them through their difference
+            while Mono::now().wrapping_sub(start) < DURATION {
+                let now = Mono::now();
+
+                for (i, next) in next_release.iter_mut().enumerate() {
+                    if now.wrapping_sub(*next) as i32 >= 0 {
+                        ::cortex_m::peripheral::NVIC::pend(INTERRUPTS[i]);
+                        *next = next.wrapping_add(PERIODS[i]);
+                    }
+                }
+            }
+
+            ::rtic_edf_pass::scheduler::benchmark::report();
+            defmt::info!("Test completed!");
+            loop {
+                ::cortex_m::asm::wfi();
+            }
+        }
+    }
+}
+
+fn generate_board_init(chip: &Chip) -> TokenStream2 {
+    match &chip.init {
+        BoardInit::Atsamd51Clocks => quote! {
+            use atsamd_hal::{
+                clock::GenericClockController,
+                pac::{CorePeripherals, Peripherals},
+            };
+
+            let mut peripherals = Peripherals::take().unwrap();
+            let _core = CorePeripherals::take().unwrap();
+
+            // Start CPU clock at 120 MHz
+            let _clocks = GenericClockController::with_external_32kosc(
+                peripherals.gclk,
+                &mut peripherals.mclk,
+                &mut peripherals.osc32kctrl,
+                &mut peripherals.oscctrl,
+                &mut peripherals.nvmctrl,
+            );
+        },
+        BoardInit::Custom(init) => quote!(#init();),
+        BoardInit::None => quote!(),
+    }
+}
@@ -2,6 +2,12 @@ use proc_macro2::TokenStream as TokenStream2;
 use quote::format_ident;
 use syn::{Ident, parse_quote};
 
+use crate::task_set::TaskSpec;
+
+/// Busy work longer than this many cycles is done with a delay loop instead of
+/// unrolled `nop`s
+const MAX_UNROLLED_NOPS: u32 = 64;
+
 #[derive(Clone, Debug)]
 pub(crate) struct Handler {
     pub vector_idx: u16,
@@ -9,26 +15,39 @@ pub(crate) struct Handler {
 }
 
 #[derive(Debug)]
-pub(crate) struct Task {
-    pub deadline: u32,
+pub(crate) struct Task<'a> {
+    pub spec: &'a TaskSpec,
     pub handler: Handler,
-    pub idx: usize,
-    pub num_delay_cycles: usize,
+    /// Timestampers of the tasks pended by this task
+    pub pends: Vec<Ident>,
 }
 
-impl Task {
+impl Task<'_> {
     pub fn generate(&self) -> TokenStream2 {
-        let deadline = &self.deadline;
-        let delay_cycles = self.num_delay_cycles.max(1);
+        let deadline = &self.spec.deadline_us;
+        let delay_cycles = self.spec.wcet_cycles.max(1);
+
+        let timestamper_ident = &self.handler.ident;
+        let task_ident = format_ident!("{}", self.spec.name);
 
-        let timestamper_ident = format_ident!("{}", self.handler.ident);
-        let task_ident = format_ident!("Task{}", self.idx);
+        let busy_work: Vec<TokenStream2> = if delay_cycles <= MAX_UNROLLED_NOPS {
+            std::iter::repeat_n(parse_quote! {cortex_m::asm::nop();}, delay_cycles as usize)
+                .collect()
+        } else {
+            vec![parse_quote! {cortex_m::asm::delay(#delay_cycles);}]
+        };
 
-        let busy_work: Vec<TokenStream2> =
-            std::iter::repeat_n(parse_quote! {cortex_m::asm::nop();}, delay_cycles).collect();
+        let shared: Vec<Ident> = self
+            .spec
+            .shared
+            .iter()
+            .map(|r| format_ident!("{r}"))
+            .collect();
+        let shared_arg = (!shared.is_empty()).then(|| quote::quote!(, shared = [#(#shared),*]));
+        let pends = &self.pends;
 
         parse_quote! {
-            #[task(deadline_us = #deadline, binds = #timestamper_ident)]
+            #[task(deadline_us = #deadline, binds = #timestamper_ident #shared_arg)]
             pub struct #task_ident {}
 
             impl RticTask for #task_ident {
@@ -37,8 +56,13 @@ impl Task {
                 }
 
                 fn exec(&mut self) {
-                    // cortex_m::peripheral::NVIC::pend(crate::app::Interrupt::#_to_pend);
                     #(#busy_work)*
+                    #(
+                        self.shared().#shared.lock(|r| *r = r.wrapping_add(1));
+                    )*
+                    #(
+                        ::cortex_m::peripheral::NVIC::pend(crate::app::Interrupt::#pends);
+                    )*
                 }
             }
         }
@@ -1,121 +0,0 @@
-pub(crate) const INTERRUPT_SOURCES: phf::Map<&'static str, u16> = phf::phf_map! {
-    "PM" => 0u16,
-    "MCLK" => 1u16,
-    "OSCCTRL_XOSC0" => 2u16,
-    "OSCCTRL_XOSC1" => 3u16,
-    "OSCCTRL_DFLL" => 4u16,
-    "OSCCTRL_DPLL0" => 5u16,
-    "OSCCTRL_DPLL1" => 6u16,
-    "OSC32KCTRL" => 7u16,
-    "SUPC_OTHER" => 8u16,
-    "SUPC_BODDET" => 9u16,
-    "WDT" => 10u16,
-    "RTC" => 11u16,
-    "EIC_EXTINT_0" => 12u16,
-    "EIC_EXTINT_1" => 13u16,
-    "EIC_EXTINT_2" => 14u16,
-    "EIC_EXTINT_3" => 15u16,
-    "EIC_EXTINT_4" => 16u16,
-    "EIC_EXTINT_5" => 17u16,
-    "EIC_EXTINT_6" => 18u16,
-    "EIC_EXTINT_7" => 19u16,
-    "EIC_EXTINT_8" => 20u16,
-    "EIC_EXTINT_9" => 21u16,
-    "EIC_EXTINT_10" => 22u16,
-    "EIC_EXTINT_11" => 23u16,
-    "EIC_EXTINT_12" => 24u16,
-    "EIC_EXTINT_13" => 25u16,
-    "EIC_EXTINT_14" => 26u16,
-    "EIC_EXTINT_15" => 27u16,
-    "FREQM" => 28u16,
-    "NVMCTRL_0" => 29u16,
-    "NVMCTRL_1" => 30u16,
-    "DMAC_0" => 31u16,
-    "DMAC_1" => 32u16,
-    "DMAC_2" => 33u16,
-    "DMAC_3" => 34u16,
-    "DMAC_OTHER" => 35u16,
-    "EVSYS_0" => 36u16,
-    "EVSYS_1" => 37u16,
-    "EVSYS_2" => 38u16,
-    "EVSYS_3" => 39u16,
-    "EVSYS_OTHER" => 40u16,
-    "PAC" => 41u16,
-    "RAMECC" => 45u16,
-    "SERCOM0_0" => 46u16,
-    "SERCOM0_1" => 47u16,
-    "SERCOM0_2" => 48u16,
-    "SERCOM0_OTHER" => 49u16,
-    "SERCOM1_0" => 50u16,
-    "SERCOM1_1" => 51u16,
-    "SERCOM1_2" => 52u16,
-    "SERCOM1_OTHER" => 53u16,
-    "SERCOM2_0" => 54u16,
-    "SERCOM2_1" => 55u16,
-    "SERCOM2_2" => 56u16,
-    "SERCOM2_OTHER" => 57u16,
-    "SERCOM3_0" => 58u16,
-    "SERCOM3_1" => 59u16,
-    "SERCOM3_2" => 60u16,
-    "SERCOM3_OTHER" => 61u16,
-    "SERCOM4_0" => 62u16,
-    "SERCOM4_1" => 63u16,
-    "SERCOM4_2" => 64u16,
-    "SERCOM4_OTHER" => 65u16,
-    "SERCOM5_0" => 66u16,
-    "SERCOM5_1" => 67u16,
-    "SERCOM5_2" => 68u16,
-    "SERCOM5_OTHER" => 69u16,
-    "USB_OTHER" => 80u16,
-    "USB_SOF_HSOF" => 81u16,
-    "USB_TRCPT0" => 82u16,
-    "USB_TRCPT1" => 83u16,
-    "TCC0_OTHER" => 85u16,
-    "TCC0_MC0" => 86u16,
-    "TCC0_MC1" => 87u16,
-    "TCC0_MC2" => 88u16,
-    "TCC0_MC3" => 89u16,
-    "TCC0_MC4" => 90u16,
-    "TCC0_MC5" => 91u16,
-    "TCC1_OTHER" => 92u16,
-    "TCC1_MC0" => 93u16,
-    "TCC1_MC1" => 94u16,
-    "TCC1_MC2" => 95u16,
-    "TCC1_MC3" => 96u16,
-    "TCC2_OTHER" => 97u16,
-    "TCC2_MC0" => 98u16,
-    "TCC2_MC1" => 99u16,
-    "TCC2_MC2" => 100u16,
-    "TCC3_OTHER" => 101u16,
-    "TCC3_MC0" => 102u16,
-    "TCC3_MC1" => 103u16,
-    "TCC4_OTHER" => 104u16,
-    "TCC4_MC0" => 105u16,
-    "TCC4_MC1" => 106u16,
-    "TC0" => 107u16,
-    "TC1" => 108u16,
-    "TC2" => 109u16,
-    "TC3" => 110u16,
-    "TC4" => 111u16,
-    "TC5" => 112u16,
-    "PDEC_OTHER" => 115u16,
-    "PDEC_MC0" => 116u16,
-    "PDEC_MC1" => 117u16,
-    "ADC0_OTHER" => 118u16,
-    "ADC0_RESRDY" => 119u16,
-    "ADC1_OTHER" => 120u16,
-    "ADC1_RESRDY" => 121u16,
-    "AC" => 122u16,
-    "DAC_OTHER" => 123u16,
-    "DAC_EMPTY_0" => 124u16,
-    "DAC_EMPTY_1" => 125u16,
-    "DAC_RESRDY_0" => 126u16,
-    "DAC_RESRDY_1" => 127u16,
-    "I2S" => 128u16,
-    "PCC" => 129u16,
-    "AES" => 130u16,
-    "TRNG" => 131u16,
-    "ICM" => 132u16,
-    "QSPI" => 134u16,
-    "SDHC0" => 135u16,
-};
@@ -1,37 +1,161 @@
 use std::{env, fs, path::PathBuf};
 
 use proc_macro::TokenStream;
-use quote::format_ident;
 use syn::parse_macro_input;
 
-use crate::{handlers::Handler, interrupt_sources::INTERRUPT_SOURCES, parse::Settings};
+use crate::{
+    analysis::Verdict,
+    parse::{Args, Input, Settings},
+    task_set::TaskSet,
+};
 
+mod analysis;
+mod chip;
 mod codegen;
 mod handlers;
-mod interrupt_sources;
 mod parse;
+mod random;
+mod task_set;
 
+/// Generate a benchmark app.
+///
+/// Takes either the path of a task set file, relative to the crate's manifest
+/// directory (see the `task_set` module for the format):
+///
+/// ```ignore
+/// generate_benchmark_app!("task-sets/pipeline.toml");
+/// ```
+///
+/// or the number of tasks per priority, the number of busy-delay cycles of
+/// every task, and a list of deadlines:
+///
+/// ```ignore
+/// generate_benchmark_app!(9, 1, [93, 119, 145]);
+/// ```
+///
+/// or the parameters of a random task set of periodic tasks (see the `random`
+/// module), all of which are optional:
+///
+/// ```ignore
+/// generate_benchmark_app!(random(
+///     tasks = 8,
+///     utilization = 0.9,
+///     seed = 42,
+///     min_period_us = 1_000,
+///     max_period_us = 100_000,
+///     min_deadline_ratio = 0.5,
+///     duration_us = 1_000_000,
+///     deadlines = 7,
+/// ));
+/// ```
+///
+/// If every task is periodic and independent (no `pends` or `shared`), the
+/// analytic EDF verdict is printed, and exported as `EXPECTED_SCHEDULABLE`. Random task sets are also written to
+/// `target/<bin name>_task_set.toml`, so that they can be reused as task set
+/// files.
+///
+/// Apps target the ATSAMD51J19A by default. Any other chip can be described
+/// before the task set (see the `chip` module). Interrupt vectors are read from
+/// the chip's SVD file, relative to the crate's manifest directory:
+///
+/// ```ignore
+/// generate_benchmark_app!(
+///     chip(
+///         svd = "stm32f411.svd",
+///         device = stm32f4xx_hal::pac,
+///         cpu_freq = 100_000_000,
+///         monotonic = cortex_m_edf_rtic::monotonic::Dwt<100_000_000>,
+///         init = crate::board_init,
+///         dispatchers = [SPI4, SPI5],
+///         exclude = [USART2],
+///     ),
+///     "task-sets/pipeline.toml"
+/// );
+/// ```
 #[proc_macro]
 pub fn generate_benchmark_app(input: TokenStream) -> TokenStream {
-    let args = parse_macro_input!(input as crate::parse::Args);
-    let settings: Settings = args
-        .try_into()
-        .expect("Macro inputs: integer, integer, [integer]");
-
-    let mut handlers: Vec<_> = INTERRUPT_SOURCES
-        .entries()
-        .map(|(i, idx)| Handler {
-            vector_idx: *idx,
-            ident: format_ident!("{i}"),
-        })
-        .collect();
-    handlers.sort_by_key(|h| h.vector_idx);
-
-    let generated_code = codegen::generate_app(handlers, settings);
+    let Input { chip, args } = parse_macro_input!(input as Input);
+    let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+
+    let svd_path = manifest_dir.join(&chip.svd);
+    let vectors = fs::read_to_string(&svd_path)
+        .map_err(|e| e.to_string())
+        .and_then(|svd| chip.vectors(&svd));
+    let (timestampers, dispatchers) = match vectors {
+        Ok(vectors) => vectors,
+        Err(e) => {
+            let msg = format!("Invalid chip description {}: {e}", svd_path.display());
+            return syn::Error::new(proc_macro2::Span::call_site(), msg)
+                .to_compile_error()
+                .into();
+        }
+    };
+
+    let (task_set, task_set_path) = match &args {
+        Args::TaskSet(path) => {
+            let full_path = manifest_dir.join(path.value());
+
+            let task_set = fs::read_to_string(&full_path)
+                .map_err(|e| e.into())
+                .and_then(|source| TaskSet::from_toml(&source));
+
+            match task_set {
+                Ok(task_set) => (task_set, Some(full_path)),
+                Err(e) => {
+                    let msg = format!("Invalid task set {}: {e}", full_path.display());
+                    return syn::Error::new(path.span(), msg).to_compile_error().into();
+                }
+            }
+        }
+        Args::Positional(args) => {
+            let settings: Settings = args
+                .clone()
+                .try_into()
+                .expect("Macro inputs: integer, integer, [integer]");
+            (TaskSet::from_settings(&settings, timestampers.len()), None)
+        }
+        Args::Random(settings) => (settings.generate(chip.cpu_hz), None),
+    };
 
-    // Write the generated code to a file in the target directory
     let target_dir = find_target_dir().expect("Failed to locate the `target` directory");
     let bin_name = std::env::var("CARGO_BIN_NAME").unwrap();
+
+    let verdict = Verdict::analyze(&task_set, chip.cpu_hz);
+    if let Some(verdict) = verdict {
+        eprintln!("Analytic EDF verdict: {verdict}");
+    }
+
+    if let Args::Random(settings) = &args {
+        let out_path = target_dir.join(format!("{bin_name}_task_set.toml"));
+        let verdict = verdict.expect("Random task sets only contain periodic tasks");
+        let contents = format!(
+            "# Generated with {settings:?}\n# Analytic EDF verdict: {verdict}\n\n{}",
+            toml::to_string(&task_set).expect("Failed to serialize the task set")
+        );
+
+        fs::write(&out_path, contents).expect("Failed to write the task set to file");
+        eprintln!("Generated task set written to {}", out_path.display());
+    }
+
+    let mut generated_code = codegen::generate_app(&chip, &timestampers, &dispatchers, &task_set);
+
+    if let Some(verdict) = verdict {
+        let schedulable = verdict.schedulable;
+        generated_code.extend(quote::quote! {
+            /// Whether the task set passes the analytic EDF schedulability test
+            pub const EXPECTED_SCHEDULABLE: bool = #schedulable;
+        });
+    }
+
+    // Rebuild the app whenever the task set changes
+    if let Some(path) = task_set_path {
+        let path = path.to_string_lossy();
+        generated_code.extend(quote::quote! {
+            const _: &str = include_str!(#path);
+        });
+    }
+
+    // Write the generated code to a file in the target directory
     let out_path = target_dir.join(format!("{bin_name}_generated.rs"));
 
     fs::write(&out_path, generated_code.to_string())
@@ -57,3 +181,6 @@ fn find_target_dir() -> Option<PathBuf> {
         None
     }
 }
+
+#[cfg(test)]
+mod tests;
@@ -1,16 +1,117 @@
 use syn::{
-    Error, LitInt, Token, bracketed,
+    Error, Ident, Lit, LitInt, LitStr, Token, bracketed, parenthesized,
     parse::{Parse, ParseStream},
     punctuated::Punctuated,
 };
 
-pub(crate) struct Args {
+use crate::{chip::Chip, random::RandomSettings};
+
+/// Macro input: an optional `chip(...)` description, followed by the task set
+pub(crate) struct Input {
+    pub chip: Chip,
+    pub args: Args,
+}
+
+impl Parse for Input {
+    fn parse(input: ParseStream) -> Result<Self, Error> {
+        let chip = if input.fork().parse::<Ident>().is_ok_and(|i| i == "chip") {
+            let chip = input.parse()?;
+            input.parse::<Token![,]>()?;
+            chip
+        } else {
+            Chip::default()
+        };
+
+        Ok(Input {
+            chip,
+            args: input.parse()?,
+        })
+    }
+}
+
+pub(crate) enum Args {
+    /// Path to a task set file, relative to the crate's manifest directory
+    TaskSet(LitStr),
+    /// Positional form: tasks per priority, busy-delay cycles and deadlines
+    Positional(PositionalArgs),
+    /// `random(key = value, ...)`: a randomly generated task set
+    Random(RandomSettings),
+}
+
+#[derive(Clone)]
+pub(crate) struct PositionalArgs {
     tasks_per_priority: LitInt,
     task_delay: LitInt,
     deadline_timings: Vec<LitInt>,
 }
 
 impl Parse for Args {
+    fn parse(input: ParseStream) -> Result<Self, Error> {
+        if input.peek(LitStr) {
+            let path = input.parse()?;
+            // Allow a trailing comma
+            let _: Option<Token![,]> = input.parse()?;
+            return Ok(Args::TaskSet(path));
+        }
+
+        if input.peek(Ident) {
+            return parse_random(input).map(Args::Random);
+        }
+
+        input.parse().map(Args::Positional)
+    }
+}
+
+/// Parse `random(tasks = 8, utilization = 0.75, seed = 42)`. Omitted keys keep
+/// their default value.
+fn parse_random(input: ParseStream) -> Result<RandomSettings, Error> {
+    let ident: Ident = input.parse()?;
+    if ident != "random" {
+        return Err(Error::new(ident.span(), "expected `random(...)`"));
+    }
+
+    let content;
+    parenthesized!(content in input);
+
+    let mut settings = RandomSettings::default();
+    let pairs = Punctuated::<(Ident, Lit), Token![,]>::parse_terminated_with(&content, |input| {
+        let key = input.parse()?;
+        input.parse::<Token![=]>()?;
+        Ok((key, input.parse()?))
+    })?;
+
+    for (key, value) in pairs {
+        let int = || match &value {
+            Lit::Int(int) => Ok(int.clone()),
+            _ => Err(Error::new(value.span(), "expected an integer")),
+        };
+        let float = || match &value {
+            Lit::Float(float) => float.base10_parse::<f64>(),
+            Lit::Int(int) => int.base10_parse::<f64>(),
+            _ => Err(Error::new(value.span(), "expected a number")),
+        };
+
+        match key.to_string().as_str() {
+            "tasks" => settings.num_tasks = int()?.base10_parse()?,
+            "utilization" => settings.utilization = float()?,
+            "seed" => settings.seed = int()?.base10_parse()?,
+            "min_period_us" => settings.min_period_us = int()?.base10_parse()?,
+            "max_period_us" => settings.max_period_us = int()?.base10_parse()?,
+            "min_deadline_ratio" => settings.min_deadline_ratio = float()?,
+            "duration_us" => settings.duration_us = int()?.base10_parse()?,
+            "deadlines" => settings.max_deadlines = int()?.base10_parse()?,
+            _ => return Err(Error::new(key.span(), format!("unknown setting `{key}`"))),
+        }
+    }
+
+    settings
+        .validate()
+        .map_err(|e| Error::new(ident.span(), e))?;
+
+    Ok(settings)
+}
+
+impl Parse for PositionalArgs {
     fn parse(input: ParseStream) -> Result<Self, Error> {
         let tasks_per_priority: LitInt = input.parse()?;
         input.parse::<Token![,]>()?;
@@ -25,7 +126,7 @@ impl Parse for Args {
         let values: Punctuated<LitInt, Token![,]> =
             content.parse_terminated(LitInt::parse, Token![,])?;
 
-        Ok(Args {
+        Ok(PositionalArgs {
             tasks_per_priority,
             task_delay,
             deadline_timings: values.into_iter().collect(),
@@ -33,10 +134,10 @@ impl Parse for Args {
     }
 }
 
-impl TryFrom<Args> for Settings {
+impl TryFrom<PositionalArgs> for Settings {
     type Error = Box<dyn std::error::Error>;
 
-    fn try_from(args: Args) -> Result<Self, Self::Error> {
+    fn try_from(args: PositionalArgs) -> Result<Self, Self::Error> {
         let tasks_per_priority = args.tasks_per_priority.base10_parse::<u16>()?;
 
         let task_delay = args.task_delay.base10_parse::<usize>()?;
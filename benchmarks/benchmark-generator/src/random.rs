@@ -0,0 +1,195 @@
+//! Random task set generation.
+//!
+//! Task utilizations are drawn with UUniFast (Bini & Buttazzo, 2005), which
+//! distributes a total utilization uniformly among the tasks. Periods are drawn
+//! log-uniformly, and deadlines are a random fraction of the period. Generation
+//! only depends on the seed, so that a task set can be regenerated on any host.
+//!
+//! Tasks with distinct deadlines need distinct priority levels, so deadlines
+//! are then quantized into at most `max_deadlines` classes: the closest classes
+//! are merged, and each deadline is rounded up to the largest deadline of its
+//! class. A task whose deadline then exceeds its period has its period
+//! stretched to its deadline, so that deadlines stay between
+//! `min_deadline_ratio` times the period and the period, and the WCETs are
+//! computed from the final periods to keep the total utilization.
+
+use crate::task_set::{Arrival, TaskSet, TaskSpec};
+
+/// Parameters of a randomly generated task set
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct RandomSettings {
+    pub num_tasks: usize,
+    /// Total utilization of the task set
+    pub utilization: f64,
+    pub seed: u64,
+    pub min_period_us: u32,
+    pub max_period_us: u32,
+    /// Smallest ratio between a task's deadline and its period. `1.0` gives
+    /// implicit deadlines.
+    pub min_deadline_ratio: f64,
+    /// How long tasks are released for
+    pub duration_us: u32,
+    /// Largest number of distinct deadlines. The ARMv7-M backend has 7 priority
+    /// levels for the tasks, one below the timestampers' priority.
+    pub max_deadlines: usize,
+}
+
+impl Default for RandomSettings {
+    fn default() -> Self {
+        Self {
+            num_tasks: 8,
+            utilization: 0.7,
+            seed: 0,
+            min_period_us: 1_000,
+            max_period_us: 100_000,
+            min_deadline_ratio: 1.0,
+            duration_us: 1_000_000,
+            max_deadlines: 7,
+        }
+    }
+}
+
+impl RandomSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.num_tasks == 0 {
+            return Err("`tasks` must be at least 1".into());
+        }
+
+        if !(self.utilization > 0.0 && self.utilization.is_finite()) {
+            return Err("`utilization` must be positive".into());
+        }
+
+        if self.min_period_us == 0 || self.min_period_us > self.max_period_us {
+            return Err("periods must satisfy 0 < `min_period_us` <= `max_period_us`".into());
+        }
+
+        if !(self.min_deadline_ratio > 0.0 && self.min_deadline_ratio <= 1.0) {
+            return Err("`min_deadline_ratio` must be in (0, 1]".into());
+        }
+
+        if self.max_deadlines == 0 {
+            return Err("`deadlines` must be at least 1".into());
+        }
+
+        Ok(())
+    }
+
+    /// Generate a task set of periodic tasks, for a CPU clocked at `cpu_hz`
+    pub fn generate(&self, cpu_hz: u32) -> TaskSet {
+        let mut rng = SplitMix64::new(self.seed);
+
+        let drawn: Vec<_> = uunifast(&mut rng, self.num_tasks, self.utilization)
+            .into_iter()
+            .map(|utilization| {
+                let period_us = log_uniform(&mut rng, self.min_period_us, self.max_period_us);
+
+                let ratio =
+                    self.min_deadline_ratio + (1.0 - self.min_deadline_ratio) * rng.next_f64();
+                let deadline_us = ((period_us as f64 * ratio).round() as u32).clamp(1, period_us);
+
+                (utilization, period_us, deadline_us)
+            })
+            .collect();
+
+        let classes = deadline_classes(drawn.iter().map(|&(_, _, d)| d), self.max_deadlines);
+
+        let tasks = drawn
+            .into_iter()
+            .enumerate()
+            .map(|(i, (utilization, period_us, deadline_us))| {
+                let deadline_us = classes[classes.partition_point(|&d| d < deadline_us)];
+                let period_us = period_us.max(deadline_us);
+
+                let cycles_per_us = cpu_hz as f64 / 1e6;
+                let wcet_cycles = (utilization * period_us as f64 * cycles_per_us).round() as u32;
+
+                TaskSpec {
+                    name: format!("Task{i}"),
+                    deadline_us,
+                    wcet_cycles: wcet_cycles.max(1),
+                    arrival: Arrival::Periodic {
+                        period_us,
+                        offset_us: 0,
+                    },
+                    shared: vec![],
+                    pends: vec![],
+                }
+            })
+            .collect();
+
+        TaskSet {
+            duration_us: Some(self.duration_us),
+            resources: vec![],
+            tasks,
+        }
+    }
+}
+
+/// Returns the sorted deadline classes covering `deadlines`, merging the two
+/// closest classes (by ratio) until there are at most `max` of them. Each class
+/// is represented by its largest deadline.
+pub(crate) fn deadline_classes(deadlines: impl Iterator<Item = u32>, max: usize) -> Vec<u32> {
+    let mut classes: Vec<u32> = deadlines.collect();
+    classes.sort_unstable();
+    classes.dedup();
+
+    while classes.len() > max {
+        let closest = (0..classes.len() - 1)
+            .min_by(|&a, &b| {
+                let ratio = |i: usize| classes[i + 1] as f64 / classes[i] as f64;
+                ratio(a).total_cmp(&ratio(b))
+            })
+            .unwrap();
+        classes.remove(closest);
+    }
+
+    classes
+}
+
+/// Draw `n` utilizations summing to `total`, uniformly distributed over the
+/// valid simplex
+pub(crate) fn uunifast(rng: &mut SplitMix64, n: usize, total: f64) -> Vec<f64> {
+    let mut utilizations = Vec::with_capacity(n);
+    let mut remaining = total;
+
+    for i in 1..n {
+        let next = remaining * rng.next_f64().powf(1.0 / (n - i) as f64);
+        utilizations.push(remaining - next);
+        remaining = next;
+    }
+
+    utilizations.push(remaining);
+    utilizations
+}
+
+/// Draw a value from a log-uniform distribution over `[min, max]`
+fn log_uniform(rng: &mut SplitMix64, min: u32, max: u32) -> u32 {
+    let (min_ln, max_ln) = ((min as f64).ln(), (max as f64).ln());
+    let value = (min_ln + (max_ln - min_ln) * rng.next_f64()).exp();
+    (value.round() as u32).clamp(min, max)
+}
+
+/// SplitMix64 pseudo-random number generator. Small, fast, and its output
+/// never changes between versions, unlike external generators.
+pub(crate) struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a value uniformly distributed in `[0, 1)`
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
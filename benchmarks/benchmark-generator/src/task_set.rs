@@ -0,0 +1,215 @@
+//! Declarative description of a benchmark task set.
+//!
+//! A task set is written in TOML:
+//!
+//! ```toml
+//! # How long periodic tasks are released for, in microseconds
+//! duration_us = 100_000
+//! # Shared resources, all of type `u32`
+//! resources = ["buffer"]
+//!
+//! [[tasks]]
+//! name = "Sensor"
+//! deadline_us = 200
+//! wcet_cycles = 4_000
+//! arrival = { kind = "periodic", period_us = 1_000, offset_us = 50 }
+//! shared = ["buffer"]
+//! pends = ["Filter"]
+//!
+//! [[tasks]]
+//! name = "Filter"
+//! deadline_us = 500
+//! wcet_cycles = 12_000
+//! shared = ["buffer"]
+//! ```
+//!
+//! Tasks without an `arrival` are released once, from `init`. Tasks listed in
+//! `pends` are released by the pending task once its busy work is done.
+
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::parse::Settings;
+
+/// Release duration used when a task set with periodic tasks doesn't specify
+/// one
+pub(crate) const DEFAULT_DURATION_US: u32 = 1_000_000;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct TaskSet {
+    /// How long periodic tasks are released for
+    pub duration_us: Option<u32>,
+    #[serde(default)]
+    pub resources: Vec<String>,
+    pub tasks: Vec<TaskSpec>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct TaskSpec {
+    pub name: String,
+    pub deadline_us: u32,
+    /// Number of busy-work cycles executed by each job
+    pub wcet_cycles: u32,
+    #[serde(default)]
+    pub arrival: Arrival,
+    /// Resources locked by each job, in order
+    #[serde(default)]
+    pub shared: Vec<String>,
+    /// Tasks released by each job once its busy work is done
+    #[serde(default)]
+    pub pends: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+pub(crate) enum Arrival {
+    /// Released once, from `init`, or only by other tasks if the task is
+    /// pended
+    #[default]
+    Once,
+    /// Released by the idle task every `period_us`, starting `offset_us` after
+    /// idle starts
+    Periodic {
+        period_us: u32,
+        #[serde(default)]
+        offset_us: u32,
+    },
+}
+
+impl TaskSet {
+    pub fn from_toml(source: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let task_set: TaskSet = toml::from_str(source)?;
+        task_set.validate()?;
+        Ok(task_set)
+    }
+
+    /// Build the task set described by the positional macro arguments: up to
+    /// `tasks_per_priority` tasks per deadline, each released once from `init`,
+    /// using at most `max_tasks` tasks.
+    pub fn from_settings(settings: &Settings, max_tasks: usize) -> Self {
+        let num_deadlines = settings.deadline_timings.len();
+        let max_deadline_count =
+            (settings.tasks_per_priority as usize).min(max_tasks / num_deadlines);
+
+        // Equally distribute tasks among available deadlines
+        let tasks = settings
+            .deadline_timings
+            .iter()
+            .flat_map(|&deadline| std::iter::repeat_n(deadline, max_deadline_count))
+            .enumerate()
+            .map(|(i, deadline_us)| TaskSpec {
+                name: format!("Task{i}"),
+                deadline_us,
+                wcet_cycles: settings.task_delay as u32,
+                arrival: Arrival::Once,
+                shared: vec![],
+                pends: vec![],
+            })
+            .collect();
+
+        Self {
+            duration_us: None,
+            resources: vec!["x".into()],
+            tasks,
+        }
+    }
+
+    /// Returns `true` if any task is released periodically
+    pub fn has_periodic_tasks(&self) -> bool {
+        self.tasks
+            .iter()
+            .any(|t| matches!(t.arrival, Arrival::Periodic { .. }))
+    }
+
+    /// Returns the index of the task named `name`
+    pub fn task_idx(&self, name: &str) -> Option<usize> {
+        self.tasks.iter().position(|t| t.name == name)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.tasks.is_empty() {
+            return Err("task set has no tasks".into());
+        }
+
+        let mut names = HashSet::new();
+        for task in self.tasks.iter() {
+            if syn::parse_str::<syn::Ident>(&task.name).is_err() {
+                return Err(format!(
+                    "task name `{}` is not a valid identifier",
+                    task.name
+                ));
+            }
+
+            if !names.insert(task.name.as_str()) {
+                return Err(format!("task `{}` is declared more than once", task.name));
+            }
+
+            if task.deadline_us == 0 {
+                return Err(format!("task `{}` has a zero deadline", task.name));
+            }
+
+            if let Arrival::Periodic { period_us: 0, .. } = task.arrival {
+                return Err(format!("task `{}` has a zero period", task.name));
+            }
+
+            if let Some(res) = task.shared.iter().find(|r| !self.resources.contains(r)) {
+                return Err(format!(
+                    "task `{}` uses undeclared resource `{res}`",
+                    task.name
+                ));
+            }
+        }
+
+        for res in self.resources.iter() {
+            if syn::parse_str::<syn::Ident>(res).is_err() {
+                return Err(format!("resource name `{res}` is not a valid identifier"));
+            }
+        }
+
+        for task in self.tasks.iter() {
+            if let Some(target) = task.pends.iter().find(|p| !names.contains(p.as_str())) {
+                return Err(format!(
+                    "task `{}` pends unknown task `{target}`",
+                    task.name
+                ));
+            }
+        }
+
+        if let Some(task) = self.find_pend_cycle() {
+            return Err(format!(
+                "task `{task}` is part of a pend cycle, which would never let the benchmark complete"
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Returns the name of a task which (transitively) pends itself
+    fn find_pend_cycle(&self) -> Option<&str> {
+        let pends: HashMap<&str, &[String]> = self
+            .tasks
+            .iter()
+            .map(|t| (t.name.as_str(), t.pends.as_slice()))
+            .collect();
+
+        self.tasks.iter().map(|t| t.name.as_str()).find(|&start| {
+            let mut visited = HashSet::new();
+            let mut stack: Vec<&str> = pends[start].iter().map(String::as_str).collect();
+
+            while let Some(task) = stack.pop() {
+                if task == start {
+                    return true;
+                }
+
+                if visited.insert(task) {
+                    stack.extend(pends[task].iter().map(String::as_str));
+                }
+            }
+
+            false
+        })
+    }
+}
@@ -0,0 +1,157 @@
+use quote::format_ident;
+use syn::Ident;
+
+use crate::{
+    chip::{BoardInit, Chip, interrupts_from_svd},
+    parse::{Args, Input},
+};
+
+const ATSAMD51_SVD: &str = include_str!("../../../atsamd51j19a.svd");
+
+const SMALL_SVD: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<device>
+  <name>TEST</name>
+  <peripherals>
+    <peripheral>
+      <name>UART0</name>
+      <interrupt><name>UART0</name><value>3</value></interrupt>
+    </peripheral>
+    <peripheral>
+      <name>TIMER</name>
+      <interrupt><name>TIMER0</name><value>1</value></interrupt>
+      <interrupt><name>RESERVED2</name><value>2</value></interrupt>
+      <interrupt><name>TIMER1</name><value>4</value></interrupt>
+    </peripheral>
+    <peripheral derivedFrom="UART0">
+      <name>UART1</name>
+      <interrupt><name>UART0</name><value>3</value></interrupt>
+      <interrupt><name>UART1</name><value>5</value></interrupt>
+    </peripheral>
+    <peripheral>
+      <name>GPIO</name>
+      <interrupt><name>GPIO</name><value>0</value></interrupt>
+    </peripheral>
+  </peripherals>
+</device>
+"#;
+
+fn idents(names: &[&str]) -> Vec<Ident> {
+    names.iter().map(|n| format_ident!("{n}")).collect()
+}
+
+fn names(handlers: &[crate::handlers::Handler]) -> Vec<String> {
+    handlers.iter().map(|h| h.ident.to_string()).collect()
+}
+
+fn test_chip() -> Chip {
+    Chip {
+        init: BoardInit::None,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn atsamd51_vectors() {
+    let vectors = interrupts_from_svd(ATSAMD51_SVD).unwrap();
+
+    assert_eq!(vectors.len(), 119);
+    assert!(
+        vectors
+            .windows(2)
+            .all(|w| w[0].vector_idx < w[1].vector_idx)
+    );
+    assert_eq!(
+        (vectors[0].ident.to_string(), vectors[0].vector_idx),
+        ("PM".into(), 0)
+    );
+    let last = vectors.last().unwrap();
+    assert_eq!(
+        (last.ident.to_string(), last.vector_idx),
+        ("SDHC0".into(), 135)
+    );
+
+    // Half of the vectors are used as timestampers by default
+    let (timestampers, dispatchers) = Chip::default().vectors(ATSAMD51_SVD).unwrap();
+    assert_eq!((timestampers.len(), dispatchers.len()), (59, 60));
+    assert_eq!(dispatchers[0].ident, "SERCOM4_0");
+}
+
+#[test]
+fn svd_vectors_are_deduplicated_and_sorted() {
+    let vectors = interrupts_from_svd(SMALL_SVD).unwrap();
+    assert_eq!(
+        names(&vectors),
+        ["GPIO", "TIMER0", "UART0", "TIMER1", "UART1"]
+    );
+}
+
+#[test]
+fn vector_selection() {
+    let mut chip = test_chip();
+    chip.exclude = idents(&["UART0"]);
+
+    let (t, d) = chip.vectors(SMALL_SVD).unwrap();
+    assert_eq!(
+        (names(&t), names(&d)),
+        (
+            vec!["GPIO".into(), "TIMER0".into()],
+            vec!["TIMER1".into(), "UART1".into()]
+        )
+    );
+
+    chip.dispatchers = Some(idents(&["GPIO"]));
+    let (t, d) = chip.vectors(SMALL_SVD).unwrap();
+    assert_eq!(names(&t), ["TIMER0", "TIMER1", "UART1"]);
+    assert_eq!(names(&d), ["GPIO"]);
+
+    chip.timestampers = Some(idents(&["UART1", "TIMER0"]));
+    let (t, _) = chip.vectors(SMALL_SVD).unwrap();
+    assert_eq!(names(&t), ["UART1", "TIMER0"]);
+
+    chip.timestampers = Some(idents(&["UART0"]));
+    let err = chip.vectors(SMALL_SVD).unwrap_err();
+    assert!(err.contains("`UART0` is not available"), "{err}");
+
+    chip.timestampers = Some(idents(&["GPIO"]));
+    let err = chip.vectors(SMALL_SVD).unwrap_err();
+    assert!(err.contains("both a timestamper and a dispatcher"), "{err}");
+}
+
+#[test]
+fn parse_chip() {
+    let input: Input = syn::parse_str(
+        r#"chip(
+            svd = "chips/test.svd",
+            device = test_pac,
+            cpu_freq = 48_000_000,
+            monotonic = cortex_m_edf_rtic::monotonic::Systick<48_000_000>,
+            init = crate::board::init,
+            dispatchers = [GPIO, UART1],
+        ),
+        "task-sets/pipeline.toml""#,
+    )
+    .unwrap();
+
+    let chip = input.chip;
+    assert_eq!(chip.svd, "chips/test.svd");
+    assert_eq!(chip.cpu_hz, 48_000_000);
+    assert_eq!(
+        chip.monotonic(),
+        syn::parse_quote!(cortex_m_edf_rtic::monotonic::Systick<48_000_000>)
+    );
+    assert!(matches!(chip.init, BoardInit::Custom(_)));
+    assert_eq!(chip.timestampers, None);
+    assert_eq!(chip.dispatchers, Some(idents(&["GPIO", "UART1"])));
+    assert!(matches!(input.args, Args::TaskSet(_)));
+
+    // Without a chip description, the benchmark board is targeted
+    let input: Input = syn::parse_str("random(seed = 1)").unwrap();
+    assert!(matches!(input.chip.init, BoardInit::Atsamd51Clocks));
+    assert_eq!(
+        input.chip.monotonic(),
+        syn::parse_quote!(::cortex_m_edf_rtic::monotonic::Dwt<120000000>)
+    );
+    assert!(matches!(input.args, Args::Random(_)));
+
+    assert!(syn::parse_str::<Input>(r#"chip(frequency = 1), "a.toml""#).is_err());
+}
@@ -0,0 +1,190 @@
+mod chip;
+mod random;
+
+use quote::format_ident;
+
+use crate::{
+    chip::Chip,
+    codegen::generate_app,
+    handlers::Handler,
+    parse::Settings,
+    task_set::{Arrival, TaskSet},
+};
+
+const PIPELINE: &str = include_str!("../../../task-sets/pipeline.toml");
+
+fn handlers(n: u16) -> Vec<Handler> {
+    (0..n)
+        .map(|i| Handler {
+            vector_idx: i,
+            ident: format_ident!("IRQ{i}"),
+        })
+        .collect()
+}
+
+fn task_set_error(source: &str) -> String {
+    TaskSet::from_toml(source).unwrap_err().to_string()
+}
+
+#[test]
+fn parse_pipeline() {
+    let task_set = TaskSet::from_toml(PIPELINE).unwrap();
+
+    assert_eq!(task_set.duration_us, Some(500_000));
+    assert_eq!(task_set.resources, ["samples", "setpoint"]);
+    assert_eq!(task_set.tasks.len(), 5);
+
+    let sampler = &task_set.tasks[0];
+    assert_eq!(sampler.name, "Sampler");
+    assert_eq!(sampler.deadline_us, 100);
+    assert_eq!(sampler.wcet_cycles, 2_400);
+    assert_eq!(
+        sampler.arrival,
+        Arrival::Periodic {
+            period_us: 250,
+            offset_us: 0
+        }
+    );
+    assert_eq!(sampler.shared, ["samples"]);
+    assert_eq!(sampler.pends, ["Filter"]);
+
+    assert_eq!(task_set.tasks[1].arrival, Arrival::Once);
+    assert!(task_set.has_periodic_tasks());
+}
+
+#[test]
+fn pipeline_load_is_below_one() {
+    let task_set = TaskSet::from_toml(PIPELINE).unwrap();
+    let wcet = |name: &str| {
+        let task = task_set.tasks.iter().find(|t| t.name == name).unwrap();
+        task.wcet_cycles as f64 / 120.0
+    };
+
+    // The Filter and the Logger are pended once per Sampler job
+    let chain = (wcet("Sampler") + wcet("Filter") + wcet("Logger")) / 250.0;
+    let load = chain + wcet("Control") / 1_000.0;
+    assert!(load < 0.65, "{load}");
+}
+
+#[test]
+fn reject_invalid_task_sets() {
+    let err = task_set_error(
+        r#"
+        [[tasks]]
+        name = "A"
+        deadline_us = 10
+        wcet_cycles = 1
+        shared = ["x"]
+        "#,
+    );
+    assert!(err.contains("undeclared resource `x`"), "{err}");
+
+    let err = task_set_error(
+        r#"
+        [[tasks]]
+        name = "A"
+        deadline_us = 10
+        wcet_cycles = 1
+        pends = ["B"]
+        "#,
+    );
+    assert!(err.contains("pends unknown task `B`"), "{err}");
+
+    let err = task_set_error(
+        r#"
+        [[tasks]]
+        name = "A"
+        deadline_us = 10
+        wcet_cycles = 1
+        pends = ["B"]
+
+        [[tasks]]
+        name = "B"
+        deadline_us = 10
+        wcet_cycles = 1
+        pends = ["A"]
+        "#,
+    );
+    assert!(err.contains("pend cycle"), "{err}");
+
+    let err = task_set_error(
+        r#"
+        [[tasks]]
+        name = "A"
+        deadline_us = 10
+        wcet_cycles = 1
+
+        [[tasks]]
+        name = "A"
+        deadline_us = 20
+        wcet_cycles = 1
+        "#,
+    );
+    assert!(err.contains("declared more than once"), "{err}");
+
+    let err = task_set_error(
+        r#"
+        [[tasks]]
+        name = "A"
+        deadline_us = 10
+        wcet_cycles = 1
+        arrival = { kind = "periodic", period_us = 0 }
+        "#,
+    );
+    assert!(err.contains("zero period"), "{err}");
+}
+
+#[test]
+fn positional_settings_distribute_tasks() {
+    let settings = Settings {
+        tasks_per_priority: 3,
+        task_delay: 1,
+        deadline_timings: vec![100, 200],
+    };
+
+    let task_set = TaskSet::from_settings(&settings, 4);
+    let deadlines: Vec<_> = task_set.tasks.iter().map(|t| t.deadline_us).collect();
+    assert_eq!(deadlines, [100, 100, 200, 200]);
+    assert!(!task_set.has_periodic_tasks());
+}
+
+#[test]
+fn generated_app_releases_chain_heads_only() {
+    let task_set = TaskSet::from_toml(PIPELINE).unwrap();
+    let handlers = handlers(12);
+    let (timestampers, dispatchers) = handlers.split_at(6);
+    let app = generate_app(&Chip::default(), timestampers, dispatchers, &task_set).to_string();
+
+    syn::parse_str::<syn::File>(&app).expect("Generated app should parse");
+
+    // `Calibration` is released from init, `Filter` and `Logger` are only
+    // pended by the previous task of the chain
+    for irq in ["IRQ1", "IRQ2", "IRQ4"] {
+        let pend = format!("NVIC :: pend (crate :: app :: Interrupt :: {irq})");
+        assert_eq!(app.matches(&pend).count(), 1, "{irq}");
+    }
+    assert!(app.contains("shared = [samples]"));
+    assert!(app.contains("cortex_m :: asm :: delay (60000u32)"));
+}
+
+#[test]
+fn generated_app_uses_the_chip_time_source() {
+    let task_set = TaskSet::from_toml(PIPELINE).unwrap();
+    let handlers = handlers(12);
+    let (timestampers, dispatchers) = handlers.split_at(6);
+
+    let app = generate_app(&Chip::default(), timestampers, dispatchers, &task_set).to_string();
+    assert!(app.contains("monotonic = :: cortex_m_edf_rtic :: monotonic :: Dwt < 120000000 >"));
+    assert!(app.contains("type Mono = :: cortex_m_edf_rtic :: monotonic :: Dwt < 120000000 >"));
+
+    let chip = Chip {
+        monotonic: Some(syn::parse_quote!(
+            cortex_m_edf_rtic::monotonic::Systick<48_000_000>
+        )),
+        ..Chip::default()
+    };
+    let app = generate_app(&chip, timestampers, dispatchers, &task_set).to_string();
+    assert!(app.contains("monotonic = cortex_m_edf_rtic :: monotonic :: Systick < 48_000_000 >"));
+    assert!(app.contains("type Mono = cortex_m_edf_rtic :: monotonic :: Systick < 48_000_000 >"));
+    assert!(!app.contains("Dwt"));
+}
@@ -0,0 +1,250 @@
+use crate::{
+    analysis::Verdict,
+    parse::Args,
+    random::{RandomSettings, SplitMix64, deadline_classes, uunifast},
+    task_set::{Arrival, TaskSet, TaskSpec},
+};
+
+const CPU_HZ: u32 = 120_000_000;
+
+fn periodic(name: &str, period_us: u32, deadline_us: u32, wcet_us: u32) -> TaskSpec {
+    TaskSpec {
+        name: name.into(),
+        deadline_us,
+        wcet_cycles: wcet_us * 120,
+        arrival: Arrival::Periodic {
+            period_us,
+            offset_us: 0,
+        },
+        shared: vec![],
+        pends: vec![],
+    }
+}
+
+fn task_set(tasks: Vec<TaskSpec>) -> TaskSet {
+    TaskSet {
+        duration_us: None,
+        resources: vec![],
+        tasks,
+    }
+}
+
+#[test]
+fn splitmix_reference_output() {
+    let mut rng = SplitMix64::new(0);
+    assert_eq!(rng.next_u64(), 0xE220_A839_7B1D_CDAF);
+    assert_eq!(rng.next_u64(), 0x6E78_9E6A_A1B9_65F4);
+}
+
+#[test]
+fn uunifast_distributes_total_utilization() {
+    let mut rng = SplitMix64::new(7);
+
+    for n in 1..20 {
+        let utilizations = uunifast(&mut rng, n, 0.8);
+        assert_eq!(utilizations.len(), n);
+        assert!(utilizations.iter().all(|&u| (0.0..=0.8).contains(&u)));
+        assert!((utilizations.iter().sum::<f64>() - 0.8).abs() < 1e-9);
+    }
+}
+
+#[test]
+fn generation_is_reproducible() {
+    let settings = RandomSettings {
+        seed: 42,
+        min_deadline_ratio: 0.5,
+        ..Default::default()
+    };
+
+    let task_set = settings.generate(CPU_HZ);
+    assert_eq!(task_set, settings.generate(CPU_HZ));
+    assert_ne!(
+        task_set,
+        RandomSettings {
+            seed: 43,
+            ..settings.clone()
+        }
+        .generate(CPU_HZ)
+    );
+
+    for task in task_set.tasks.iter() {
+        let Arrival::Periodic { period_us, .. } = task.arrival else {
+            panic!("random tasks should be periodic");
+        };
+        assert!((1_000..=100_000).contains(&period_us));
+        assert!(task.deadline_us * 2 >= period_us && task.deadline_us <= period_us);
+    }
+
+    // Generated task sets can be reused as task set files
+    let toml = toml::to_string(&task_set).unwrap();
+    assert_eq!(TaskSet::from_toml(&toml).unwrap(), task_set);
+}
+
+#[test]
+fn deadlines_fit_the_priority_levels() {
+    for seed in 0..20 {
+        let settings = RandomSettings {
+            seed,
+            num_tasks: 12,
+            min_deadline_ratio: 0.5,
+            ..Default::default()
+        };
+
+        let task_set = settings.generate(CPU_HZ);
+        let mut deadlines: Vec<_> = task_set.tasks.iter().map(|t| t.deadline_us).collect();
+        deadlines.sort_unstable();
+        deadlines.dedup();
+        assert!(deadlines.len() <= 7, "{deadlines:?}");
+
+        for task in task_set.tasks.iter() {
+            let Arrival::Periodic { period_us, .. } = task.arrival else {
+                panic!("random tasks should be periodic");
+            };
+            assert!(task.deadline_us * 2 >= period_us && task.deadline_us <= period_us);
+        }
+
+        let verdict = Verdict::analyze(&task_set, CPU_HZ).unwrap();
+        assert!((verdict.utilization - 0.7).abs() < 1e-3);
+    }
+
+    // The settings of the `random` benchmark
+    let settings = RandomSettings {
+        num_tasks: 8,
+        utilization: 0.9,
+        seed: 1,
+        min_period_us: 1_000,
+        max_period_us: 50_000,
+        min_deadline_ratio: 0.6,
+        duration_us: 2_000_000,
+        max_deadlines: 7,
+    };
+    let mut deadlines: Vec<_> = settings
+        .generate(CPU_HZ)
+        .tasks
+        .iter()
+        .map(|t| t.deadline_us)
+        .collect();
+    deadlines.sort_unstable();
+    deadlines.dedup();
+    assert!(deadlines.len() <= 7, "{deadlines:?}");
+}
+
+#[test]
+fn closest_deadline_classes_are_merged() {
+    let deadlines = [100, 1_000, 105, 2_000, 100, 4_000];
+    assert_eq!(
+        deadline_classes(deadlines.into_iter(), 5),
+        [100, 105, 1_000, 2_000, 4_000]
+    );
+    assert_eq!(
+        deadline_classes(deadlines.into_iter(), 3),
+        [105, 2_000, 4_000]
+    );
+    assert_eq!(deadline_classes(deadlines.into_iter(), 1), [4_000]);
+}
+
+#[test]
+fn generated_utilization_matches_target() {
+    for seed in 0..10 {
+        let settings = RandomSettings {
+            seed,
+            utilization: 0.9,
+            ..Default::default()
+        };
+
+        let verdict = Verdict::analyze(&settings.generate(CPU_HZ), CPU_HZ).unwrap();
+        assert!((verdict.utilization - 0.9).abs() < 1e-3);
+        assert!(verdict.schedulable);
+    }
+}
+
+#[test]
+fn implicit_deadlines() {
+    let verdict = Verdict::analyze(
+        &task_set(vec![
+            periodic("A", 100, 100, 50),
+            periodic("B", 200, 200, 100),
+        ]),
+        CPU_HZ,
+    )
+    .unwrap();
+    assert!(verdict.schedulable);
+    assert_eq!(verdict.utilization, 1.0);
+
+    let verdict = Verdict::analyze(
+        &task_set(vec![
+            periodic("A", 100, 100, 50),
+            periodic("B", 200, 200, 101),
+        ]),
+        CPU_HZ,
+    )
+    .unwrap();
+    assert!(!verdict.schedulable);
+}
+
+#[test]
+fn constrained_deadlines() {
+    // U = 0.6, but both jobs are due 5 us after a synchronous release
+    let verdict = Verdict::analyze(
+        &task_set(vec![periodic("A", 10, 5, 3), periodic("B", 10, 5, 3)]),
+        CPU_HZ,
+    )
+    .unwrap();
+    assert!(!verdict.schedulable);
+    assert_eq!(verdict.first_miss_us, Some(5));
+
+    let verdict = Verdict::analyze(
+        &task_set(vec![
+            periodic("A", 10, 5, 2),
+            periodic("B", 10, 8, 3),
+            periodic("C", 40, 40, 8),
+        ]),
+        CPU_HZ,
+    )
+    .unwrap();
+    assert!(verdict.schedulable, "{verdict}");
+}
+
+#[test]
+fn verdict_needs_periodic_tasks() {
+    let mut tasks = task_set(vec![periodic("A", 10, 10, 1)]);
+    tasks.tasks[0].arrival = Arrival::Once;
+    assert_eq!(Verdict::analyze(&tasks, CPU_HZ), None);
+}
+
+#[test]
+fn verdict_needs_independent_tasks() {
+    let mut tasks = task_set(vec![periodic("A", 10, 10, 1), periodic("B", 20, 20, 1)]);
+    assert!(Verdict::analyze(&tasks, CPU_HZ).is_some());
+
+    // The blocking on a shared resource isn't bounded
+    tasks.resources = vec!["x".into()];
+    tasks.tasks[1].shared = vec!["x".into()];
+    assert_eq!(Verdict::analyze(&tasks, CPU_HZ), None);
+}
+
+#[test]
+fn parse_random_args() {
+    let Args::Random(settings) = syn::parse_str(
+        "random(tasks = 4, utilization = 1, seed = 3, min_deadline_ratio = 0.25, deadlines = 3)",
+    )
+    .unwrap() else {
+        panic!("expected random settings");
+    };
+
+    assert_eq!(
+        settings,
+        RandomSettings {
+            num_tasks: 4,
+            utilization: 1.0,
+            seed: 3,
+            min_deadline_ratio: 0.25,
+            max_deadlines: 3,
+            ..Default::default()
+        }
+    );
+
+    assert!(syn::parse_str::<Args>("random(tasks = 0)").is_err());
+    assert!(syn::parse_str::<Args>("random(deadlines = 0)").is_err());
+    assert!(syn::parse_str::<Args>("random(period = 3)").is_err());
+}
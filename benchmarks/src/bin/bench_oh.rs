@@ -9,9 +9,10 @@ benchmark_generator::generate_benchmark_app!(
     8,
     // Number of busy-delay cycles (Will be replaced with 1 if set to 0 here)
     1,
-    // Deadline timings. Should be stritcly ordered and contain no duplicates.
+    // Deadline timings in microseconds. Should be stritcly ordered and contain no
+    // duplicates.
     [
-        70_000_000, 60_000_000, 50_000_000, 40_000_000, 30_000_000, 20_000_000,
-        10_000_000 // 10_000_000, 20_000_000, 30_000_000, 40_000_000, 50_000_000, 60_000_000
+        583_333, 500_000, 416_667, 333_333, 250_000, 166_667,
+        83_333 // 83_333, 166_667, 250_000, 333_333, 416_667, 500_000
     ]
 );
@@ -9,8 +9,9 @@ benchmark_generator::generate_benchmark_app!(
     9,
     // Number of busy-delay cycles (Will be replaced with 1 if set to 0 here)
     1,
-    // Deadline timings. Should be stritcly ordered and contain no duplicates.
-    [11_179, 14_277, 17_426, 20_292, 22_965, 25_034]
+    // Deadline timings in microseconds. Should be stritcly ordered and contain no
+    // duplicates.
+    [93, 119, 145, 169, 191, 209]
 );
 
 // -------------------------------- RESULTS -----------------------------------------
@@ -19,7 +20,7 @@ benchmark_generator::generate_benchmark_app!(
 //
 // Prio:              6           5           4           3           2           1
 // Max queue len:     48          40          32          24          16          8
-// Rel DL:          11_179      14_277      17_426      20_292      22_965      25_034
+// Rel DL (cycles): 11_179      14_277      17_426      20_292      22_965      25_034
 // Diff:                   3098        3152        2866        2673        2069
 //
 // ----------------------------------------------------------------------------------
@@ -84,9 +84,6 @@ mod app {
         }
     }
 
-    // TODO: currently the deadline is counted in cycles.
-    // We need to retrieve the clock frequency if we want a chance at specifying
-    // them in us instead
     #[task(deadline_us = 1_000_000, binds = SERCOM1_1, shared = [x])]
     pub struct ManualTask {}
 
@@ -106,9 +103,6 @@ mod app {
         }
     }
 
-    // TODO: currently the deadline is counted in cycles.
-    // We need to retrieve the clock frequency if we want a chance at specifying
-    // them in us instead
     #[task(deadline_us = 4_000_000, binds = TC5, shared = [x])]
     pub struct ShortTimerTask {}
 
@@ -132,9 +126,6 @@ mod app {
         }
     }
 
-    // TODO: currently the deadline is counted in cycles.
-    // We need to retrieve the clock frequency if we want a chance at specifying
-    // them in us instead
     #[task(deadline_us = 8_000_000, binds = TC4, shared = [x])]
     pub struct LongTimerTask {}
 
@@ -0,0 +1,19 @@
+#![no_main]
+#![no_std]
+
+use defmt_rtt as _;
+use panic_probe as _;
+
+// Sweep the utilization and seed to compare the analytic verdict, printed at
+// build time, with the deadline misses observed on hardware. The 8 tasks share
+// the 7 deadline classes which fit the ARMv7-M priority levels.
+benchmark_generator::generate_benchmark_app!(random(
+    tasks = 8,
+    utilization = 0.9,
+    seed = 1,
+    min_period_us = 1_000,
+    max_period_us = 50_000,
+    min_deadline_ratio = 0.6,
+    duration_us = 2_000_000,
+    deadlines = 7,
+));
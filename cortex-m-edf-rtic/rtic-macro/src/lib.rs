@@ -9,17 +9,30 @@ struct AtsamdEdfRtic;
 
 // TODO: this should probably take into account the NVIC prio bits somehow?
 const MIN_TASK_PRIORITY: u16 = 1;
+#[cfg(not(feature = "thumbv6-backend"))]
 const MAX_TASK_PRIORITY: u16 = 8;
+// ARMv6-M cores only implement 2 priority bits
+#[cfg(feature = "thumbv6-backend")]
+const MAX_TASK_PRIORITY: u16 = 4;
 
 #[proc_macro_attribute]
 pub fn app(args: TokenStream, input: TokenStream) -> TokenStream {
     let mut builder = RticMacroBuilder::new(AtsamdEdfRtic);
-    let edf_pass = EdfPass::new(MIN_TASK_PRIORITY, MAX_TASK_PRIORITY);
+    let edf_pass = EdfPass::new(MIN_TASK_PRIORITY, MAX_TASK_PRIORITY, default_time_source());
 
     builder.bind_pre_core_pass(edf_pass);
     builder.build_rtic_macro(args, input)
 }
 
+/// ARMv6-M cores don't have a DWT cycle counter
+fn default_time_source() -> syn::Path {
+    if cfg!(feature = "thumbv6-backend") {
+        parse_quote!(::cortex_m_edf_rtic::monotonic::Systick)
+    } else {
+        parse_quote!(::cortex_m_edf_rtic::monotonic::Dwt)
+    }
+}
+
 // ========== Trait implementations ==========
 impl CorePassBackend for AtsamdEdfRtic {
     fn default_task_priority(&self) -> u16 {
@@ -48,21 +61,14 @@ impl CorePassBackend for AtsamdEdfRtic {
                 }
             });
 
-        let start_dwt_cycle_counter = quote! {
-            let (mut dwt, dcb) =  {
-                let core = cortex_m::peripheral::Peripherals::steal();
-                (core.DWT, core.DCB)
-            };
-
-            cortex_m::peripheral::DWT::unlock();
-            dcb.demcr.modify(|r| r | (1 << 24));
-            dwt.set_cycle_count(0);
-            dwt.enable_cycle_counter();
+        let start_time_source = quote! {
+            <EdfTimeSource as ::rtic_edf_pass::time::TimeSource>::start();
+            <NvicScheduler as ::rtic_edf_pass::scheduler::Scheduler<EDF_RUN_QUEUE_LEN, EDF_WAIT_QUEUE_LEN>>::start_budget_timer();
         };
 
         Some(quote! {
             unsafe {
-                #start_dwt_cycle_counter
+                #start_time_source
                 #(#initialize_dispatcher_interrupts)*
             }
 
@@ -70,7 +76,7 @@ impl CorePassBackend for AtsamdEdfRtic {
     }
     fn populate_idle_loop(&self) -> Option<TokenStream2> {
         Some(quote! {
-            unsafe { core::arch::asm!("wfi" ); }
+            idle_sleep(|_| ::cortex_m_edf_rtic::export::wfi());
         })
     }
     fn generate_interrupt_free_fn(&self, mut empty_body_fn: ItemFn) -> ItemFn {
@@ -102,10 +108,29 @@ impl CorePassBackend for AtsamdEdfRtic {
         &self,
         app_args: &AppArgs,
         app_info: &SubApp,
-        _app_analysis: &SubAnalysis,
+        app_analysis: &SubAnalysis,
     ) -> Option<TokenStream2> {
         let peripheral_crate = &app_args.pacs[app_info.core as usize];
 
+        let source_masks = if cfg!(feature = "thumbv6-backend") {
+            let set_masks = app_analysis.used_irqs.iter().map(|(irq_name, priority)| {
+                quote! {
+                    masks[#priority as usize - 1] |= 1 << (#peripheral_crate::Interrupt::#irq_name as u32);
+                }
+            });
+
+            quote! {
+                // interrupt sources bound at each logical priority, used by the source masking lock
+                const EDF_SOURCE_MASKS: [u32; 1 << NVIC_PRIO_BITS] = {
+                    let mut masks = [0u32; 1 << NVIC_PRIO_BITS];
+                    #(#set_masks)*
+                    masks
+                };
+            }
+        } else {
+            quote! {}
+        };
+
         // define only once
         if app_info.core == 0 {
             Some(quote! {
@@ -113,6 +138,7 @@ impl CorePassBackend for AtsamdEdfRtic {
                 static mut OLD_CS: bool = false;
                 static mut CS: bool = false;
                 use #peripheral_crate::NVIC_PRIO_BITS;
+                #source_masks
             })
         } else {
             None
@@ -124,9 +150,17 @@ impl CorePassBackend for AtsamdEdfRtic {
         _app_info: &SubApp,
         incomplete_lock_fn: syn::ImplItemFn,
     ) -> syn::ImplItemFn {
-        let lock_impl: syn::Block = parse_quote! {
-            {
-                unsafe { ::cortex_m_edf_rtic::export::lock(resource_ptr, CEILING as u8, NVIC_PRIO_BITS, f); }
+        let lock_impl: syn::Block = if cfg!(feature = "thumbv6-backend") {
+            parse_quote! {
+                {
+                    unsafe { ::cortex_m_edf_rtic::export::lock(resource_ptr, CEILING as u8, &EDF_SOURCE_MASKS, f); }
+                }
+            }
+        } else {
+            parse_quote! {
+                {
+                    unsafe { ::cortex_m_edf_rtic::export::lock(resource_ptr, CEILING as u8, NVIC_PRIO_BITS, f); }
+                }
             }
         };
 
@@ -1,3 +1,4 @@
+#[cfg(not(feature = "thumbv6-backend"))]
 use cortex_m::register::{basepri, basepri_max};
 pub use cortex_m::{
     Peripherals,
@@ -19,6 +20,7 @@ pub const fn cortex_logical2hw(logical: u8, nvic_prio_bits: u8) -> u8 {
 }
 
 // TODO: need to think how to abstract this
+#[cfg(not(feature = "thumbv6-backend"))]
 #[inline(always)]
 pub fn run<F>(priority: u8, f: F)
 where
@@ -68,6 +70,7 @@ where
 /// even in some edge cases where this may be omitted.
 /// Total OH of per task is max 2 clock cycles, negligible in practice
 /// but can in theory be fixed.
+#[cfg(not(feature = "thumbv6-backend"))]
 #[inline(always)]
 pub unsafe fn lock<T, R>(
     ptr: *mut T,
@@ -87,3 +90,74 @@ pub unsafe fn lock<T, R>(
         }
     }
 }
+
+/// Without BASEPRI, the interrupted priority doesn't need to be restored:
+/// locks restore the interrupt sources they masked themselves.
+#[cfg(feature = "thumbv6-backend")]
+#[inline(always)]
+pub fn run<F>(_priority: u8, f: F)
+where
+    F: FnOnce(),
+{
+    f();
+}
+
+/// Lock implementation using interrupt source masking, for cores without
+/// BASEPRI (ARMv6-M).
+///
+/// # Safety
+///
+/// The system ceiling is raised from current to ceiling by either
+/// - masking (through the NVIC ICER register) every interrupt source whose
+///   priority is lower than or equal to the ceiling, or
+/// - disable all interrupts in case we want to mask interrupts with maximum
+///   priority
+///
+/// `masks[p - 1]` must hold the set of interrupt sources bound at logical
+/// priority `p`. ARMv6-M supports at most 32 external interrupts, so a single
+/// word is enough to hold a mask.
+///
+/// Only the sources which were enabled when the lock was taken are re-enabled
+/// when it is released, so this composes with the scheduler masking the
+/// timestamper interrupts.
+#[cfg(feature = "thumbv6-backend")]
+#[inline(always)]
+pub unsafe fn lock<T, R, const N: usize>(
+    ptr: *mut T,
+    ceiling: u8,
+    masks: &[u32; N],
+    f: impl FnOnce(&mut T) -> R,
+) -> R {
+    if ceiling as usize >= N {
+        cortex_m::interrupt::free(|_| unsafe { f(&mut *ptr) })
+    } else {
+        let mask = compute_mask(ceiling, masks);
+        // SAFETY: ISER/ICER are write-1-to-set/clear registers, so only the sources in
+        // `mask` are affected
+        let nvic = unsafe { &*cortex_m::peripheral::NVIC::PTR };
+
+        let enabled = nvic.iser[0].read() & mask;
+        unsafe { nvic.icer[0].write(mask) };
+        cortex_m::asm::dsb();
+        cortex_m::asm::isb();
+
+        let r = unsafe { f(&mut *ptr) };
+
+        unsafe { nvic.iser[0].write(enabled) };
+        r
+    }
+}
+
+/// Returns the set of interrupt sources with a logical priority lower than or
+/// equal to `ceiling`
+#[cfg(feature = "thumbv6-backend")]
+#[inline(always)]
+const fn compute_mask<const N: usize>(ceiling: u8, masks: &[u32; N]) -> u32 {
+    let mut mask = 0;
+    let mut i = 0;
+    while i < ceiling as usize && i < N {
+        mask |= masks[i];
+        i += 1;
+    }
+    mask
+}
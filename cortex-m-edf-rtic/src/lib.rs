@@ -1,5 +1,6 @@
 #![no_std]
 
 pub mod export;
+pub mod monotonic;
 
 pub use rtic_macro::app;
@@ -0,0 +1,332 @@
+//! Time sources which can be selected through the `monotonic` app argument.
+
+use core::{
+    marker::PhantomData,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+#[cfg(not(feature = "thumbv6-backend"))]
+use cortex_m::peripheral::DWT;
+use cortex_m::peripheral::{SCB, SYST, syst::SystClkSource};
+#[cfg(not(feature = "thumbv6-backend"))]
+use rtic_edf_pass::time::BudgetTimer;
+use rtic_edf_pass::{time::TimeSource, types::Timestamp};
+
+/// Time source based on the DWT cycle counter, ticking at the core clock
+/// frequency `CPU_HZ`.
+///
+/// This is the default time source, except with the `thumbv6-backend` feature
+/// where [`Systick`] is used instead. Manually resetting the CYCCNT register
+/// will mess up the scheduling. Note that CYCCNT stops counting while the core
+/// is sleeping on some devices, see [`CompensatedDwt`]. Not available on
+/// ARMv6-M.
+#[cfg(not(feature = "thumbv6-backend"))]
+pub struct Dwt<const CPU_HZ: u32>;
+
+#[cfg(not(feature = "thumbv6-backend"))]
+impl<const CPU_HZ: u32> TimeSource for Dwt<CPU_HZ> {
+    const TICK_HZ: u32 = CPU_HZ;
+
+    unsafe fn start() {
+        let (mut dwt, dcb) = {
+            let core = unsafe { cortex_m::Peripherals::steal() };
+            (core.DWT, core.DCB)
+        };
+
+        DWT::unlock();
+        unsafe { dcb.demcr.modify(|r| r | (1 << 24)) };
+        dwt.set_cycle_count(0);
+        dwt.enable_cycle_counter();
+    }
+
+    #[inline]
+    fn now() -> Timestamp {
+        DWT::cycle_count()
+    }
+}
+
+/// Time spent with the cycle counter stopped, in cycles
+#[cfg(not(feature = "thumbv6-backend"))]
+static DWT_SLEEP_OFFSET: AtomicU32 = AtomicU32::new(0);
+
+/// Time source based on the DWT cycle counter like [`Dwt`], compensated for
+/// the time the core spends in sleep modes where the cycle counter stops.
+///
+/// The sleep duration must be measured by a clock which keeps running in
+/// sleep, such as an RTC, and accounted for with [`compensate`] from the idle
+/// hook, before it returns:
+///
+/// ```ignore
+/// app::idle_sleep(|hint| {
+///     let before = CompensatedDwt::<CPU_HZ>::now();
+///     let rtc_before = rtc.count();
+///     enter_stop_mode(hint);
+///     let slept = rtc.count().wrapping_sub(rtc_before);
+///     CompensatedDwt::<CPU_HZ>::compensate(before, convert_ticks(slept, RTC_HZ, CPU_HZ));
+/// });
+/// ```
+///
+/// [`compensate`]: CompensatedDwt::compensate
+#[cfg(not(feature = "thumbv6-backend"))]
+pub struct CompensatedDwt<const CPU_HZ: u32>;
+
+#[cfg(not(feature = "thumbv6-backend"))]
+impl<const CPU_HZ: u32> CompensatedDwt<CPU_HZ> {
+    /// Account for a sleep period which started at `before` and lasted
+    /// `slept` cycles. Only the part of the sleep during which the cycle
+    /// counter was stopped is added, so that the time source keeps running
+    /// whether or not the cycle counter stops in the sleep mode used.
+    ///
+    /// Must be called with interrupts disabled, as done by the idle hook.
+    pub fn compensate(before: Timestamp, slept: u32) {
+        let counted = Self::now().wrapping_sub(before);
+        let offset = DWT_SLEEP_OFFSET.load(Ordering::Relaxed);
+        DWT_SLEEP_OFFSET.store(
+            offset.wrapping_add(slept.saturating_sub(counted)),
+            Ordering::Relaxed,
+        );
+    }
+}
+
+#[cfg(not(feature = "thumbv6-backend"))]
+impl<const CPU_HZ: u32> TimeSource for CompensatedDwt<CPU_HZ> {
+    const TICK_HZ: u32 = CPU_HZ;
+
+    unsafe fn start() {
+        unsafe { <Dwt<CPU_HZ> as TimeSource>::start() }
+    }
+
+    #[inline]
+    fn now() -> Timestamp {
+        DWT::cycle_count().wrapping_add(DWT_SLEEP_OFFSET.load(Ordering::Relaxed))
+    }
+}
+
+/// Convert a duration measured by a clock ticking at `from_hz` into ticks of a
+/// clock ticking at `to_hz`, for example an RTC sleep duration into CPU cycles
+#[inline]
+pub const fn convert_ticks(ticks: u32, from_hz: u32, to_hz: u32) -> u32 {
+    let converted = ticks as u64 * to_hz as u64 / from_hz as u64;
+    if converted > u32::MAX as u64 {
+        u32::MAX
+    } else {
+        converted as u32
+    }
+}
+
+/// DEMCR: enable the DebugMonitor exception
+#[cfg(not(feature = "thumbv6-backend"))]
+const DEMCR_MON_EN: u32 = 1 << 16;
+/// DEMCR: pend the DebugMonitor exception
+#[cfg(not(feature = "thumbv6-backend"))]
+const DEMCR_MON_PEND: u32 = 1 << 17;
+/// DWT_FUNCTION: compare against CYCCNT (CYCMATCH), and generate a debug event
+#[cfg(not(feature = "thumbv6-backend"))]
+const DWT_FUNCTION_CYCCNT_DEBUG_EVENT: u32 = (1 << 7) | 0b0100;
+
+/// Budget timer based on the first DWT comparator, matching the cycle counter
+/// of the [`Dwt`] time source. It must be used with that time source, and not
+/// with [`CompensatedDwt`], whose timestamps are offset from the cycle counter.
+///
+/// An expiry raises the DebugMonitor exception, whose handler must call the
+/// generated `on_budget_timer()` function:
+///
+/// ```ignore
+/// #[cortex_m_rt::exception]
+/// fn DebugMonitor() {
+///     app::on_budget_timer();
+/// }
+/// ```
+///
+/// DebugMonitor has the highest configurable priority (0) out of reset. It
+/// isn't raised while a debugger has enabled halting debug, and the first DWT
+/// comparator is no longer available for watchpoints. Not available on
+/// ARMv6-M.
+#[cfg(not(feature = "thumbv6-backend"))]
+pub struct DwtBudget;
+
+#[cfg(not(feature = "thumbv6-backend"))]
+impl BudgetTimer for DwtBudget {
+    unsafe fn start() {
+        let dcb = unsafe { cortex_m::Peripherals::steal() }.DCB;
+        unsafe { dcb.demcr.modify(|r| r | DEMCR_MON_EN) };
+    }
+
+    #[inline]
+    fn arm(expiry: Timestamp) {
+        // SAFETY: the comparator is reserved to the budget timer, and only armed by the
+        // scheduler inside critical sections
+        let (dwt, dcb) = unsafe { (&*DWT::PTR, &*cortex_m::peripheral::DCB::PTR) };
+        unsafe {
+            dwt.c[0].comp.write(expiry);
+            dwt.c[0].mask.write(0);
+            dwt.c[0].function.write(DWT_FUNCTION_CYCCNT_DEBUG_EVENT);
+        }
+
+        // The comparator only matches the exact value of the cycle counter. The
+        // expiry may be past the next wrap of the counter.
+        if DWT::cycle_count().wrapping_sub(expiry) as i32 >= 0 {
+            unsafe { dcb.demcr.modify(|r| r | DEMCR_MON_PEND) };
+        }
+    }
+
+    #[inline]
+    fn disarm() {
+        unsafe { (*DWT::PTR).c[0].function.write(0) };
+    }
+}
+
+const SYST_BITS: u32 = 24;
+const SYST_RELOAD: u32 = (1 << SYST_BITS) - 1;
+
+static SYST_OVERFLOWS: AtomicU32 = AtomicU32::new(0);
+
+/// Time source based on the 24-bit SysTick timer clocked from the core clock
+/// (`CPU_HZ`), extended to 32 bits by counting its overflows in software.
+///
+/// Unlike [`Dwt`], this is available on every Cortex-M core. Timestamps are 32
+/// bits wide, and absolute deadlines must not wrap around: the scheduler
+/// panics with "Deadline overflowed" once a deadline passes 2^32 ticks, i.e.
+/// after about 89 seconds at 48 MHz.
+///
+/// The SysTick exception handler must call [`Systick::on_interrupt`]:
+///
+/// ```ignore
+/// #[cortex_m_rt::exception]
+/// fn SysTick() {
+///     cortex_m_edf_rtic::monotonic::Systick::<CPU_HZ>::on_interrupt();
+/// }
+/// ```
+pub struct Systick<const CPU_HZ: u32>;
+
+impl<const CPU_HZ: u32> Systick<CPU_HZ> {
+    /// Account for a SysTick overflow. Must be called from the SysTick
+    /// exception handler.
+    #[inline]
+    pub fn on_interrupt() {
+        // The counter is only ever written from the SysTick exception, so a plain
+        // load/store pair is enough (and available on ARMv6-M)
+        let overflows = SYST_OVERFLOWS.load(Ordering::Relaxed);
+        SYST_OVERFLOWS.store(overflows.wrapping_add(1), Ordering::Relaxed);
+    }
+}
+
+impl<const CPU_HZ: u32> TimeSource for Systick<CPU_HZ> {
+    const TICK_HZ: u32 = CPU_HZ;
+
+    unsafe fn start() {
+        let mut syst = unsafe { cortex_m::Peripherals::steal() }.SYST;
+
+        syst.set_clock_source(SystClkSource::Core);
+        syst.set_reload(SYST_RELOAD);
+        syst.clear_current();
+        syst.enable_interrupt();
+        syst.enable_counter();
+    }
+
+    #[inline]
+    fn now() -> Timestamp {
+        cortex_m::interrupt::free(|_| {
+            let overflows = SYST_OVERFLOWS.load(Ordering::Relaxed);
+            let elapsed = SYST_RELOAD - SYST::get_current();
+
+            // The counter may have wrapped before the SysTick exception got a chance to
+            // run. In that case, read it again now that we know it won't wrap for a
+            // while.
+            if SCB::is_pendst_pending() {
+                extend(
+                    overflows.wrapping_add(1),
+                    SYST_RELOAD - SYST::get_current(),
+                    SYST_BITS,
+                )
+            } else {
+                extend(overflows, elapsed, SYST_BITS)
+            }
+        })
+    }
+}
+
+/// A free-running hardware up-counter with an overflow interrupt, for use with
+/// [`HwTimer`].
+pub trait HwCounter {
+    /// Counter frequency, in Hz
+    const TICK_HZ: u32;
+
+    /// Width of the counter, in bits (at most 32)
+    const BITS: u32;
+
+    /// Configure and start the counter, and enable its overflow interrupt.
+    ///
+    /// # Safety
+    ///
+    /// Called once by [`TimeSource::start`].
+    unsafe fn start();
+
+    /// Returns the current value of the counter
+    fn count() -> u32;
+
+    /// Returns `true` if the counter has overflowed but its overflow interrupt
+    /// hasn't been serviced yet
+    fn overflow_pending() -> bool;
+
+    /// Software overflow counter. Every implementor must return its own
+    /// `static`.
+    fn overflows() -> &'static AtomicU32;
+}
+
+/// Time source based on a generic hardware timer, extended to 32 bits by
+/// counting its overflows in software.
+///
+/// Like with [`Systick`], absolute deadlines must not wrap around: the
+/// scheduler panics with "Deadline overflowed" once a deadline passes 2^32
+/// ticks, i.e. after about 72 minutes at 1 MHz. Also, deadlines declared in
+/// microseconds are rounded down to ticks, so with a slow counter, tasks must
+/// keep distinct deadlines in ticks for every priority level; this is checked
+/// at compile time.
+///
+/// The counter's overflow interrupt handler must clear the interrupt flag, then
+/// call [`HwTimer::on_interrupt`]. It must run often enough that no more than
+/// one overflow can happen while it is pending.
+pub struct HwTimer<C>(PhantomData<C>);
+
+impl<C: HwCounter> HwTimer<C> {
+    /// Account for a counter overflow. Must be called from the counter's
+    /// overflow interrupt handler.
+    #[inline]
+    pub fn on_interrupt() {
+        let overflows = C::overflows().load(Ordering::Relaxed);
+        C::overflows().store(overflows.wrapping_add(1), Ordering::Relaxed);
+    }
+}
+
+impl<C: HwCounter> TimeSource for HwTimer<C> {
+    const TICK_HZ: u32 = C::TICK_HZ;
+
+    unsafe fn start() {
+        unsafe { C::start() }
+    }
+
+    #[inline]
+    fn now() -> Timestamp {
+        if C::BITS >= 32 {
+            return C::count();
+        }
+
+        cortex_m::interrupt::free(|_| {
+            let overflows = C::overflows().load(Ordering::Relaxed);
+            let count = C::count();
+
+            if C::overflow_pending() {
+                extend(overflows.wrapping_add(1), C::count(), C::BITS)
+            } else {
+                extend(overflows, count, C::BITS)
+            }
+        })
+    }
+}
+
+/// Combine a software overflow counter with a `bits` wide hardware counter
+#[inline(always)]
+fn extend(overflows: u32, count: u32, bits: u32) -> Timestamp {
+    (overflows << bits) | count
+}
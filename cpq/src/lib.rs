@@ -319,6 +319,65 @@ impl<T: PartialOrd, const N: usize> PriorityQueue<T, N> {
         }
     }
 
+    /// Remove every element for which `f` returns `true`, and return the
+    /// number of removed elements.
+    ///
+    /// A [`pop`](PriorityQueue::pop) preempted by this call returns `None`, as
+    /// if it had been stolen by a preempting pop: the caller must then pop the
+    /// queue in its place.
+    pub fn remove_if(&self, mut f: impl FnMut(&T) -> bool) -> usize {
+        // Entire node-swapping must be performed atomically
+        critical_section::with(|_| unsafe {
+            let mut removed = 0;
+            let mut prev = None;
+            let mut cursor = self.get_head_ptr();
+
+            while let Some(idx) = cursor {
+                let next = *self.next_at(idx);
+
+                if f(self.peek_at(idx)) {
+                    (*self.node_at(idx)).value.assume_init_drop();
+
+                    // Unlink the node, and move it into the free list
+                    match prev {
+                        Some(prev) => *self.next_at(prev) = next,
+                        None => self.set_head_ptr(next),
+                    }
+                    *self.next_at(idx) = self.get_free_ptr();
+                    self.set_free_ptr(Some(idx));
+
+                    removed += 1;
+                } else {
+                    prev = Some(idx);
+                }
+
+                cursor = next;
+            }
+
+            if removed == 0 {
+                return 0;
+            }
+
+            self.set_tail_ptr(prev);
+
+            // Update the cached queue minimum
+            let mut min: Option<NodePtr> = None;
+            let mut cursor = self.get_head_ptr();
+            while let Some(idx) = cursor {
+                if min.is_none_or(|min| self.peek_at(idx) < self.peek_at(min)) {
+                    min = Some(idx);
+                }
+                cursor = *self.next_at(idx);
+            }
+            self.set_min_ptr(min);
+
+            // The cursors of an ongoing pop may point to removed nodes
+            *self.traversal_state.get() = None;
+
+            removed
+        })
+    }
+
     #[inline]
     pub fn pop(&self) -> Option<T> {
         unsafe {
@@ -446,3 +446,56 @@ fn len_method_works_correctly() {
         assert_eq!(pq.len(cs), 0);
     });
 }
+
+#[test]
+fn remove_if() {
+    let mut pq = PriorityQueue::<i32, 6>::new();
+
+    for value in [5, 1, 8, 3, 1, 9] {
+        pq.insert(value).unwrap();
+    }
+
+    // Nothing to remove
+    assert_eq!(pq.remove_if(|v| *v > 100), 0);
+
+    // Remove the head, the minimums, and the tail
+    assert_eq!(pq.remove_if(|v| *v == 5 || *v == 1 || *v == 9), 4);
+    assert_eq!(pq.min(), Some(3));
+    assert_eq!(*pq.head_ptr.get_mut(), Some(2));
+    assert_tail(&mut pq, 3);
+
+    // The freed nodes can be reused
+    for value in [7, 6, 2, 4] {
+        pq.insert(value).unwrap();
+    }
+    assert_eq!(pq.insert(0), Err(crate::Error::QueueFull));
+    assert_eq!(pq.min(), Some(2));
+
+    critical_section::with(|cs| assert_eq!(pq.len(cs), 6));
+    for value in [2, 3, 4, 6, 7, 8] {
+        assert_eq!(pq.pop(), Some(value));
+    }
+    assert_eq!(pq.pop(), None);
+
+    // An ongoing pop is interrupted, and the next one starts over
+    pq.insert(1).unwrap();
+    pq.insert(2).unwrap();
+    *pq.traversal_state.get_mut() = Some(crate::TraversalState {
+        min_ptr: 0,
+        second_min_ptr: 0,
+        prev_cursor: 0,
+        cursor: 0,
+        min_predecessor: 0,
+    });
+    assert_eq!(pq.remove_if(|v| *v == 1), 1);
+    assert!(pq.traversal_state.get_mut().is_none());
+    assert_eq!(pq.pop(), Some(2));
+
+    // Remove every element
+    pq.insert(1).unwrap();
+    pq.insert(2).unwrap();
+    assert_eq!(pq.remove_if(|_| true), 2);
+    assert_eq!(pq.min(), None);
+    assert_eq!(*pq.head_ptr.get_mut(), None);
+    assert_eq!(*pq.tail_ptr.get_mut(), None);
+}
@@ -0,0 +1,125 @@
+//! Export to the Common Trace Format (CTF 1.8), which can be read by
+//! Babeltrace and Trace Compass.
+//!
+//! A CTF trace is a directory holding a `metadata` file, which describes the
+//! binary layout of the events in TSDL, and a binary `stream` file. Each event
+//! of the stream holds its kind (`uint8`), its timestamp extended to 64 bits
+//! (`uint64`, in ticks of the time source) and the task's dispatcher index
+//! (`uint16`), all little-endian and packed.
+
+use std::{fs, io, path::Path};
+
+use crate::{Clock, Event, EventKind};
+
+/// Every event kind, in the order of their ids
+const EVENT_KINDS: [EventKind; 7] = [
+    EventKind::Arrival,
+    EventKind::Enqueue,
+    EventKind::DirectExecute,
+    EventKind::Dispatch,
+    EventKind::Complete,
+    EventKind::Miss,
+    EventKind::Cancel,
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CtfTrace {
+    /// TSDL metadata
+    pub metadata: String,
+    /// Binary event stream
+    pub stream: Vec<u8>,
+}
+
+impl CtfTrace {
+    pub fn new(events: &[Event], tick_hz: u32) -> Self {
+        let mut clock = Clock::default();
+        let mut stream = Vec::with_capacity(events.len() * 11);
+
+        for event in events {
+            stream.push(event.kind as u8);
+            stream.extend(clock.extend(event.timestamp).to_le_bytes());
+            stream.extend(event.task.to_le_bytes());
+        }
+
+        Self {
+            metadata: metadata(tick_hz),
+            stream,
+        }
+    }
+
+    /// Write the trace into `dir`, which is created if needed
+    pub fn write_to(&self, dir: &Path) -> io::Result<()> {
+        fs::create_dir_all(dir)?;
+        fs::write(dir.join("metadata"), &self.metadata)?;
+        fs::write(dir.join("stream"), &self.stream)
+    }
+}
+
+/// Name of an event kind in the trace
+pub fn event_name(kind: EventKind) -> &'static str {
+    match kind {
+        EventKind::Arrival => "arrival",
+        EventKind::Enqueue => "enqueue",
+        EventKind::DirectExecute => "direct_execute",
+        EventKind::Dispatch => "dispatch",
+        EventKind::Complete => "complete",
+        EventKind::Miss => "deadline_miss",
+        EventKind::Cancel => "cancel",
+    }
+}
+
+fn metadata(tick_hz: u32) -> String {
+    let mut metadata = format!(
+        r#"/* CTF 1.8 */
+
+typealias integer {{ size = 8; align = 8; signed = false; }} := uint8_t;
+typealias integer {{ size = 16; align = 8; signed = false; }} := uint16_t;
+
+trace {{
+    major = 1;
+    minor = 8;
+    byte_order = le;
+}};
+
+env {{
+    domain = "rtic-edf";
+}};
+
+clock {{
+    name = monotonic;
+    freq = {tick_hz};
+    offset = 0;
+}};
+
+typealias integer {{
+    size = 64; align = 8; signed = false;
+    map = clock.monotonic.value;
+}} := uint64_clock_monotonic_t;
+
+stream {{
+    event.header := struct {{
+        uint8_t id;
+        uint64_clock_monotonic_t timestamp;
+    }};
+}};
+"#
+    );
+
+    for kind in EVENT_KINDS {
+        metadata.push_str(&format!(
+            r#"
+event {{
+    name = "{}";
+    id = {};
+    fields := struct {{
+        uint16_t task;
+    }};
+}};
+"#,
+            event_name(kind),
+            kind as u8,
+        ));
+    }
+
+    metadata
+}
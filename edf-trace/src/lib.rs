@@ -0,0 +1,284 @@
+//! Host-side decoder for the binary scheduling trace recorded with the `trace`
+//! feature of `rtic-edf-pass`.
+//!
+//! The raw event stream is decoded into a [`Timeline`] of jobs, which can be
+//! printed or exported to the Chrome trace event format (which Perfetto also
+//! opens). The events themselves can be exported to the Common Trace Format
+//! with [`ctf::CtfTrace`].
+
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+};
+
+pub use rtic_edf_pass::scheduler::trace::{Event, EventKind};
+use serde_json::{Value, json};
+
+pub mod ctf;
+
+/// Decode a raw event stream. Events with an unknown kind, and a trailing
+/// partial event, are skipped.
+pub fn decode(bytes: &[u8]) -> Vec<Event> {
+    bytes
+        .chunks_exact(Event::SIZE)
+        .filter_map(|chunk| Event::decode(chunk.try_into().unwrap()))
+        .collect()
+}
+
+/// Returns the name of every task, indexed by dispatcher index, from the JSON
+/// schedule report written by the EDF pass.
+pub fn task_names_from_report(report: &str) -> Result<HashMap<u16, String>, serde_json::Error> {
+    let report: Value = serde_json::from_str(report)?;
+
+    Ok(report["tasks"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|task| {
+            let idx = task["dispatcher_idx"].as_u64()?.try_into().ok()?;
+            let name = task["name"].as_str()?.to_owned();
+            Some((idx, name))
+        })
+        .collect())
+}
+
+/// A single job, from its release to its completion. Times are expressed in
+/// ticks of the time source, and extended to 64 bits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Job {
+    /// Dispatcher index of the task
+    pub task: u16,
+    pub arrival: u64,
+    /// `true` if the job had to wait in the wait queue
+    pub enqueued: bool,
+    /// When the dispatcher started running the job
+    pub dispatch: Option<u64>,
+    pub completion: Option<u64>,
+    pub missed_deadline: bool,
+    /// When the job was cancelled, while waiting in the wait queue
+    pub cancellation: Option<u64>,
+}
+
+/// Jobs reconstructed from an event stream, in order of arrival
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Timeline {
+    pub jobs: Vec<Job>,
+    /// Frequency of the time source
+    pub tick_hz: u32,
+}
+
+impl Timeline {
+    pub fn new(events: &[Event], tick_hz: u32) -> Self {
+        let mut jobs: Vec<Job> = Vec::new();
+        // Indices of each task's in-flight jobs, in order of arrival. Jobs of a task
+        // can overlap, but run one after the other in that order, like the
+        // scheduler's `task::Arrivals`.
+        let mut in_flight: HashMap<u16, VecDeque<usize>> = HashMap::new();
+        let mut last_completed: HashMap<u16, usize> = HashMap::new();
+
+        let mut clock = Clock::default();
+
+        for event in events {
+            let ts = clock.extend(event.timestamp);
+            let task = event.task;
+
+            match event.kind {
+                EventKind::Arrival => {
+                    in_flight.entry(task).or_default().push_back(jobs.len());
+                    jobs.push(Job {
+                        task,
+                        arrival: ts,
+                        enqueued: false,
+                        dispatch: None,
+                        completion: None,
+                        missed_deadline: false,
+                        cancellation: None,
+                    });
+                }
+                // Only the job which just arrived can be enqueued
+                EventKind::Enqueue => {
+                    if let Some(&idx) = in_flight.get(&task).and_then(VecDeque::back) {
+                        jobs[idx].enqueued = true;
+                    }
+                }
+                // Handing the job over to its dispatcher doesn't change its state
+                EventKind::DirectExecute => {}
+                EventKind::Dispatch => {
+                    if let Some(&idx) = in_flight.get(&task).and_then(VecDeque::front) {
+                        jobs[idx].dispatch = Some(ts);
+                    }
+                }
+                EventKind::Complete => {
+                    if let Some(idx) = in_flight.get_mut(&task).and_then(VecDeque::pop_front) {
+                        jobs[idx].completion = Some(ts);
+                        last_completed.insert(task, idx);
+                    }
+                }
+                EventKind::Miss => {
+                    if let Some(&idx) = last_completed.get(&task) {
+                        jobs[idx].missed_deadline = true;
+                    }
+                }
+                // Only the jobs waiting in the wait queue can be cancelled, which are the
+                // most recent ones
+                EventKind::Cancel => {
+                    if let Some(idx) = in_flight.get_mut(&task).and_then(VecDeque::pop_back) {
+                        jobs[idx].cancellation = Some(ts);
+                    }
+                }
+            }
+        }
+
+        Self { jobs, tick_hz }
+    }
+
+    /// Convert ticks to microseconds
+    pub fn ticks_to_us(&self, ticks: u64) -> f64 {
+        ticks as f64 * 1e6 / self.tick_hz as f64
+    }
+
+    /// Export the timeline to the Chrome trace event format. Each task gets
+    /// its own track, named after `names` if it contains the task.
+    ///
+    /// Execution slices span from dispatch to completion, and therefore
+    /// include the time during which the job was preempted.
+    pub fn to_chrome_trace(&self, names: &HashMap<u16, String>) -> Value {
+        let name = |task: u16| {
+            names
+                .get(&task)
+                .cloned()
+                .unwrap_or_else(|| format!("task {task}"))
+        };
+
+        let mut tasks: Vec<_> = self.jobs.iter().map(|j| j.task).collect();
+        tasks.sort_unstable();
+        tasks.dedup();
+
+        let mut events: Vec<Value> = tasks
+            .iter()
+            .map(|&task| {
+                json!({
+                    "name": "thread_name",
+                    "ph": "M",
+                    "pid": 0,
+                    "tid": task,
+                    "args": { "name": name(task) },
+                })
+            })
+            .collect();
+
+        for job in self.jobs.iter() {
+            events.push(json!({
+                "name": "arrival",
+                "ph": "i",
+                "s": "t",
+                "ts": self.ticks_to_us(job.arrival),
+                "pid": 0,
+                "tid": job.task,
+            }));
+
+            if let (Some(dispatch), Some(completion)) = (job.dispatch, job.completion) {
+                events.push(json!({
+                    "name": name(job.task),
+                    "ph": "X",
+                    "ts": self.ticks_to_us(dispatch),
+                    "dur": self.ticks_to_us(completion.saturating_sub(dispatch)),
+                    "pid": 0,
+                    "tid": job.task,
+                    "args": {
+                        "enqueued": job.enqueued,
+                        "response_time_us": self.ticks_to_us(completion.saturating_sub(job.arrival)),
+                    },
+                }));
+            }
+
+            if job.missed_deadline {
+                events.push(json!({
+                    "name": "deadline miss",
+                    "ph": "i",
+                    "s": "t",
+                    "ts": self.ticks_to_us(job.completion.unwrap_or(job.arrival)),
+                    "pid": 0,
+                    "tid": job.task,
+                }));
+            }
+
+            if let Some(cancellation) = job.cancellation {
+                events.push(json!({
+                    "name": "cancel",
+                    "ph": "i",
+                    "s": "t",
+                    "ts": self.ticks_to_us(cancellation),
+                    "pid": 0,
+                    "tid": job.task,
+                }));
+            }
+        }
+
+        json!({
+            "traceEvents": events,
+            "displayTimeUnit": "ns",
+        })
+    }
+}
+
+impl fmt::Display for Timeline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let fmt_time = |t: Option<u64>| match t {
+            Some(t) => format!("{:.1}", self.ticks_to_us(t)),
+            None => "-".into(),
+        };
+
+        writeln!(
+            f,
+            "{:>6} {:>12} {:>12} {:>12} {:>9}",
+            "task", "arrival us", "dispatch us", "complete us", "status"
+        )?;
+
+        for job in self.jobs.iter() {
+            let status = match (job.cancellation, job.missed_deadline, job.enqueued) {
+                (Some(_), _, _) => "cancelled",
+                (None, true, _) => "MISSED",
+                (None, false, true) => "enqueued",
+                (None, false, false) => "direct",
+            };
+
+            writeln!(
+                f,
+                "{:>6} {:>12} {:>12} {:>12} {:>9}",
+                job.task,
+                fmt_time(Some(job.arrival)),
+                fmt_time(job.dispatch),
+                fmt_time(job.completion),
+                status,
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Extends 32-bit timestamps to 64 bits, assuming that consecutive events are
+/// less than half the timestamp range apart.
+#[derive(Default)]
+struct Clock {
+    last: Option<u32>,
+    high: u64,
+}
+
+impl Clock {
+    fn extend(&mut self, ts: u32) -> u64 {
+        if let Some(last) = self.last
+            && ts < last
+            && last - ts > u32::MAX / 2
+        {
+            self.high += 1 << 32;
+        }
+
+        self.last = Some(ts);
+        self.high + ts as u64
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,112 @@
+//! Decode a binary scheduling trace and print its timeline.
+//!
+//! ```text
+//! edf-trace <trace.bin> --tick-hz <hz> [--report <schedule.json>] [--chrome <out.json>] [--ctf <dir>]
+//! ```
+//!
+//! `--report` names the tasks after the JSON schedule report written at build
+//! time. `--chrome` exports the timeline in the Chrome trace event format,
+//! which can be opened in Perfetto or `chrome://tracing`. `--ctf` exports the
+//! events as a Common Trace Format trace, which can be opened in Babeltrace or
+//! Trace Compass.
+
+use std::{collections::HashMap, fs, path::Path, process::ExitCode};
+
+use edf_trace::{Timeline, ctf::CtfTrace, decode, task_names_from_report};
+
+const USAGE: &str = "usage: edf-trace <trace.bin> --tick-hz <hz> [--report <schedule.json>] [--chrome <out.json>] [--ctf <dir>]";
+
+struct Args {
+    trace: String,
+    tick_hz: u32,
+    report: Option<String>,
+    chrome: Option<String>,
+    ctf: Option<String>,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = std::env::args().skip(1);
+
+    let mut trace = None;
+    let mut tick_hz = None;
+    let mut report = None;
+    let mut chrome = None;
+    let mut ctf = None;
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("missing value for {arg}"));
+
+        match arg.as_str() {
+            "--tick-hz" => {
+                tick_hz = Some(
+                    value()?
+                        .replace('_', "")
+                        .parse()
+                        .map_err(|e| format!("invalid --tick-hz: {e}"))?,
+                )
+            }
+            "--report" => report = Some(value()?),
+            "--chrome" => chrome = Some(value()?),
+            "--ctf" => ctf = Some(value()?),
+            _ if trace.is_none() && !arg.starts_with("--") => trace = Some(arg),
+            _ => return Err(format!("unexpected argument {arg}")),
+        }
+    }
+
+    Ok(Args {
+        trace: trace.ok_or("missing trace file")?,
+        tick_hz: tick_hz.ok_or("missing --tick-hz")?,
+        report,
+        chrome,
+        ctf,
+    })
+}
+
+fn run(args: Args) -> Result<(), String> {
+    let bytes = fs::read(&args.trace).map_err(|e| format!("failed to read {}: {e}", args.trace))?;
+    let events = decode(&bytes);
+    let timeline = Timeline::new(&events, args.tick_hz);
+
+    let names = match &args.report {
+        Some(path) => {
+            let report =
+                fs::read_to_string(path).map_err(|e| format!("failed to read {path}: {e}"))?;
+            task_names_from_report(&report).map_err(|e| format!("invalid report {path}: {e}"))?
+        }
+        None => HashMap::new(),
+    };
+
+    let mut sorted_names: Vec<_> = names.iter().collect();
+    sorted_names.sort();
+    for (task, name) in sorted_names {
+        println!("task {task}: {name}");
+    }
+    print!("{timeline}");
+
+    if let Some(path) = &args.chrome {
+        let trace = serde_json::to_string(&timeline.to_chrome_trace(&names)).unwrap();
+        fs::write(path, trace).map_err(|e| format!("failed to write {path}: {e}"))?;
+    }
+
+    if let Some(path) = &args.ctf {
+        CtfTrace::new(&events, args.tick_hz)
+            .write_to(Path::new(path))
+            .map_err(|e| format!("failed to write {path}: {e}"))?;
+    }
+
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let result = parse_args()
+        .map_err(|e| format!("{e}\n{USAGE}"))
+        .and_then(run);
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,253 @@
+use std::collections::HashMap;
+
+use rtic_edf_pass::scheduler::trace::TraceBuffer;
+
+use crate::{Event, EventKind, Timeline, decode, task_names_from_report};
+
+fn event(kind: EventKind, task: u16, timestamp: u32) -> Event {
+    Event {
+        kind,
+        task,
+        timestamp,
+    }
+}
+
+fn encode(events: &[Event]) -> Vec<u8> {
+    events.iter().flat_map(|e| e.encode()).collect()
+}
+
+/// Task 0 is released, then preempted by task 1. Task 2 is released while task
+/// 1 is running, and waits in the wait queue.
+fn sample_events() -> Vec<Event> {
+    use EventKind::*;
+
+    vec![
+        event(Arrival, 0, 100),
+        event(DirectExecute, 0, 100),
+        event(Dispatch, 0, 110),
+        event(Arrival, 1, 200),
+        event(DirectExecute, 1, 200),
+        event(Dispatch, 1, 210),
+        event(Arrival, 2, 250),
+        event(Enqueue, 2, 250),
+        event(Complete, 1, 300),
+        event(Complete, 0, 400),
+        event(DirectExecute, 2, 400),
+        event(Dispatch, 2, 410),
+        event(Complete, 2, 900),
+        event(Miss, 2, 900),
+    ]
+}
+
+#[test]
+fn encode_decode() {
+    let events = sample_events();
+    assert_eq!(decode(&encode(&events)), events);
+}
+
+#[test]
+fn decode_skips_unknown_and_partial_events() {
+    let mut bytes = encode(&[event(EventKind::Arrival, 3, 42)]);
+    bytes.extend([0xff, 0, 0, 0, 0, 0, 0, 0]);
+    bytes.extend(encode(&[event(EventKind::Complete, 3, 43)]));
+    bytes.extend([1, 0, 0]);
+
+    assert_eq!(
+        decode(&bytes),
+        [
+            event(EventKind::Arrival, 3, 42),
+            event(EventKind::Complete, 3, 43)
+        ]
+    );
+}
+
+#[test]
+fn timeline() {
+    let timeline = Timeline::new(&sample_events(), 1_000_000);
+
+    let summary: Vec<_> = timeline
+        .jobs
+        .iter()
+        .map(|j| {
+            (
+                j.task,
+                j.arrival,
+                j.dispatch,
+                j.completion,
+                j.enqueued,
+                j.missed_deadline,
+            )
+        })
+        .collect();
+
+    assert_eq!(
+        summary,
+        [
+            (0, 100, Some(110), Some(400), false, false),
+            (1, 200, Some(210), Some(300), false, false),
+            (2, 250, Some(410), Some(900), true, true),
+        ]
+    );
+}
+
+#[test]
+fn timeline_handles_timestamp_wraparound() {
+    use EventKind::*;
+
+    let events = [
+        event(Arrival, 0, u32::MAX - 10),
+        event(Dispatch, 0, u32::MAX - 5),
+        event(Complete, 0, 20),
+    ];
+    let timeline = Timeline::new(&events, 1_000_000);
+
+    let job = &timeline.jobs[0];
+    assert_eq!(job.completion.unwrap() - job.arrival, 31);
+}
+
+#[test]
+fn timeline_of_cancelled_jobs() {
+    use EventKind::*;
+
+    // Two more jobs of task 3 wait behind the running one, and are cancelled
+    let events = [
+        event(Arrival, 3, 100),
+        event(DirectExecute, 3, 100),
+        event(Dispatch, 3, 110),
+        event(Arrival, 3, 150),
+        event(Enqueue, 3, 150),
+        event(Arrival, 3, 200),
+        event(Enqueue, 3, 200),
+        event(Cancel, 3, 250),
+        event(Cancel, 3, 250),
+        event(Complete, 3, 300),
+    ];
+    let timeline = Timeline::new(&events, 1_000_000);
+
+    let summary: Vec<_> = timeline
+        .jobs
+        .iter()
+        .map(|j| (j.arrival, j.dispatch, j.completion, j.cancellation))
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            (100, Some(110), Some(300), None),
+            (150, None, None, Some(250)),
+            (200, None, None, Some(250)),
+        ]
+    );
+
+    let trace = timeline.to_chrome_trace(&HashMap::new());
+    let cancels = trace["traceEvents"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|e| e["name"] == "cancel")
+        .count();
+    assert_eq!(cancels, 2);
+    assert!(timeline.to_string().contains("cancelled"));
+}
+
+#[test]
+fn chrome_trace() {
+    let timeline = Timeline::new(&sample_events(), 10_000_000);
+    let names = HashMap::from([(2, "Slow".to_string())]);
+
+    let trace = timeline.to_chrome_trace(&names);
+    let events = trace["traceEvents"].as_array().unwrap();
+
+    let slices: Vec<_> = events.iter().filter(|e| e["ph"] == "X").collect();
+    assert_eq!(slices.len(), 3);
+
+    let slow = slices.iter().find(|e| e["tid"] == 2).unwrap();
+    assert_eq!(slow["name"], "Slow");
+    assert_eq!(slow["ts"], 41.0);
+    assert_eq!(slow["dur"], 49.0);
+    assert_eq!(slow["args"]["enqueued"], true);
+
+    let unnamed = slices.iter().find(|e| e["tid"] == 0).unwrap();
+    assert_eq!(unnamed["name"], "task 0");
+
+    let misses = events
+        .iter()
+        .filter(|e| e["name"] == "deadline miss")
+        .count();
+    assert_eq!(misses, 1);
+}
+
+#[test]
+fn names_from_report() {
+    let report = r#"{
+        "app": "app",
+        "tasks": [
+            { "name": "Slow", "dispatcher_idx": 0 },
+            { "name": "Fast", "dispatcher_idx": 1 }
+        ]
+    }"#;
+
+    let names = task_names_from_report(report).unwrap();
+    assert_eq!(
+        names,
+        HashMap::from([(0, "Slow".to_string()), (1, "Fast".to_string())])
+    );
+}
+
+#[test]
+fn trace_buffer_round_trip() {
+    let buffer = TraceBuffer::<4>::new();
+    let events: Vec<_> = (0..6)
+        .map(|i| event(EventKind::Arrival, i, i as u32 * 10))
+        .collect();
+
+    for e in events.iter() {
+        buffer.push(*e);
+    }
+    assert_eq!(buffer.dropped(), 2);
+
+    // Only whole events are drained
+    let mut buf = [0; 20];
+    let n = buffer.drain(&mut buf);
+    assert_eq!(decode(&buf[..n]), events[..2]);
+
+    buffer.push(events[4]);
+
+    let mut buf = [0; 64];
+    let n = buffer.drain(&mut buf);
+    assert_eq!(decode(&buf[..n]), [events[2], events[3], events[4]]);
+    assert_eq!(buffer.drain(&mut buf), 0);
+}
+
+#[test]
+fn trace_buffer_concurrent_producers() {
+    const PER_TASK: u32 = 200;
+    let buffer = TraceBuffer::<1024>::new();
+
+    // Every producer pushes events with increasing timestamps, racing with the
+    // others for the slots
+    std::thread::scope(|s| {
+        for task in 0..4 {
+            let buffer = &buffer;
+            s.spawn(move || {
+                for i in 0..PER_TASK {
+                    buffer.push(event(EventKind::Dispatch, task, i));
+                }
+            });
+        }
+    });
+    assert_eq!(buffer.dropped(), 0);
+
+    let mut buf = vec![0; 1024 * Event::SIZE];
+    let n = buffer.drain(&mut buf);
+    let events = decode(&buf[..n]);
+    assert_eq!(events.len(), 4 * PER_TASK as usize);
+
+    for task in 0..4 {
+        let timestamps: Vec<_> = events
+            .iter()
+            .filter(|e| e.task == task)
+            .map(|e| e.timestamp)
+            .collect();
+        assert_eq!(timestamps, (0..PER_TASK).collect::<Vec<_>>());
+    }
+}
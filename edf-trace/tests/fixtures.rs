@@ -0,0 +1,67 @@
+//! Decode recorded traces and compare the result with the expected outputs
+//! stored next to them in `tests/fixtures`.
+
+use std::{collections::HashMap, fs, path::PathBuf};
+
+use edf_trace::{Timeline, ctf::CtfTrace, decode, task_names_from_report};
+
+const TICK_HZ: u32 = 12_000_000;
+
+fn fixture(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join(name)
+}
+
+fn read(name: &str) -> Vec<u8> {
+    let path = fixture(name);
+    fs::read(&path).unwrap_or_else(|e| panic!("Failed to read {}: {e}", path.display()))
+}
+
+fn read_to_string(name: &str) -> String {
+    String::from_utf8(read(name)).unwrap()
+}
+
+fn names() -> HashMap<u16, String> {
+    task_names_from_report(&read_to_string("wait_queue_schedule.json")).unwrap()
+}
+
+#[test]
+fn timeline() {
+    let timeline = Timeline::new(&decode(&read("wait_queue.bin")), TICK_HZ);
+
+    assert_eq!(timeline.to_string(), read_to_string("wait_queue.timeline"));
+}
+
+#[test]
+fn timeline_of_overlapping_jobs() {
+    // The second job of task 3 arrives while the first one runs, and completes
+    // after its deadline
+    let timeline = Timeline::new(&decode(&read("overlapping.bin")), TICK_HZ);
+
+    assert_eq!(timeline.to_string(), read_to_string("overlapping.timeline"));
+}
+
+#[test]
+fn chrome_trace() {
+    let timeline = Timeline::new(&decode(&read("wait_queue.bin")), TICK_HZ);
+
+    let trace = serde_json::to_string_pretty(&timeline.to_chrome_trace(&names())).unwrap();
+    assert_eq!(trace, read_to_string("wait_queue.chrome.json"));
+}
+
+#[test]
+fn ctf() {
+    let trace = CtfTrace::new(&decode(&read("wait_queue.bin")), TICK_HZ);
+
+    assert_eq!(trace.metadata, read_to_string("wait_queue.ctf/metadata"));
+    assert_eq!(trace.stream, read("wait_queue.ctf/stream"));
+}
+
+#[test]
+fn ctf_timestamps_are_extended_past_wraparound() {
+    let trace = CtfTrace::new(&decode(&read("wraparound.bin")), TICK_HZ);
+
+    assert_eq!(trace.stream, read("wraparound.ctf/stream"));
+}
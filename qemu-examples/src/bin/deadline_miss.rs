@@ -0,0 +1,89 @@
+//! A task hogging the CPU past its own deadline delays a task released in the
+//! meantime, which then misses its deadline. The miss is caught by the
+//! `check-missed-deadlines` feature when the late task is dispatched.
+
+#![no_main]
+#![no_std]
+
+use qemu_examples as _;
+
+#[cortex_m_edf_rtic::app(
+    device = lm3s6965,
+    dispatchers = [SSI0, QEI0],
+    cpu_freq = 12_000_000,
+    monotonic = cortex_m_edf_rtic::monotonic::Systick::<12_000_000>,
+)]
+mod app {
+    use cortex_m::peripheral::NVIC;
+    use cortex_m_semihosting::{debug, hprintln};
+    use lm3s6965::Interrupt;
+    use rtic_edf_pass::time::{TimeSource, us_to_ticks};
+
+    #[shared]
+    struct Shared {}
+
+    #[init]
+    fn system_init() -> Shared {
+        NVIC::pend(Interrupt::GPIOA);
+
+        Shared {}
+    }
+
+    #[idle]
+    pub struct IdleTask;
+
+    impl RticIdleTask for IdleTask {
+        fn init() -> Self {
+            Self
+        }
+
+        fn exec(&mut self) -> ! {
+            hprintln!("idle");
+            debug::exit(debug::EXIT_SUCCESS);
+
+            loop {
+                core::hint::spin_loop();
+            }
+        }
+    }
+
+    #[task(deadline_us = 10_000, binds = GPIOA)]
+    pub struct HogTask;
+
+    impl RticTask for HogTask {
+        fn init() -> Self {
+            Self
+        }
+
+        fn exec(&mut self) {
+            hprintln!("hog: start");
+            NVIC::pend(Interrupt::GPIOB);
+
+            // Busy for much longer than the deadline of both tasks
+            let start = EdfTimeSource::now();
+            while EdfTimeSource::now().wrapping_sub(start) < us_to_ticks::<EdfTimeSource>(50_000) {
+                core::hint::spin_loop();
+            }
+
+            hprintln!("hog: end");
+        }
+    }
+
+    #[task(deadline_us = 20_000, binds = GPIOB)]
+    pub struct VictimTask;
+
+    impl RticTask for VictimTask {
+        fn init() -> Self {
+            Self
+        }
+
+        fn exec(&mut self) {
+            hprintln!("victim");
+        }
+    }
+}
+
+#[cortex_m_rt::exception]
+fn SysTick() {
+    cortex_m_edf_rtic::monotonic::Systick::<12_000_000>::on_interrupt();
+}
@@ -0,0 +1,90 @@
+//! A task with a short deadline released while a shared resource is locked
+//! only runs once the lock is released.
+
+#![no_main]
+#![no_std]
+
+use qemu_examples as _;
+
+#[cortex_m_edf_rtic::app(
+    device = lm3s6965,
+    dispatchers = [SSI0, QEI0],
+    cpu_freq = 12_000_000,
+    monotonic = cortex_m_edf_rtic::monotonic::Systick::<12_000_000>,
+)]
+mod app {
+    use cortex_m::peripheral::NVIC;
+    use cortex_m_semihosting::{debug, hprintln};
+    use lm3s6965::Interrupt;
+
+    #[shared]
+    struct Shared {
+        x: u32,
+    }
+
+    #[init]
+    fn system_init() -> Shared {
+        NVIC::pend(Interrupt::GPIOA);
+
+        Shared { x: 0 }
+    }
+
+    #[idle]
+    pub struct IdleTask;
+
+    impl RticIdleTask for IdleTask {
+        fn init() -> Self {
+            Self
+        }
+
+        fn exec(&mut self) -> ! {
+            hprintln!("idle");
+            debug::exit(debug::EXIT_SUCCESS);
+
+            loop {
+                core::hint::spin_loop();
+            }
+        }
+    }
+
+    #[task(deadline_us = 100_000, binds = GPIOA, shared = [x])]
+    pub struct LowTask;
+
+    impl RticTask for LowTask {
+        fn init() -> Self {
+            Self
+        }
+
+        fn exec(&mut self) {
+            self.shared().x.lock(|x| {
+                *x += 1;
+                hprintln!("low: locked x = {}", *x);
+                NVIC::pend(Interrupt::GPIOB);
+                hprintln!("low: unlocking");
+            });
+
+            hprintln!("low: done");
+        }
+    }
+
+    #[task(deadline_us = 10_000, binds = GPIOB, shared = [x])]
+    pub struct HighTask;
+
+    impl RticTask for HighTask {
+        fn init() -> Self {
+            Self
+        }
+
+        fn exec(&mut self) {
+            self.shared().x.lock(|x| {
+                *x += 1;
+                hprintln!("high: x = {}", *x);
+            });
+        }
+    }
+}
+
+#[cortex_m_rt::exception]
+fn SysTick() {
+    cortex_m_edf_rtic::monotonic::Systick::<12_000_000>::on_interrupt();
+}
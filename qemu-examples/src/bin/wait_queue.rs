@@ -0,0 +1,109 @@
+//! Tasks released while a task with an earlier deadline is running wait in
+//! the wait queue, and are then dispatched in deadline order rather than in
+//! arrival order.
+
+#![no_main]
+#![no_std]
+
+use qemu_examples as _;
+
+#[cortex_m_edf_rtic::app(
+    device = lm3s6965,
+    dispatchers = [SSI0, QEI0, I2C0, UART1],
+    cpu_freq = 12_000_000,
+    monotonic = cortex_m_edf_rtic::monotonic::Systick::<12_000_000>,
+)]
+mod app {
+    use cortex_m::peripheral::NVIC;
+    use cortex_m_semihosting::{debug, hprintln};
+    use lm3s6965::Interrupt;
+
+    #[shared]
+    struct Shared {}
+
+    #[init]
+    fn system_init() -> Shared {
+        NVIC::pend(Interrupt::GPIOA);
+
+        Shared {}
+    }
+
+    #[idle]
+    pub struct IdleTask;
+
+    impl RticIdleTask for IdleTask {
+        fn init() -> Self {
+            Self
+        }
+
+        fn exec(&mut self) -> ! {
+            hprintln!("idle");
+            debug::exit(debug::EXIT_SUCCESS);
+
+            loop {
+                core::hint::spin_loop();
+            }
+        }
+    }
+
+    #[task(deadline_us = 50_000, binds = GPIOA)]
+    pub struct UrgentTask;
+
+    impl RticTask for UrgentTask {
+        fn init() -> Self {
+            Self
+        }
+
+        fn exec(&mut self) {
+            hprintln!("urgent: releasing a, b, c");
+            NVIC::pend(Interrupt::GPIOB);
+            NVIC::pend(Interrupt::GPIOC);
+            NVIC::pend(Interrupt::GPIOD);
+            hprintln!("urgent: done");
+        }
+    }
+
+    #[task(deadline_us = 300_000, binds = GPIOB)]
+    pub struct TaskA;
+
+    impl RticTask for TaskA {
+        fn init() -> Self {
+            Self
+        }
+
+        fn exec(&mut self) {
+            hprintln!("a");
+        }
+    }
+
+    #[task(deadline_us = 200_000, binds = GPIOC)]
+    pub struct TaskB;
+
+    impl RticTask for TaskB {
+        fn init() -> Self {
+            Self
+        }
+
+        fn exec(&mut self) {
+            hprintln!("b");
+        }
+    }
+
+    #[task(deadline_us = 100_000, binds = GPIOD)]
+    pub struct TaskC;
+
+    impl RticTask for TaskC {
+        fn init() -> Self {
+            Self
+        }
+
+        fn exec(&mut self) {
+            hprintln!("c");
+        }
+    }
+}
+
+#[cortex_m_rt::exception]
+fn SysTick() {
+    cortex_m_edf_rtic::monotonic::Systick::<12_000_000>::on_interrupt();
+}
@@ -0,0 +1,16 @@
+#![no_std]
+
+use core::panic::PanicInfo;
+
+use cortex_m_semihosting::{debug, hprintln};
+
+/// Prints the panic message only (without its location), so that the expected
+/// output of the examples doesn't depend on line numbers.
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    hprintln!("{}", info.message());
+    debug::exit(debug::EXIT_FAILURE);
+
+    #[allow(clippy::empty_loop)]
+    loop {}
+}
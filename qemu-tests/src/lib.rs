@@ -0,0 +1,124 @@
+//! Host-side runner for the examples in `qemu-examples`.
+//!
+//! Each example is built for `thumbv7m-none-eabi` and run in
+//! `qemu-system-arm` through the runner configured in
+//! `qemu-examples/.cargo/config.toml`. Its semihosting output is then compared
+//! against `qemu-examples/expected/<example>.run`. The examples are also built
+//! for `thumbv6m-none-eabi` with the ARMv6-M backend.
+
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// Result of running an example in QEMU
+pub struct Run {
+    /// `true` if the example exited with `EXIT_SUCCESS`
+    pub success: bool,
+    /// Everything the example printed through semihosting
+    pub stdout: String,
+}
+
+fn examples_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("..")
+        .join("qemu-examples")
+}
+
+/// Build and run an example in QEMU.
+///
+/// # Panics
+///
+/// Panics if the example can't be built or QEMU can't be started.
+pub fn run_example(name: &str) -> Run {
+    let output = Command::new(env!("CARGO"))
+        .args(["run", "--release", "--quiet", "--bin", name])
+        .current_dir(examples_dir())
+        .output()
+        .unwrap_or_else(|e| panic!("Failed to run cargo for example {name}: {e}"));
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    // cargo's own errors (build failure, missing runner) show up on stderr and
+    // aren't the example's fault
+    assert!(
+        !stderr.contains("error:"),
+        "Failed to build or run example {name}:\n{stderr}"
+    );
+
+    Run {
+        success: output.status.success(),
+        stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+    }
+}
+
+/// Build an example for `target`, with the given features of
+/// `qemu-examples`, without running it.
+///
+/// # Panics
+///
+/// Panics if the example can't be built.
+pub fn build_example(name: &str, target: &str, features: &[&str]) {
+    let output = Command::new(env!("CARGO"))
+        .args(["build", "--release", "--quiet", "--bin", name])
+        .args(["--target", target])
+        .args(["--features", &features.join(",")])
+        .current_dir(examples_dir())
+        .output()
+        .unwrap_or_else(|e| panic!("Failed to run cargo for example {name}: {e}"));
+
+    assert!(
+        output.status.success(),
+        "Failed to build example {name} for {target}:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+/// Returns why the examples can't run on this host, if `qemu-system-arm` or
+/// the `thumbv7m-none-eabi` target is missing
+pub fn missing_requirement() -> Option<String> {
+    let qemu = Command::new("qemu-system-arm").arg("--version").output();
+    if !qemu.is_ok_and(|o| o.status.success()) {
+        return Some("qemu-system-arm isn't installed".into());
+    }
+
+    missing_target("thumbv7m-none-eabi")
+}
+
+/// Returns why the examples can't be built for `target` on this host, if it
+/// isn't installed
+pub fn missing_target(target: &str) -> Option<String> {
+    let Some(sysroot) = Command::new("rustc")
+        .args(["--print", "sysroot"])
+        .current_dir(examples_dir())
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+    else {
+        return Some("rustc can't be run".into());
+    };
+    let sysroot = String::from_utf8_lossy(&sysroot.stdout);
+    let path = Path::new(sysroot.trim())
+        .join("lib")
+        .join("rustlib")
+        .join(target);
+    if !path.exists() {
+        return Some(format!("the {target} target isn't installed"));
+    }
+
+    None
+}
+
+/// Returns the expected output of an example.
+///
+/// # Panics
+///
+/// Panics if the example has no `.run` file.
+pub fn expected_output(name: &str) -> String {
+    let path = examples_dir()
+        .join("expected")
+        .join(name)
+        .with_extension("run");
+
+    std::fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("Failed to read {}: {e}", path.display()))
+}
@@ -0,0 +1,49 @@
+//! Run the `qemu-examples` and compare their output with the expected one.
+//!
+//! These tests need `qemu-system-arm` and the `thumbv7m-none-eabi` target.
+//! They are skipped when either is missing, unless `QEMU_TESTS_REQUIRED` is
+//! set, in which case they fail.
+
+use qemu_tests::{expected_output, missing_requirement, run_example};
+
+fn check(name: &str, should_succeed: bool) {
+    if let Some(missing) = missing_requirement() {
+        assert!(
+            std::env::var_os("QEMU_TESTS_REQUIRED").is_none(),
+            "Can't run example {name}: {missing}"
+        );
+        eprintln!("Skipping example {name}: {missing}");
+        return;
+    }
+
+    let run = run_example(name);
+    assert_eq!(
+        run.stdout,
+        expected_output(name),
+        "Unexpected output for {name}"
+    );
+    assert_eq!(
+        run.success, should_succeed,
+        "Unexpected exit status for {name}"
+    );
+}
+
+#[test]
+fn preemption() {
+    check("preemption", true);
+}
+
+#[test]
+fn wait_queue() {
+    check("wait_queue", true);
+}
+
+#[test]
+fn shared() {
+    check("shared", true);
+}
+
+#[test]
+fn deadline_miss() {
+    check("deadline_miss", false);
+}
@@ -0,0 +1,25 @@
+//! Build the `qemu-examples` with the ARMv6-M backend.
+//!
+//! QEMU has no ARMv6-M machine matching the examples' memory map, so this only
+//! makes sure that the backend compiles for `thumbv6m-none-eabi`. The test is
+//! skipped when the target is missing, unless `QEMU_TESTS_REQUIRED` is set, in
+//! which case it fails.
+
+use qemu_tests::{build_example, missing_target};
+
+const TARGET: &str = "thumbv6m-none-eabi";
+
+#[test]
+fn thumbv6_backend_builds() {
+    if let Some(missing) = missing_target(TARGET) {
+        assert!(
+            std::env::var_os("QEMU_TESTS_REQUIRED").is_none(),
+            "Can't build the examples for {TARGET}: {missing}"
+        );
+        eprintln!("Skipping the {TARGET} build: {missing}");
+        return;
+    }
+
+    // `shared` locks a resource, which goes through the interrupt source masking
+    build_example("shared", TARGET, &["thumbv6-backend"]);
+}
@@ -0,0 +1,122 @@
+//! Deadline to priority conversion.
+//!
+//! Tasks are sorted by relative deadline, and each unique deadline gets its
+//! own priority level: the longest deadline gets the lowest priority (1), the
+//! shortest one the highest. Every task also gets its own dispatcher, in the
+//! same order.
+
+use std::fmt;
+
+use proc_macro2::Ident;
+use syn::Path;
+
+use crate::types::Deadline;
+
+/// Priority, run queue index and dispatcher assigned to a single task
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskAssignment {
+    pub name: Ident,
+    pub deadline_us: Deadline,
+    /// Priority of the task's dispatcher
+    pub priority: u16,
+    /// Index of the task's priority level in the run queue
+    pub rq_idx: u16,
+    /// Index of the task's dispatcher in the `dispatchers` app argument
+    pub dispatcher_idx: u16,
+    /// Interrupt used to dispatch the task
+    pub dispatcher: Path,
+}
+
+/// Result of [`assign_priorities`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PriorityAssignment {
+    /// Assigned tasks, sorted by dispatcher index
+    pub tasks: Vec<TaskAssignment>,
+}
+
+impl PriorityAssignment {
+    /// Returns the assignment for the task named `name`, if any
+    pub fn get(&self, name: &Ident) -> Option<&TaskAssignment> {
+        self.tasks.iter().find(|t| t.name == *name)
+    }
+
+    /// Returns the number of unique priority levels
+    pub fn num_priorities(&self) -> usize {
+        self.tasks
+            .iter()
+            .map(|t| t.priority)
+            .max()
+            .unwrap_or(0)
+            .into()
+    }
+}
+
+impl fmt::Display for PriorityAssignment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for t in self.tasks.iter() {
+            writeln!(
+                f,
+                "Task {}:\n\tdeadline: {} us\n\tpriority: {}\n\trun queue idx: {}\n\tdispatcher: {}",
+                t.name,
+                t.deadline_us,
+                t.priority,
+                t.rq_idx,
+                t.dispatcher.get_ident().unwrap(),
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Assign priorities and dispatchers to a set of `(name, deadline)` tasks.
+///
+/// Tasks with equal deadlines share a priority level, and are ordered by name
+/// so that the result doesn't depend on the order in which they are declared.
+///
+/// # Panics
+///
+/// Panics if there are fewer dispatchers than tasks.
+pub fn assign_priorities(tasks: &[(Ident, Deadline)], dispatchers: &[Path]) -> PriorityAssignment {
+    assert!(
+        dispatchers.len() >= tasks.len(),
+        "The EDF scheduler needs at least as many dispatchers as there are tasks ({} tasks, {} dispatchers).",
+        tasks.len(),
+        dispatchers.len()
+    );
+
+    let mut sorted_tasks = tasks.to_vec();
+    sorted_tasks.sort_by(|(name_a, dl_a), (name_b, dl_b)| {
+        dl_b.cmp(dl_a)
+            .then_with(|| name_a.to_string().cmp(&name_b.to_string()))
+    });
+
+    let mut priority = 0;
+    let mut prev_deadline = None;
+
+    let tasks = sorted_tasks
+        .into_iter()
+        .zip(dispatchers)
+        .enumerate()
+        .map(|(dispatcher_idx, ((name, deadline_us), dispatcher))| {
+            if prev_deadline != Some(deadline_us) {
+                priority += 1;
+                prev_deadline = Some(deadline_us);
+            }
+
+            TaskAssignment {
+                name,
+                deadline_us,
+                priority,
+                // Subtract 1 such that the minimum index is 0
+                rq_idx: priority - 1,
+                dispatcher_idx: dispatcher_idx
+                    .try_into()
+                    .expect("Unsupported dispatcher priority level: over u16::MAX"),
+                dispatcher: dispatcher.clone(),
+            }
+        })
+        .collect();
+
+    PriorityAssignment { tasks }
+}
@@ -1,19 +1,41 @@
 use crate::edf_pass::parse::EdfTask;
 
-use super::parse::App;
+use super::{demand::demand_test, modes::mode_analysis, parse::App};
 
-use heck::ToSnakeCase;
-use proc_macro2::{Span, TokenStream};
+use heck::{ToSnakeCase, ToUpperCamelCase};
+use proc_macro2::{Literal, Span, TokenStream};
 use quote::{format_ident, quote};
 use syn::{ItemMod, parse_quote};
 
+/// Runtime instrumentation of the scheduler whose bookkeeping is generated
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Instrumentation {
+    pub stats: bool,
+    pub response_times: bool,
+}
+
+impl Instrumentation {
+    /// Instrumentation enabled by the features of this crate, which the
+    /// generated code relies on
+    pub const fn from_features() -> Self {
+        Self {
+            stats: cfg!(feature = "stats"),
+            response_times: cfg!(feature = "response-time"),
+        }
+    }
+}
+
 pub struct CodeGen {
     app: App,
+    instrumentation: Instrumentation,
 }
 
 impl CodeGen {
-    pub fn new(app: App) -> CodeGen {
-        Self { app }
+    pub fn new(app: App, instrumentation: Instrumentation) -> CodeGen {
+        Self {
+            app,
+            instrumentation,
+        }
     }
 
     pub fn run(&mut self) -> ItemMod {
@@ -77,15 +99,169 @@ impl CodeGen {
             .collect();
 
         let run_queue_len = self.app.dispatcher_priorities().len();
+
+        // Relative deadline of every priority level in every mode, and number of
+        // tasks of every priority level
+        let num_modes = self.app.num_modes();
+        let mode_deadlines: Vec<_> = (0..num_modes)
+            .map(|mode| {
+                let deadlines = self.app.level_deadlines(mode);
+                quote! {
+                    [#(::rtic_edf_pass::time::us_to_ticks::<EdfTimeSource>(#deadlines),)*]
+                }
+            })
+            .collect();
+        let tasks_per_level: Vec<_> = (0..run_queue_len as u16)
+            .map(|rq_idx| self.app.tasks.iter().filter(|t| t.rq_idx == rq_idx).count() as u16)
+            .collect();
         let wait_queue_len = self.app.wait_queue_len();
         let num_dispatchers = dispatchers.len();
 
         let pac_path = &self.app.app_parameters.pac_path;
+        let time_source = &self.app.time_source;
+
+        let (stats_field, stats_init, stats_impl, stats_accessor) = if self.instrumentation.stats {
+            (
+                quote! {
+                    stats: ::rtic_edf_pass::scheduler::stats::SchedulerStats<
+                        [::rtic_edf_pass::scheduler::stats::TaskStats; NUM_EDF_DISPATCHERS]
+                    >,
+                },
+                quote! { stats: ::rtic_edf_pass::scheduler::stats::SchedulerStats::new(), },
+                quote! {
+                    #[inline]
+                    fn stats(&self) -> &::rtic_edf_pass::scheduler::stats::SchedulerStats {
+                        &self.stats
+                    }
+                },
+                quote! {
+                    /// Runtime statistics of the scheduler. Tasks are indexed by
+                    /// their `EdfTaskBinding::DISPATCHER_IDX`.
+                    pub fn scheduler_stats() -> &'static ::rtic_edf_pass::scheduler::stats::SchedulerStats {
+                        SCHEDULER.stats()
+                    }
+                },
+            )
+        } else {
+            Default::default()
+        };
+
+        let (timings_field, timings_init, timings_impl, timings_accessor) = if self
+            .instrumentation
+            .response_times
+        {
+            (
+                quote! {
+                    response_times: ::rtic_edf_pass::scheduler::response_time::ResponseTimes<
+                        [::rtic_edf_pass::scheduler::response_time::TaskTimings; NUM_EDF_DISPATCHERS]
+                    >,
+                },
+                quote! { response_times: ::rtic_edf_pass::scheduler::response_time::ResponseTimes::new(), },
+                quote! {
+                    #[inline]
+                    fn response_times(&self) -> &::rtic_edf_pass::scheduler::response_time::ResponseTimes {
+                        &self.response_times
+                    }
+                },
+                quote! {
+                    /// Release jitter, busy time and response time histograms of
+                    /// every task, indexed by their `EdfTaskBinding::DISPATCHER_IDX`.
+                    pub fn response_times() -> &'static ::rtic_edf_pass::scheduler::response_time::ResponseTimes {
+                        SCHEDULER.response_times()
+                    }
+                },
+            )
+        } else {
+            Default::default()
+        };
+
+        let switch_timestampers = self.app.tasks.iter().map(|task| {
+            let task_ident = &task.task_struct.ident;
+            let binds = &task.timestamper_binding;
+
+            quote! {
+                if #task_ident::MODES & (1 << mode) == 0 {
+                    #task_ident::mask_timestamper_interrupt();
+                } else if !::cortex_m::peripheral::NVIC::is_enabled(Interrupt::#binds) {
+                    #task_ident::unpend_timestamper_interrupt();
+                    unsafe { #task_ident::unmask_timestamper_interrupt() };
+                }
+            }
+        });
+
+        let served: Vec<_> = self
+            .app
+            .tasks
+            .iter()
+            .filter(|t| t.server.is_some())
+            .collect();
+        let served_idents = served.iter().map(|t| &t.task_struct.ident);
+        let served_binds = served.iter().map(|t| &t.timestamper_binding);
+
+        let (poll_servers, server_impl) = if served.is_empty() {
+            Default::default()
+        } else {
+            let idents = served.iter().map(|t| &t.task_struct.ident);
+            let lookup = served.iter().map(|t| {
+                let (ident, idx) = (&t.task_struct.ident, t.dispatcher_idx);
+                quote! { #idx => #ident::server(), }
+            });
+            (
+                quote! {
+                    /// Release the arrivals of served tasks throttled by their constant
+                    /// bandwidth server, if it caught up. This otherwise only happens when
+                    /// a task arrives or completes, or when `idle_sleep` returns.
+                    pub fn poll_servers() {
+                        SCHEDULER.poll_servers();
+                    }
+                },
+                quote! {
+                    #[inline]
+                    fn server(
+                        dispatcher_idx: u16,
+                    ) -> Option<&'static ::rtic_edf_pass::scheduler::Server> {
+                        use ::rtic_edf_pass::task::EdfTaskBinding;
+
+                        match dispatcher_idx {
+                            #(#lookup)*
+                            _ => None,
+                        }
+                    }
+
+                    fn next_server_resume(
+                        cs: &::rtic_edf_pass::scheduler::CriticalSection<'_>,
+                    ) -> Option<::rtic_edf_pass::types::Timestamp> {
+                        use ::rtic_edf_pass::task::EdfTaskBinding;
+
+                        [#(#idents::server().and_then(|s| s.resumes_at(cs)),)*]
+                            .into_iter()
+                            .flatten()
+                            .min()
+                    }
+                },
+            )
+        };
+
+        let (budget_field, budget_init, budget_impl, budget_api) = self.generate_budget_items();
+        let (slack_impl, slack_api) = self.generate_slack_items();
+
+        let mode_api = self.generate_mode_api();
 
         parse_quote! {
+            /// Time source used to timestamp task arrivals
+            pub type EdfTimeSource = #time_source;
+
             const EDF_WAIT_QUEUE_LEN: usize = #wait_queue_len;
             const EDF_RUN_QUEUE_LEN: usize = #run_queue_len;
             const NUM_EDF_DISPATCHERS: usize = #num_dispatchers;
+            const EDF_NUM_MODES: usize = #num_modes;
+
+            /// Relative deadline of every priority level in every mode
+            const EDF_MODE_DEADLINES: [[::rtic_edf_pass::types::Deadline; EDF_RUN_QUEUE_LEN]; EDF_NUM_MODES] = [
+                #(#mode_deadlines,)*
+            ];
+            // The deadlines must keep the priority order once rounded to ticks
+            const _: () = ::rtic_edf_pass::scheduler::assert_level_order(&EDF_MODE_DEADLINES);
 
             // TODO: cortex-m leaking here?
             const EDF_DISPATCHERS: [#pac_path::Interrupt; NUM_EDF_DISPATCHERS] = [
@@ -97,6 +273,11 @@ impl CodeGen {
                running_queue: ::rtic_edf_pass::scheduler::RunQueue<EDF_RUN_QUEUE_LEN>,
                 min_deadline: ::rtic_edf_pass::scheduler::SystemDeadline,
                 task_queue: ::rtic_edf_pass::scheduler::WaitQueue<EDF_WAIT_QUEUE_LEN>,
+                deadlines: ::rtic_edf_pass::scheduler::DeadlineTable<EDF_RUN_QUEUE_LEN>,
+                modes: ::rtic_edf_pass::scheduler::Modes<EDF_RUN_QUEUE_LEN>,
+                #budget_field
+                #stats_field
+                #timings_field
             }
 
             impl NvicScheduler {
@@ -106,16 +287,23 @@ impl CodeGen {
                        running_queue: ::rtic_edf_pass::scheduler::RunQueue::new(),
                         min_deadline: ::rtic_edf_pass::scheduler::SystemDeadline::new(),
                         task_queue: ::rtic_edf_pass::scheduler::WaitQueue::new(),
+                        deadlines: ::rtic_edf_pass::scheduler::DeadlineTable::new(
+                            EDF_MODE_DEADLINES[0],
+                            [#(#tasks_per_level,)*],
+                        ),
+                        modes: ::rtic_edf_pass::scheduler::Modes::new(&EDF_MODE_DEADLINES),
+                        #budget_init
+                        #stats_init
+                        #timings_init
                     }
                 }
             }
 
             impl ::rtic_edf_pass::scheduler::Scheduler<EDF_RUN_QUEUE_LEN, EDF_WAIT_QUEUE_LEN> for NvicScheduler {
 
-                // TODO: cortex-m is leaking here
                 #[inline]
                 fn now() -> ::rtic_edf_pass::types::Timestamp {
-                    ::cortex_m::peripheral::DWT::cycle_count()
+                    <EdfTimeSource as ::rtic_edf_pass::time::TimeSource>::now()
                 }
 
                 #[inline]
@@ -133,13 +321,248 @@ impl CodeGen {
                     &self.task_queue
                 }
 
+                #[inline]
+                fn deadlines(&self) -> &::rtic_edf_pass::scheduler::DeadlineTable<EDF_RUN_QUEUE_LEN> {
+                    &self.deadlines
+                }
+
+                #[inline]
+                fn modes(&self) -> &::rtic_edf_pass::scheduler::Modes<EDF_RUN_QUEUE_LEN> {
+                    &self.modes
+                }
+
                 #[inline]
                 fn pend_dispatcher(idx: u16) {
                     ::cortex_m::peripheral::NVIC::pend(EDF_DISPATCHERS[idx as usize]);
                 }
+
+                fn switch_timestampers(mode: u16) {
+                    use ::rtic_edf_pass::task::EdfTaskBinding;
+
+                    // TODO this is sort of sketchy, we should somehow get the right path to the interrupt enum variant
+                    #(#switch_timestampers)*
+                }
+
+                fn resume_servers(
+                    _cs: &::rtic_edf_pass::scheduler::CriticalSection<'_>,
+                    _now: ::rtic_edf_pass::types::Timestamp,
+                ) {
+                    use ::rtic_edf_pass::task::EdfTaskBinding;
+
+                    #(
+                        if #served_idents::server().is_some_and(|s| s.resume(_now, _cs)) {
+                            ::cortex_m::peripheral::NVIC::pend(Interrupt::#served_binds);
+                            unsafe { #served_idents::unmask_timestamper_interrupt() };
+                        }
+                    )*
+                }
+
+                #server_impl
+                #budget_impl
+                #slack_impl
+                #stats_impl
+                #timings_impl
             }
 
             static SCHEDULER: NvicScheduler = NvicScheduler::new();
+
+            /// Change the relative deadline of task `T`, in microseconds, for the jobs
+            /// released from now on. See `Scheduler::set_relative_deadline` for the
+            /// accepted changes.
+            pub fn set_relative_deadline<T: ::rtic_edf_pass::task::EdfTaskBinding>(
+                deadline_us: u32,
+            ) -> Result<(), ::rtic_edf_pass::scheduler::DeadlineError> {
+                let deadline = ::rtic_edf_pass::time::try_us_to_ticks::<EdfTimeSource>(deadline_us)
+                    .ok_or(::rtic_edf_pass::scheduler::DeadlineError::TooLong)?;
+                SCHEDULER.set_relative_deadline::<T>(deadline)
+            }
+
+            /// Cancel the jobs of task `T` which are still waiting to be dispatched,
+            /// and return how many were cancelled. See `Scheduler::cancel_pending_jobs`.
+            pub fn cancel_pending_jobs<T: ::rtic_edf_pass::task::EdfTaskBinding>() -> u16 {
+                SCHEDULER.cancel_pending_jobs::<T>()
+            }
+
+            /// Run one iteration of the idle loop: call `sleep` with the scheduler's
+            /// idle hint, inside a critical section. See `Scheduler::idle`.
+            pub fn idle_sleep(sleep: impl FnOnce(::rtic_edf_pass::scheduler::IdleHint)) {
+                SCHEDULER.idle(sleep);
+            }
+
+            #mode_api
+            #poll_servers
+            #budget_api
+            #slack_api
+
+            #stats_accessor
+            #timings_accessor
+        }
+    }
+
+    /// Generate the budget bookkeeping of the scheduler and the budget timer
+    /// handling, if a task declares an execution budget or is served
+    fn generate_budget_items(&self) -> (TokenStream, TokenStream, TokenStream, TokenStream) {
+        let params = &self.app.app_parameters;
+        let (Some(timer), Some(overrun)) = (&params.budget_timer, &params.budget_overrun) else {
+            return Default::default();
+        };
+        if self
+            .app
+            .tasks
+            .iter()
+            .all(|t| t.budget_us.is_none() && t.server.is_none())
+        {
+            return Default::default();
+        }
+
+        (
+            quote! {
+                budgets: ::rtic_edf_pass::scheduler::Budgets<EDF_RUN_QUEUE_LEN>,
+            },
+            quote! { budgets: ::rtic_edf_pass::scheduler::Budgets::new(), },
+            quote! {
+                #[inline]
+                fn budgets(&self) -> Option<&::rtic_edf_pass::scheduler::Budgets<EDF_RUN_QUEUE_LEN>> {
+                    Some(&self.budgets)
+                }
+
+                unsafe fn start_budget_timer() {
+                    unsafe { <EdfBudgetTimer as ::rtic_edf_pass::time::BudgetTimer>::start() };
+                }
+
+                #[inline]
+                fn set_budget_timer(expiry: Option<::rtic_edf_pass::types::Timestamp>) {
+                    use ::rtic_edf_pass::time::BudgetTimer;
+
+                    match expiry {
+                        Some(expiry) => EdfBudgetTimer::arm(expiry),
+                        None => EdfBudgetTimer::disarm(),
+                    }
+                }
+            },
+            quote! {
+                /// Timer enforcing the execution budgets of the tasks
+                pub type EdfBudgetTimer = #timer;
+
+                /// Handle an expiry of the budget timer, calling the overrun handler if
+                /// the running job overran its budget, or if the budget of its server
+                /// ran out. Must be called from the budget timer's interrupt handler.
+                pub fn on_budget_timer() {
+                    if let Some(task) = SCHEDULER.budget_expired() {
+                        #overrun(task);
+                    }
+                }
+            },
+        )
+    }
+
+    /// Generate the worst-case demand of the tasks and the slack query, if every
+    /// task declares a WCET
+    fn generate_slack_items(&self) -> (TokenStream, TokenStream) {
+        if self
+            .app
+            .tasks
+            .iter()
+            .any(|t| t.analyzed_wcet_us().is_none())
+        {
+            return Default::default();
+        }
+
+        let idents = self.app.tasks.iter().map(|t| &t.task_struct.ident);
+        let spare_times = (0..self.app.num_modes()).map(|mode| {
+            let (min_us, rate_ppm, offset_us, blocking_us) =
+                match demand_test(&mode_analysis(&self.app, mode)) {
+                    Some(test) if test.schedulable => (
+                        test.min_spare_us.min(u32::MAX as u64) as u32,
+                        ((1.0 - test.utilization) * 1e6).floor().clamp(0.0, 1e6) as u32,
+                        test.spare_offset_us.ceil().min(u32::MAX as f64) as u32,
+                        test.max_blocking_us,
+                    ),
+                    _ => (0, 0, 0, 0),
+                };
+
+            quote! {
+                ::rtic_edf_pass::scheduler::SpareTime::new(
+                    ::rtic_edf_pass::time::us_to_ticks::<EdfTimeSource>(#min_us),
+                    #rate_ppm,
+                    ::rtic_edf_pass::time::us_to_ticks_ceil::<EdfTimeSource>(#offset_us),
+                    ::rtic_edf_pass::time::us_to_ticks_ceil::<EdfTimeSource>(#blocking_us),
+                )
+            }
+        });
+
+        (
+            quote! {
+                #[inline]
+                fn spare_time(mode: u16) -> Option<::rtic_edf_pass::scheduler::SpareTime> {
+                    Some(EDF_MODE_SPARE_TIME[mode as usize])
+                }
+
+                fn demand(
+                    &self,
+                    cs: &::rtic_edf_pass::scheduler::CriticalSection<'_>,
+                    window: ::rtic_edf_pass::types::Deadline,
+                ) -> Option<::rtic_edf_pass::types::Deadline> {
+                    [#(self.task_demand::<#idents>(cs, window),)*]
+                        .into_iter()
+                        .try_fold(0, |sum: ::rtic_edf_pass::types::Deadline, demand| {
+                            Some(sum.saturating_add(demand?))
+                        })
+                }
+            },
+            quote! {
+                /// Worst-case spare time of the tasks of every mode
+                const EDF_MODE_SPARE_TIME: [::rtic_edf_pass::scheduler::SpareTime; EDF_NUM_MODES] = [
+                    #(#spare_times,)*
+                ];
+
+                /// Returns how long the processor can be taken away from the tasks right
+                /// now without any job missing its deadline, in ticks of `EdfTimeSource`.
+                /// See `Scheduler::slack`.
+                pub fn slack() -> ::rtic_edf_pass::types::Deadline {
+                    SCHEDULER.slack()
+                }
+            },
+        )
+    }
+
+    /// Generate the mode enum and mode change API, if the app declares modes
+    fn generate_mode_api(&self) -> TokenStream {
+        let modes = &self.app.app_parameters.modes;
+        if modes.is_empty() {
+            return TokenStream::new();
+        }
+
+        let variants: Vec<_> = modes
+            .iter()
+            .map(|m| format_ident!("{}", m.to_string().to_upper_camel_case(), span = m.span()))
+            .collect();
+        let indices = 0..variants.len() as u16;
+
+        quote! {
+            /// Operating modes declared by the app
+            #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+            pub enum EdfMode {
+                #(#variants,)*
+            }
+
+            impl EdfMode {
+                const ALL: [EdfMode; EDF_NUM_MODES] = [#(EdfMode::#variants,)*];
+            }
+
+            /// Request a switch to `mode`, which happens at the next idle instant.
+            /// See `Scheduler::request_mode`.
+            pub fn request_mode(mode: EdfMode) {
+                let idx = match mode {
+                    #(EdfMode::#variants => #indices,)*
+                };
+                SCHEDULER.request_mode(idx);
+            }
+
+            /// Returns the current operating mode
+            pub fn current_mode() -> EdfMode {
+                EdfMode::ALL[SCHEDULER.modes().current() as usize]
+            }
         }
     }
 
@@ -147,7 +570,9 @@ impl CodeGen {
         self.app
             .tasks
             .iter()
-            .map(|t| t.generate_timestamper_binding(self.app.timestamper_priority))
+            .map(|t| {
+                t.generate_timestamper_binding(self.app.timestamper_priority, self.instrumentation)
+            })
             .collect()
     }
 
@@ -186,7 +611,8 @@ impl CodeGen {
                         const RUN_QUEUE_IDX: u16 = #rq_idx;
 
                         let task_to_run =  unsafe { #static_ident.assume_init_mut() };
-                        let deadline_to_restore = SCHEDULER.check_missed_deadline(RUN_QUEUE_IDX);
+                        SCHEDULER.dispatcher_entry::<#task_ident>();
+                        SCHEDULER.check_missed_deadline(RUN_QUEUE_IDX);
                         task_to_run.exec();
                         SCHEDULER.dispatcher_exit::<#task_ident>(RUN_QUEUE_IDX);
                     }
@@ -199,14 +625,90 @@ impl CodeGen {
 }
 
 impl EdfTask {
-    pub fn generate_timestamper_binding(&self, priority: u16) -> TokenStream {
+    pub fn generate_timestamper_binding(
+        &self,
+        priority: u16,
+        instrumentation: Instrumentation,
+    ) -> TokenStream {
         let binds = &self.timestamper_binding;
         let task_struct_ident = &self.task_struct.ident;
 
         let dispatcher_idx = self.dispatcher_idx;
         let rq_idx = self.rq_idx;
         let sched_task_ident = format_ident!("__edf_scheduler_signal_{task_struct_ident}");
-        let deadline_us = self.deadline_us;
+        let max_jobs = self.max_jobs();
+        let modes = self.modes_mask();
+
+        let (admit, server) = match self.server {
+            Some(server) => {
+                let (budget_us, period_us) = (server.budget_us, server.period_us);
+                (
+                    quote! {
+                        // Throttled arrivals are released again by `resume_servers`
+                        let now = <EdfTimeSource as ::rtic_edf_pass::time::TimeSource>::now();
+                        if #task_struct_ident::server().is_some_and(|s| !s.admit(now, &cs)) {
+                            return;
+                        }
+                    },
+                    quote! {
+                        #[inline]
+                        fn server() -> Option<&'static ::rtic_edf_pass::scheduler::Server> {
+                            static SERVER: ::rtic_edf_pass::scheduler::Server =
+                                ::rtic_edf_pass::scheduler::Server::new(
+                                    ::rtic_edf_pass::time::us_to_ticks::<EdfTimeSource>(#budget_us),
+                                    ::rtic_edf_pass::time::us_to_ticks::<EdfTimeSource>(#period_us),
+                                );
+                            Some(&SERVER)
+                        }
+                    },
+                )
+            }
+            None => Default::default(),
+        };
+
+        let budget = self.budget_us.map(|budget_us| {
+            quote! {
+                const BUDGET: Option<::rtic_edf_pass::types::Deadline> =
+                    Some(::rtic_edf_pass::time::us_to_ticks::<EdfTimeSource>(#budget_us));
+            }
+        });
+
+        let wcet = self.analyzed_wcet_us().map(|wcet_us| {
+            quote! {
+                const WCET: Option<::rtic_edf_pass::types::Deadline> =
+                    Some(::rtic_edf_pass::time::us_to_ticks_ceil::<EdfTimeSource>(#wcet_us));
+            }
+        });
+        let min_interarrival = self.analyzed_min_interarrival_us().map(|min_interarrival_us| {
+            quote! {
+                const MIN_INTERARRIVAL: Option<::rtic_edf_pass::types::Deadline> =
+                    Some(::rtic_edf_pass::time::us_to_ticks::<EdfTimeSource>(#min_interarrival_us));
+            }
+        });
+
+        // The stats and the response time histograms need the arrival time of
+        // every active job
+        let (record_arrival, arrivals) = if instrumentation.stats || instrumentation.response_times
+        {
+            let slots = Literal::usize_unsuffixed(max_jobs as usize);
+            (
+                quote! {
+                    #task_struct_ident::arrivals()
+                        .push(<EdfTimeSource as ::rtic_edf_pass::time::TimeSource>::now(), &cs);
+                },
+                quote! {
+                    #[inline]
+                    fn arrivals() -> &'static ::rtic_edf_pass::task::Arrivals {
+                        static ARRIVALS: ::rtic_edf_pass::task::Arrivals<
+                            [::core::sync::atomic::AtomicU32; #slots],
+                        > = ::rtic_edf_pass::task::Arrivals::new();
+                        &ARRIVALS
+                    }
+                },
+            )
+        } else {
+            Default::default()
+        };
 
         parse_quote! {
             #[task(priority = #priority, binds = #binds)]
@@ -226,10 +728,28 @@ impl EdfTask {
                     // SAFETY: we are running at the highest system priority.
                     let cs = unsafe { ::rtic_edf_pass::scheduler::CriticalSection::new() };
 
+                    // Outside of its modes, the task keeps its timestamper masked until a
+                    // mode change resumes it
+                    if !SCHEDULER.modes().releases(#task_struct_ident::MODES) {
+                        return;
+                    }
+
+                    #admit
+
+                    // Keep accepting arrivals until the task has as many active jobs as it
+                    // can overlap
+                    if #task_struct_ident::active_jobs().release(&cs) < #task_struct_ident::MAX_JOBS {
+                        unsafe { #task_struct_ident::unmask_timestamper_interrupt() };
+                    }
+
+                    #record_arrival
+
                     SCHEDULER.schedule(
                         cs,
                         ::rtic_edf_pass::task::Task::new(
-                            #deadline_us,
+                            SCHEDULER.deadlines().get(
+                                <#task_struct_ident as ::rtic_edf_pass::task::EdfTaskBinding>::RUN_QUEUE_IDX,
+                            ),
                             <#task_struct_ident as ::rtic_edf_pass::task::EdfTaskBinding>::DISPATCHER_IDX,
                             <#task_struct_ident as ::rtic_edf_pass::task::EdfTaskBinding>::RUN_QUEUE_IDX,
                         ),
@@ -242,6 +762,21 @@ impl EdfTask {
             impl ::rtic_edf_pass::task::EdfTaskBinding for #task_struct_ident {
                 const DISPATCHER_IDX: u16 = #dispatcher_idx;
                 const RUN_QUEUE_IDX: u16 = #rq_idx;
+                const MAX_JOBS: u16 = #max_jobs;
+                const MODES: u32 = #modes;
+                #budget
+                #wcet
+                #min_interarrival
+
+                #[inline]
+                fn active_jobs() -> &'static ::rtic_edf_pass::task::ActiveJobs {
+                    static ACTIVE_JOBS: ::rtic_edf_pass::task::ActiveJobs =
+                        ::rtic_edf_pass::task::ActiveJobs::new();
+                    &ACTIVE_JOBS
+                }
+
+                #arrivals
+                #server
 
                 #[inline]
                 unsafe fn unmask_timestamper_interrupt() {
@@ -0,0 +1,203 @@
+//! Processor demand analysis.
+//!
+//! The demand bound function of a task, `dbf_i(t) = max(0, floor((t - D_i) /
+//! T_i) + 1) * C_i`, is the longest execution time of its jobs which are both
+//! released and due within any interval of length `t`. It holds for
+//! constrained (`D <= T`) as well as arbitrary (`D > T`) deadlines. Under EDF
+//! with SRP, the task set is schedulable if, for every interval length `t`,
+//! `sum(dbf_i(t)) + B(t) <= t`, where `B(t)` is the longest critical section
+//! of a task with a deadline longer than `t` which can block a task with a
+//! deadline no longer than `t`.
+//!
+//! `T_i` is the minimum inter-arrival time of the task, which every task must
+//! declare for the test to run. The condition only needs checking at absolute
+//! deadlines (`t = D_i + k * T_i`), up to a bound derived from the
+//! utilization.
+//!
+//! The spare time `t - sum(dbf_i(t)) - B(t)` left by the tasks in an interval
+//! starting at an idle instant bounds how long the processor can be taken away
+//! from them, see [`Scheduler::slack`](crate::scheduler::Scheduler::slack).
+
+use serde::Serialize;
+
+use super::srp::SrpAnalysis;
+
+/// Intervals longer than this are not checked, which only matters for
+/// utilizations very close to 1
+const MAX_HORIZON_US: u64 = 60_000_000;
+
+/// Result of the processor demand test
+#[derive(Debug, Clone, Serialize)]
+pub struct DemandTest {
+    /// Sum of `C_i / T_i`
+    pub utilization: f64,
+    /// Longest interval checked
+    pub horizon_us: u64,
+    /// First interval in which the demand exceeds the available time
+    pub overload: Option<Overload>,
+    pub schedulable: bool,
+    /// Shortest spare time in an interval ending at an absolute deadline, or 0
+    /// if the tasks aren't schedulable
+    pub min_spare_us: u64,
+    /// `sum(max(0, (T_i - D_i) * U_i)) + max_blocking_us`: the spare time in an
+    /// interval of length `t` is at least `t * (1 - U) - spare_offset_us`
+    pub spare_offset_us: f64,
+    /// Longest critical section which can block a task, an upper bound of
+    /// `B(t)`
+    pub max_blocking_us: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Overload {
+    pub interval_us: u64,
+    /// Execution time of the jobs released and due within the interval
+    pub demand_us: u64,
+    pub blocking_us: u32,
+}
+
+struct TaskDemand {
+    deadline_us: u64,
+    period_us: u64,
+    wcet_us: u64,
+    /// Deadline of the blocking task and length of the critical section, for
+    /// every critical section which can block the task
+    blockers: Vec<(u64, u32)>,
+}
+
+impl TaskDemand {
+    fn dbf(&self, t: u64) -> u64 {
+        if t < self.deadline_us {
+            0
+        } else {
+            ((t - self.deadline_us) / self.period_us + 1) * self.wcet_us
+        }
+    }
+}
+
+/// Run the processor demand test. Returns `None` if a WCET, a minimum
+/// inter-arrival time or a blocking critical section length isn't annotated.
+pub fn demand_test(srp: &SrpAnalysis) -> Option<DemandTest> {
+    let tasks = srp
+        .tasks
+        .iter()
+        .map(|t| {
+            let blockers = t
+                .blockers
+                .iter()
+                .map(|b| {
+                    let blocker = srp.task(&b.task).unwrap();
+                    Some((blocker.deadline_us as u64, b.cs_us?))
+                })
+                .collect::<Option<Vec<_>>>()?;
+
+            Some(TaskDemand {
+                deadline_us: t.deadline_us as u64,
+                period_us: t.period_us()? as u64,
+                wcet_us: t.wcet_us? as u64,
+                blockers,
+            })
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    let utilization: f64 = tasks
+        .iter()
+        .map(|t| t.wcet_us as f64 / t.period_us as f64)
+        .sum();
+
+    let max_deadline = tasks.iter().map(|t| t.deadline_us).max().unwrap_or(0);
+    let min_deadline = tasks.iter().map(|t| t.deadline_us).min().unwrap_or(0);
+    let max_blocking = tasks
+        .iter()
+        .flat_map(|t| t.blockers.iter().map(|(_, cs)| *cs))
+        .max()
+        .unwrap_or(0);
+    let spare_offset_us: f64 = tasks
+        .iter()
+        .map(|t| {
+            ((t.period_us as f64 - t.deadline_us as f64) * t.wcet_us as f64 / t.period_us as f64)
+                .max(0.0)
+        })
+        .sum::<f64>()
+        + max_blocking as f64;
+
+    // Past `L`, sum(dbf_i(t)) + B(t) <= t * U + sum((T_i - D_i) * U_i) + B <= t
+    let horizon_us = if utilization < 1.0 {
+        let slack: f64 = tasks
+            .iter()
+            .map(|t| {
+                (t.period_us as f64 - t.deadline_us as f64) * t.wcet_us as f64 / t.period_us as f64
+            })
+            .sum();
+        let bound = ((slack + max_blocking as f64) / (1.0 - utilization)).ceil();
+        (bound.max(0.0) as u64).max(max_deadline)
+    } else {
+        MAX_HORIZON_US
+    }
+    .min(MAX_HORIZON_US);
+
+    // The shortest spare time is at most the one of the first deadline, which
+    // `t * (1 - U) - spare_offset_us` exceeds past this bound
+    let spare_horizon_us = if utilization < 1.0 {
+        let bound = ((spare_offset_us + min_deadline as f64) / (1.0 - utilization)).ceil();
+        bound as u64
+    } else {
+        MAX_HORIZON_US
+    }
+    .clamp(horizon_us, MAX_HORIZON_US);
+
+    let mut checkpoints: Vec<u64> = tasks
+        .iter()
+        .flat_map(|t| {
+            (t.deadline_us..=spare_horizon_us).step_by(t.period_us.try_into().unwrap_or(usize::MAX))
+        })
+        .collect();
+    checkpoints.sort_unstable();
+    checkpoints.dedup();
+
+    let demand = |t: u64| tasks.iter().map(|task| task.dbf(t)).sum::<u64>();
+    let blocking = |t: u64| {
+        tasks
+            .iter()
+            .filter(|task| task.deadline_us <= t)
+            .flat_map(|task| task.blockers.iter())
+            .filter(|(blocker_deadline, _)| *blocker_deadline > t)
+            .map(|(_, cs)| *cs)
+            .max()
+            .unwrap_or(0)
+    };
+
+    let overload = checkpoints
+        .iter()
+        .take_while(|&&t| t <= horizon_us)
+        .find_map(|&t| {
+            let demand_us = demand(t);
+            let blocking_us = blocking(t);
+
+            (demand_us + blocking_us as u64 > t).then_some(Overload {
+                interval_us: t,
+                demand_us,
+                blocking_us,
+            })
+        });
+
+    let schedulable = overload.is_none() && utilization <= 1.0;
+    let min_spare_us = if schedulable {
+        checkpoints
+            .iter()
+            .map(|&t| t.saturating_sub(demand(t) + blocking(t) as u64))
+            .min()
+            .unwrap_or(0)
+    } else {
+        0
+    };
+
+    Some(DemandTest {
+        utilization,
+        horizon_us,
+        overload,
+        schedulable,
+        min_spare_us,
+        spare_offset_us,
+        max_blocking_us: max_blocking,
+    })
+}
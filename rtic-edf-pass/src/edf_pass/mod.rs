@@ -1,25 +1,46 @@
+pub mod assignment;
 mod codegen;
+pub mod demand;
 // mod error;
+pub mod modes;
 mod parse;
+mod report;
+pub mod srp;
 
-use codegen::CodeGen;
+#[cfg(test)]
+mod tests;
+
+use codegen::{CodeGen, Instrumentation};
+use demand::demand_test;
 use parse::App;
 use proc_macro2::TokenStream;
+use report::ScheduleReport;
 use rtic_core::RticPass;
 use rtic_core::parse_utils::RticAttr;
-use syn::{ItemMod, parse_quote};
+use srp::SrpAnalysis;
+use syn::{ItemMod, Path, parse_quote};
 
 pub struct EdfPass {
     min_priority: u16,
     max_priority: u16,
+    default_time_source: Path,
+    instrumentation: Instrumentation,
 }
 
 impl EdfPass {
+    /// Create a new EDF pass.
+    ///
+    /// `default_time_source` is used when the app doesn't select one through
+    /// the `monotonic` argument. It must be a type implementing
+    /// [`TimeSource`](crate::time::TimeSource) with a single const generic
+    /// parameter, which receives the `cpu_freq` app argument.
     #[allow(clippy::new_without_default)]
-    pub fn new(min_priority: u16, max_priority: u16) -> Self {
+    pub fn new(min_priority: u16, max_priority: u16, default_time_source: Path) -> Self {
         Self {
             min_priority,
             max_priority,
+            default_time_source,
+            instrumentation: Instrumentation::from_features(),
         }
     }
 }
@@ -31,6 +52,35 @@ impl RticPass for EdfPass {
         let mut parsed = App::parse(self, &params, app_mod)?;
 
         self.analyze(&mut parsed);
+        let srp = SrpAnalysis::from_app(&parsed);
+        srp.verify(parsed.timestamper_priority)?;
+        for mode in 0..parsed.num_modes() {
+            let Some(test) = demand_test(&modes::mode_analysis(&parsed, mode)) else {
+                continue;
+            };
+            if test.schedulable {
+                continue;
+            }
+
+            let reason = match test.overload {
+                Some(o) => format!(
+                    "{} us of demand and {} us of blocking in an interval of {} us",
+                    o.demand_us, o.blocking_us, o.interval_us
+                ),
+                None => format!("utilization of {:.3}", test.utilization),
+            };
+            let mode = match parsed.app_parameters.modes.get(mode) {
+                Some(name) => format!(" in mode `{name}`"),
+                None => String::new(),
+            };
+            eprintln!(
+                "warning: the EDF tasks of `{}` may miss their deadlines{mode}: {reason}",
+                parsed.mod_ident
+            );
+        }
+        if std::env::var_os(report::REPORT_ENV_VAR).is_some() {
+            ScheduleReport::new(&parsed).write();
+        }
 
         for task in parsed.tasks.iter_mut() {
             let priority = task.dispatcher_priority;
@@ -39,7 +89,7 @@ impl RticPass for EdfPass {
             let _ = task.params.elements.insert("priority".into(), expr);
         }
 
-        let code = CodeGen::new(parsed).run();
+        let code = CodeGen::new(parsed, self.instrumentation).run();
         Ok((args, code))
     }
 
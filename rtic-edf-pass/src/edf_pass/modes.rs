@@ -0,0 +1,126 @@
+//! Operating modes.
+//!
+//! An app may declare named modes with the `modes = [normal, degraded, ...]`
+//! argument, the first one being the initial mode. Tasks are active in every
+//! mode, unless they list the modes they are active in, optionally with a
+//! deadline overriding `deadline_us` in that mode:
+//! `modes = [normal, degraded = 20_000]`.
+//!
+//! Priorities are assigned from the `deadline_us` of the tasks, and don't
+//! change with the mode. In every mode, the deadlines of the priority levels
+//! must therefore keep the order of the declared deadlines, and the active
+//! tasks sharing a priority level must share their deadline. The priority
+//! levels without an active task keep their declared deadline.
+
+use proc_macro2::Ident;
+
+use crate::types::Deadline;
+
+use super::{
+    parse::{App, EdfTask},
+    srp::{SrpAnalysis, SrpTask},
+};
+
+/// Returns the relative deadline of every priority level in `mode`, by run
+/// queue index, or an error if they don't keep the priority order
+pub fn level_deadlines(
+    tasks: &[EdfTask],
+    modes: &[Ident],
+    mode: usize,
+) -> syn::Result<Vec<Deadline>> {
+    let num_levels = tasks
+        .iter()
+        .map(|t| t.rq_idx as usize + 1)
+        .max()
+        .unwrap_or(0);
+    let mode_name = || {
+        modes
+            .get(mode)
+            .map_or(String::new(), |m| format!(" in mode `{m}`"))
+    };
+
+    // Task setting the deadline of every level, if any is active
+    let mut level_tasks: Vec<Option<&EdfTask>> = vec![None; num_levels];
+    let mut deadlines: Vec<Deadline> = vec![0; num_levels];
+
+    for task in tasks {
+        let level = task.rq_idx as usize;
+
+        let Some(deadline) = task.mode_deadlines[mode] else {
+            if level_tasks[level].is_none() {
+                deadlines[level] = task.deadline_us;
+            }
+            continue;
+        };
+
+        if let Some(other) = level_tasks[level]
+            && deadlines[level] != deadline
+        {
+            return Err(syn::Error::new(
+                task.task_struct.ident.span(),
+                format!(
+                    "Tasks `{}` and `{}` share a priority level, but have different deadlines{} ({} us and {} us)",
+                    other.task_struct.ident,
+                    task.task_struct.ident,
+                    mode_name(),
+                    deadlines[level],
+                    deadline
+                ),
+            ));
+        }
+
+        level_tasks[level] = Some(task);
+        deadlines[level] = deadline;
+    }
+
+    // Run queue index 0 is the lowest priority, and must have the longest
+    // deadline
+    for level in 1..num_levels {
+        if deadlines[level] < deadlines[level - 1] {
+            continue;
+        }
+
+        let task_at = |level: usize| {
+            level_tasks[level]
+                .or_else(|| tasks.iter().find(|t| t.rq_idx as usize == level))
+                .unwrap()
+        };
+        let (urgent, other) = (task_at(level), task_at(level - 1));
+
+        return Err(syn::Error::new(
+            urgent.task_struct.ident.span(),
+            format!(
+                "The deadline of task `{}`{} ({} us) must stay shorter than the one of task `{}` ({} us), which has a lower priority",
+                urgent.task_struct.ident,
+                mode_name(),
+                deadlines[level],
+                other.task_struct.ident,
+                deadlines[level - 1]
+            ),
+        ));
+    }
+
+    Ok(deadlines)
+}
+
+/// SRP analysis of the tasks active in `mode`, with their deadline in that
+/// mode
+pub fn mode_analysis(app: &App, mode: usize) -> SrpAnalysis {
+    let tasks: Vec<_> = app
+        .tasks
+        .iter()
+        .filter_map(|t| {
+            Some(SrpTask {
+                name: t.task_struct.ident.clone(),
+                deadline_us: t.mode_deadlines[mode]?,
+                priority: t.dispatcher_priority,
+                shared: t.shared.clone(),
+                min_interarrival_us: t.analyzed_min_interarrival_us(),
+                wcet_us: t.analyzed_wcet_us(),
+                cs_us: t.cs_us.clone(),
+            })
+        })
+        .collect();
+
+    SrpAnalysis::new(&tasks, &app.shared_resources())
+}
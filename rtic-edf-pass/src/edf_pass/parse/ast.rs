@@ -1,3 +1,4 @@
+use proc_macro2::Ident;
 use rtic_core::parse_utils::RticAttr;
 use syn::{Expr, ItemStruct, Lit, Path};
 
@@ -6,7 +7,17 @@ use crate::types::Deadline;
 pub struct AppParameters {
     pub dispatchers: Vec<Path>,
     pub pac_path: Path,
-    pub _cpu_freq: u32,
+    pub cpu_freq: u32,
+    /// User-selected time source, if any
+    pub monotonic: Option<Path>,
+    /// Timer enforcing the execution budgets, if any
+    pub budget_timer: Option<Path>,
+    /// Function called with the dispatcher index of a task which overran its
+    /// execution budget, if any
+    pub budget_overrun: Option<Path>,
+    /// Operating modes, the initial one first. Empty if the app doesn't
+    /// declare any.
+    pub modes: Vec<Ident>,
 }
 
 impl AppParameters {
@@ -41,10 +52,60 @@ impl AppParameters {
             panic!("`cpu_freq` must be a integer literal representing the CPU frequency in Hertz")
         });
 
+        let monotonic = match args.elements.get("monotonic") {
+            Some(Expr::Path(p)) => Some(p.path.clone()),
+            Some(_) => panic!("`monotonic` must be a path to a type implementing `TimeSource`"),
+            None => None,
+        };
+
+        let budget_timer = match args.elements.get("budget_timer") {
+            Some(Expr::Path(p)) => Some(p.path.clone()),
+            Some(_) => panic!("`budget_timer` must be a path to a type implementing `BudgetTimer`"),
+            None => None,
+        };
+
+        let budget_overrun = match args.elements.get("budget_overrun") {
+            Some(Expr::Path(p)) => Some(p.path.clone()),
+            Some(_) => panic!("`budget_overrun` must be a path to a `fn(u16)` overrun handler"),
+            None => None,
+        };
+
+        let mut modes = vec![];
+        match args.elements.get("modes") {
+            Some(Expr::Array(array)) => {
+                for e in array.elems.iter() {
+                    let Some(mode) = expr_ident(e) else {
+                        panic!("`modes` must be a list of mode names");
+                    };
+
+                    if modes.contains(mode) {
+                        return Err(syn::Error::new(
+                            mode.span(),
+                            format!("Mode `{mode}` is declared more than once"),
+                        ));
+                    }
+                    modes.push(mode.clone());
+                }
+
+                if modes.is_empty() || modes.len() > u32::BITS as usize {
+                    return Err(syn::Error::new_spanned(
+                        array,
+                        format!("Apps must declare between 1 and {} modes", u32::BITS),
+                    ));
+                }
+            }
+            Some(_) => panic!("`modes` must be a list of mode names"),
+            None => {}
+        }
+
         Ok(Self {
             dispatchers: dispatcher_vec,
             pac_path: pac_path.path.clone(),
-            _cpu_freq: cpu_freq,
+            cpu_freq,
+            monotonic,
+            budget_timer,
+            budget_overrun,
+            modes,
         })
     }
 }
@@ -57,20 +118,91 @@ pub struct TaskStructDef {
     pub deadline_us: Deadline,
     /// Interrupt handler signalling task arrival
     pub binds: Path,
+    /// Shared resources accessed by the task
+    pub shared: Vec<Ident>,
+    /// Minimum time between two arrivals, if declared
+    pub min_interarrival_us: Option<u32>,
+    /// Worst-case execution time, if annotated
+    pub wcet_us: Option<u32>,
+    /// Length of the longest critical section on each annotated resource
+    pub cs_us: Vec<(Ident, u32)>,
+    /// Modes in which the task is active, with the deadline overriding
+    /// `deadline_us` in that mode, if any. `None` if the task is active in
+    /// every mode.
+    pub modes: Option<Vec<(Ident, Option<Deadline>)>>,
+    /// Constant bandwidth server of an aperiodic task
+    pub server: Option<ServerParams>,
+    /// Execution budget of every job, enforced at runtime
+    pub budget_us: Option<u32>,
+}
+
+/// Budget and period of a constant bandwidth server, declared on a task as
+/// `server = [budget_us = 500, period_us = 10_000]`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ServerParams {
+    pub budget_us: u32,
+    pub period_us: u32,
+}
+
+impl ServerParams {
+    fn parse(expr: &Expr, task: &Ident) -> Self {
+        let Expr::Array(array) = expr else {
+            panic!("`server` must be of the form `[budget_us = <budget>, period_us = <period>]`");
+        };
+
+        let (mut budget_us, mut period_us) = (None, None);
+        for e in array.elems.iter() {
+            let Expr::Assign(assign) = e else {
+                panic!("`server` entries must be of the form `key = <value in us>`");
+            };
+
+            let value = int_lit(&assign.right).unwrap_or_else(|| {
+                panic!("The server parameters of task `{task}` must be integer literals, in microseconds")
+            });
+            match expr_ident(&assign.left).map(ToString::to_string).as_deref() {
+                Some("budget_us") => budget_us = Some(value),
+                Some("period_us") => period_us = Some(value),
+                _ => panic!("Unknown server parameter, expected `budget_us` or `period_us`"),
+            }
+        }
+
+        let (Some(budget_us), Some(period_us)) = (budget_us, period_us) else {
+            panic!("The server of task `{task}` must specify `budget_us` and `period_us`");
+        };
+        assert!(
+            budget_us > 0 && budget_us <= period_us,
+            "The server budget of task `{task}` must be greater than 0, and no longer than its period"
+        );
+
+        Self {
+            budget_us,
+            period_us,
+        }
+    }
 }
 
 impl TaskStructDef {
     pub fn from_struct((task_struct, attr_idx): (ItemStruct, usize)) -> syn::Result<Self> {
         let mut params = RticAttr::parse_from_attr(&task_struct.attrs[attr_idx])?;
 
+        let server = match params.elements.remove("server") {
+            Some(expr) => Some(ServerParams::parse(&expr, &task_struct.ident)),
+            None => None,
+        };
+
         let deadline_us = if let Some(Expr::Lit(syn::ExprLit {
             lit: Lit::Int(int), ..
         })) = params.elements.get("deadline_us")
         {
+            assert!(
+                server.is_none(),
+                "Task `{}` is served by a server, whose period is its deadline. Please remove `deadline_us`.",
+                task_struct.ident
+            );
             // deadline explicitly assigned by the user
             int.base10_parse().ok()
         } else {
-            None
+            server.as_ref().map(|s| s.period_us)
         }
         .expect("EDF tasks must specify a deadline via the `deadline_us` attribute");
 
@@ -89,12 +221,197 @@ impl TaskStructDef {
         };
         let binds = binds.path.clone();
 
+        // Left in the task parameters for the core pass to handle
+        let mut shared = vec![];
+        if let Some(Expr::Array(array)) = params.elements.get("shared") {
+            for e in array.elems.iter() {
+                match e {
+                    Expr::Path(p) if p.path.get_ident().is_some() => {
+                        shared.push(p.path.get_ident().unwrap().clone());
+                    }
+                    _ => panic!("`shared` must be a list of shared resource names"),
+                }
+            }
+        }
+
+        let min_interarrival_us = params.elements.remove("min_interarrival_us").map(|expr| {
+            let t = int_lit(&expr)
+                .expect("`min_interarrival_us` must be an integer literal, in microseconds");
+            assert!(t > 0, "`min_interarrival_us` must be greater than 0");
+            t
+        });
+
+        let overlapping_jobs = match params.elements.remove("overlapping_jobs") {
+            Some(Expr::Lit(syn::ExprLit {
+                lit: Lit::Bool(b), ..
+            })) => b.value,
+            Some(_) => panic!("`overlapping_jobs` must be `true` or `false`"),
+            None => false,
+        };
+
+        // Timing annotations are only used by the analysis
+        let wcet_us = params.elements.remove("wcet_us").map(|expr| {
+            int_lit(&expr).expect("`wcet_us` must be an integer literal, in microseconds")
+        });
+
+        let budget_us = params.elements.remove("budget_us").map(|expr| {
+            let budget =
+                int_lit(&expr).expect("`budget_us` must be an integer literal, in microseconds");
+            assert!(budget > 0, "`budget_us` must be greater than 0");
+
+            if let Some(wcet) = wcet_us {
+                assert!(
+                    budget >= wcet,
+                    "The budget of task `{}` ({budget} us) is shorter than its WCET ({wcet} us)",
+                    task_struct.ident
+                );
+            }
+            budget
+        });
+
+        // The analysis models a served task as a sporadic task with the server budget
+        // as WCET, and the server period as deadline and minimum inter-arrival time.
+        // Its jobs never overlap.
+        if let Some(server) = &server {
+            assert!(
+                min_interarrival_us.is_none(),
+                "Task `{}` is served by a server, whose period is its deadline and minimum inter-arrival time. Please remove `min_interarrival_us`.",
+                task_struct.ident
+            );
+            assert!(
+                budget_us.is_none(),
+                "Task `{}` is served by a server, which enforces its budget. Please remove `budget_us`.",
+                task_struct.ident
+            );
+
+            let Some(wcet) = wcet_us else {
+                panic!(
+                    "Task `{}` is served by a server, and must declare its WCET with `wcet_us`",
+                    task_struct.ident
+                );
+            };
+            assert!(
+                server.budget_us >= wcet,
+                "The server budget of task `{}` ({} us) is shorter than its WCET ({wcet} us)",
+                task_struct.ident,
+                server.budget_us
+            );
+        }
+
+        let mut cs_us = vec![];
+        match params.elements.remove("cs_us") {
+            Some(Expr::Array(array)) => {
+                for e in array.elems.iter() {
+                    let Expr::Assign(assign) = e else {
+                        panic!("`cs_us` entries must be of the form `resource = <length in us>`");
+                    };
+
+                    let resource = match &*assign.left {
+                        Expr::Path(p) if p.path.get_ident().is_some() => {
+                            p.path.get_ident().unwrap().clone()
+                        }
+                        _ => panic!(
+                            "`cs_us` entries must be of the form `resource = <length in us>`"
+                        ),
+                    };
+                    let len = int_lit(&assign.right).unwrap_or_else(|| {
+                        panic!("The critical section length of `{resource}` must be an integer literal, in microseconds")
+                    });
+
+                    assert!(
+                        shared.contains(&resource),
+                        "Task `{}` has a critical section on `{resource}`, which isn't in its `shared` resources",
+                        task_struct.ident
+                    );
+
+                    if let Some(wcet) = wcet_us {
+                        assert!(
+                            len <= wcet,
+                            "The critical section of task `{}` on `{resource}` ({len} us) is longer than its WCET ({wcet} us)",
+                            task_struct.ident
+                        );
+                    }
+
+                    cs_us.push((resource, len));
+                }
+            }
+            Some(_) => panic!("`cs_us` must be a list of `resource = <length in us>` entries"),
+            None => {}
+        }
+
+        let modes = match params.elements.remove("modes") {
+            Some(Expr::Array(array)) => Some(
+                array
+                    .elems
+                    .iter()
+                    .map(|e| match e {
+                        Expr::Assign(assign) => {
+                            let (Some(mode), Some(deadline)) =
+                                (expr_ident(&assign.left), int_lit(&assign.right))
+                            else {
+                                panic!("`modes` entries must be of the form `mode` or `mode = <deadline in us>`");
+                            };
+                            (mode.clone(), Some(deadline))
+                        }
+                        _ => match expr_ident(e) {
+                            Some(mode) => (mode.clone(), None),
+                            None => panic!("`modes` entries must be of the form `mode` or `mode = <deadline in us>`"),
+                        },
+                    })
+                    .collect(),
+            ),
+            Some(_) => panic!("`modes` must be a list of `mode` or `mode = <deadline in us>` entries"),
+            None => None,
+        };
+
+        // The timestamper of a task whose jobs overlap stays unmasked while its jobs
+        // run, so a level-triggered interrupt source would release a job again as
+        // soon as the timestamper returns
+        if let (Some(t), false) = (min_interarrival_us, overlapping_jobs) {
+            let mode_deadlines = modes
+                .iter()
+                .flatten()
+                .filter_map(|mode: &(Ident, Option<Deadline>)| mode.1);
+            if let Some(d) = std::iter::once(deadline_us)
+                .chain(mode_deadlines)
+                .find(|&d| d > t)
+            {
+                panic!(
+                    "The jobs of task `{}` can overlap, since its deadline ({d} us) is longer than its minimum inter-arrival time ({t} us). Its interrupt source must then not stay pending once the timestamper returns (for example, a software-pended or edge-triggered interrupt). If so, opt in with `overlapping_jobs = true`.",
+                    task_struct.ident
+                );
+            }
+        }
+
         Ok(Self {
             params,
             attr_idx,
             task_struct,
             deadline_us,
             binds,
+            shared,
+            min_interarrival_us,
+            wcet_us,
+            cs_us,
+            modes,
+            server,
+            budget_us,
         })
     }
 }
+
+fn expr_ident(expr: &Expr) -> Option<&Ident> {
+    match expr {
+        Expr::Path(p) => p.path.get_ident(),
+        _ => None,
+    }
+}
+
+fn int_lit(expr: &Expr) -> Option<u32> {
+    match expr {
+        Expr::Lit(syn::ExprLit {
+            lit: Lit::Int(int), ..
+        }) => int.base10_parse().ok(),
+        _ => None,
+    }
+}
@@ -1,14 +1,22 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
-use crate::{EdfPass, edf_pass::parse::ast::AppParameters, types::Deadline};
+use crate::{
+    EdfPass,
+    edf_pass::{assignment::assign_priorities, modes, parse::ast::AppParameters},
+    types::Deadline,
+};
 
-use super::parse::ast::TaskStructDef;
+use super::parse::ast::{ServerParams, TaskStructDef};
 use proc_macro2::Ident;
 use rtic_core::parse_utils::RticAttr;
-use syn::{Item, ItemMod, ItemStruct, Path, Visibility};
+use syn::{Item, ItemMod, ItemStruct, Path, Type, Visibility, parse_quote};
 
 pub mod ast;
 
+/// Set this environment variable when building to print the priority
+/// assignment of every task
+const VERBOSE_ENV_VAR: &str = "RTIC_EDF_VERBOSE";
+
 #[derive(Debug, Clone)]
 pub struct EdfTask {
     pub params: RticAttr,
@@ -26,6 +34,66 @@ pub struct EdfTask {
     pub dispatcher: Path,
     /// Interrupt handler signalling task arrival (aka timestamper)
     pub timestamper_binding: Path,
+    /// Shared resources accessed by the task
+    pub shared: Vec<Ident>,
+    /// Minimum time between two arrivals, if declared
+    pub min_interarrival_us: Option<u32>,
+    /// Worst-case execution time, if annotated
+    pub wcet_us: Option<u32>,
+    /// Length of the longest critical section on each annotated resource
+    pub cs_us: Vec<(Ident, u32)>,
+    /// Deadline of the task in every mode, or `None` in the modes where it
+    /// isn't active
+    pub mode_deadlines: Vec<Option<Deadline>>,
+    /// Constant bandwidth server of an aperiodic task
+    pub server: Option<ServerParams>,
+    /// Execution budget of every job, enforced at runtime
+    pub budget_us: Option<u32>,
+}
+
+impl EdfTask {
+    /// Returns the maximum number of jobs of the task which can be active at
+    /// once, over every mode it is active in. Without a minimum inter-arrival
+    /// time, a job must complete before the next one arrives.
+    pub fn max_jobs(&self) -> u16 {
+        self.mode_deadlines
+            .iter()
+            .flatten()
+            .map(|&d| max_jobs(d, self.min_interarrival_us))
+            .max()
+            .unwrap_or(1)
+    }
+
+    /// Returns the WCET the analysis uses for every job: the server budget of a
+    /// served task, which its jobs can't exceed without the overrun handler
+    /// being called
+    pub fn analyzed_wcet_us(&self) -> Option<u32> {
+        self.server.map_or(self.wcet_us, |s| Some(s.budget_us))
+    }
+
+    /// Returns the minimum inter-arrival time the analysis uses: the server
+    /// period of a served task
+    pub fn analyzed_min_interarrival_us(&self) -> Option<u32> {
+        self.server
+            .map_or(self.min_interarrival_us, |s| Some(s.period_us))
+    }
+
+    /// Returns the bit set of the modes in which the task is active
+    pub fn modes_mask(&self) -> u32 {
+        self.mode_deadlines
+            .iter()
+            .enumerate()
+            .filter(|(_, d)| d.is_some())
+            .fold(0, |mask, (mode, _)| mask | 1 << mode)
+    }
+}
+
+/// `ceil(deadline / min inter-arrival time)`, at least 1
+pub fn max_jobs(deadline_us: Deadline, min_interarrival_us: Option<u32>) -> u16 {
+    min_interarrival_us
+        .map_or(1, |t| deadline_us.div_ceil(t).max(1))
+        .try_into()
+        .expect("Unsupported number of overlapping jobs: over u16::MAX")
 }
 
 /// Type to represent an RTIC application for deadline to priority conversion
@@ -36,6 +104,8 @@ pub struct App {
     pub tasks: Vec<EdfTask>,
     pub rest_of_code: Vec<Item>,
     pub timestamper_priority: u16,
+    /// Time source used to timestamp task arrivals
+    pub time_source: Type,
 }
 
 impl App {
@@ -67,14 +137,38 @@ impl App {
             .map(TaskStructDef::from_struct)
             .collect::<syn::Result<Vec<_>>>()?;
 
-        assert!(
-            app_parameters.dispatchers.len() >= task_defs.len(),
-            "The EDF scheduler needs at least as many dispatchers as there are tasks ({} tasks, {} dispatchers).",
-            task_defs.len(),
-            app_parameters.dispatchers.len()
-        );
+        let tasks = Self::assign_dispatchers_and_priorities(
+            task_defs,
+            &app_parameters.dispatchers,
+            &app_parameters.modes,
+        )?;
+
+        let time_source = match &app_parameters.monotonic {
+            Some(monotonic) => parse_quote!(#monotonic),
+            None => {
+                let default = &edf_pass.default_time_source;
+                let cpu_freq = app_parameters.cpu_freq;
+                parse_quote!(#default<#cpu_freq>)
+            }
+        };
+
+        if let Some(task) = tasks
+            .iter()
+            .find(|t| t.budget_us.is_some() || t.server.is_some())
+            && (app_parameters.budget_timer.is_none() || app_parameters.budget_overrun.is_none())
+        {
+            return Err(syn::Error::new(
+                task.task_struct.ident.span(),
+                format!(
+                    "Task `{}` declares an execution budget or a server, which requires the `budget_timer` and `budget_overrun` app arguments",
+                    task.task_struct.ident
+                ),
+            ));
+        }
 
-        let tasks = Self::assign_dispatchers_and_priorities(task_defs, &app_parameters.dispatchers);
+        for mode in 0..app_parameters.modes.len().max(1) {
+            modes::level_deadlines(&tasks, &app_parameters.modes, mode)?;
+        }
 
         Ok(Self {
             mod_ident: app_mod.ident,
@@ -83,6 +177,7 @@ impl App {
             tasks,
             rest_of_code,
             timestamper_priority: edf_pass.max_priority,
+            time_source,
         })
     }
 
@@ -91,81 +186,152 @@ impl App {
         self.tasks.iter().map(|t| t.dispatcher_priority).collect()
     }
 
-    /// Returns the length of the generated wait queue.
+    /// Returns the length of the generated wait queue: the maximum number of
+    /// jobs which can be active at once, summed over every task.
     ///
-    /// (wait queue length) = (number of tasks in the system) - (number of
-    /// unique priorities)
-    ///
-    /// This works because for each priority level, we can bypass the queue one
-    /// time before having the enqueue an arriving task, by pending the task
-    /// directly in its dispatcher.
+    /// For each priority level, we can bypass the queue one time before having
+    /// the enqueue an arriving task, by pending the task directly in its
+    /// dispatcher. The queue is nonetheless sized for every active job, so that
+    /// it never runs out of space.
     pub fn wait_queue_len(&self) -> usize {
-        self.tasks.len()
+        self.tasks.iter().map(|t| t.max_jobs() as usize).sum()
+    }
+
+    /// Returns the number of modes. Apps which don't declare any have a single
+    /// one.
+    pub fn num_modes(&self) -> usize {
+        self.app_parameters.modes.len().max(1)
+    }
+
+    /// Returns the relative deadline of every priority level in `mode`, by run
+    /// queue index
+    pub fn level_deadlines(&self, mode: usize) -> Vec<Deadline> {
+        modes::level_deadlines(&self.tasks, &self.app_parameters.modes, mode)
+            .expect("Mode deadlines are checked when parsing")
+    }
+
+    /// Returns the names of the fields of the `#[shared]` struct, in
+    /// declaration order
+    pub fn shared_resources(&self) -> Vec<Ident> {
+        self.rest_of_code
+            .iter()
+            .find_map(|item| match item {
+                Item::Struct(strct) if is_struct_with_attr(strct, "shared").is_some() => {
+                    Some(strct)
+                }
+                _ => None,
+            })
+            .map(|strct| {
+                strct
+                    .fields
+                    .iter()
+                    .filter_map(|f| f.ident.clone())
+                    .collect()
+            })
+            .unwrap_or_default()
     }
 
     fn assign_dispatchers_and_priorities(
         tasks: Vec<TaskStructDef>,
         dispatchers: &[Path],
-    ) -> Vec<EdfTask> {
-        use itertools::Itertools;
+        modes: &[Ident],
+    ) -> syn::Result<Vec<EdfTask>> {
+        let deadlines: Vec<_> = tasks
+            .iter()
+            .map(|t| (t.task_struct.ident.clone(), t.deadline_us))
+            .collect();
+        let assignment = assign_priorities(&deadlines, dispatchers);
 
-        let mut sorted_tasks = tasks;
-        sorted_tasks.sort_by_key(|t| t.deadline_us);
-        sorted_tasks.reverse();
+        if std::env::var_os(VERBOSE_ENV_VAR).is_some() {
+            eprint!("{assignment}");
+        }
 
-        // Get windows of identical deadlines and convert those to priorities
-        let prio_groups = std::iter::once(true)
-            .chain(
-                sorted_tasks
-                    .iter()
-                    .tuple_windows()
-                    .map(|(a, b)| a.deadline_us != b.deadline_us),
-            )
-            .scan(0, |acc, is_new| {
-                if is_new {
-                    *acc += 1;
-                }
-                Some(*acc)
-            })
-            .collect::<Vec<_>>();
+        let mut tasks: HashMap<_, _> = tasks
+            .into_iter()
+            .map(|t| (t.task_struct.ident.clone(), t))
+            .collect();
 
-        let tasks = sorted_tasks
+        assignment
+            .tasks
             .into_iter()
-            .enumerate()
-            .zip(prio_groups)
-            .zip(dispatchers)
-            .map(|(((dispatcher_idx, task), prio), dispatcher_path)| {
-                // Subtract 1 such that the minimum index is 0
-                let rq_idx = prio - 1;
-                let priority = prio;
-
-                EdfTask {
+            .map(|a| {
+                let task = tasks
+                    .remove(&a.name)
+                    .unwrap_or_else(|| panic!("Task `{}` is defined more than once", a.name));
+                let mode_deadlines = mode_deadlines(&task, modes)?;
+
+                Ok(EdfTask {
                     params: task.params,
                     attr_idx: task.attr_idx,
                     task_struct: task.task_struct,
-                    dispatcher_priority: priority,
-                    rq_idx,
-                    dispatcher_idx: dispatcher_idx
-                        .try_into()
-                        .expect("Unsupported dispatcher priority level: over u16::MAX"),
-                    dispatcher: dispatcher_path.clone(),
+                    dispatcher_priority: a.priority,
+                    rq_idx: a.rq_idx,
+                    dispatcher_idx: a.dispatcher_idx,
+                    dispatcher: a.dispatcher,
                     deadline_us: task.deadline_us,
                     timestamper_binding: task.binds,
-                }
+                    shared: task.shared,
+                    min_interarrival_us: task.min_interarrival_us,
+                    wcet_us: task.wcet_us,
+                    cs_us: task.cs_us,
+                    mode_deadlines,
+                    server: task.server,
+                    budget_us: task.budget_us,
+                })
             })
-            .collect::<Vec<_>>();
-
-        for t in tasks.iter() {
-            eprintln!(
-                "Task:\n\tdeadline: {}\n\ttimestamper: {}\n\tdispatcher: {}",
-                t.deadline_us,
-                t.timestamper_binding.get_ident().unwrap(),
-                t.dispatcher.get_ident().unwrap(),
-            );
+            .collect()
+    }
+}
+
+/// Resolve the deadline of a task in every mode
+fn mode_deadlines(task: &TaskStructDef, modes: &[Ident]) -> syn::Result<Vec<Option<Deadline>>> {
+    let name = &task.task_struct.ident;
+
+    let Some(task_modes) = &task.modes else {
+        return Ok(vec![Some(task.deadline_us); modes.len().max(1)]);
+    };
+
+    if modes.is_empty() {
+        return Err(syn::Error::new(
+            name.span(),
+            format!("Task `{name}` declares modes, but the app doesn't declare any"),
+        ));
+    }
+
+    let mut deadlines = vec![None; modes.len()];
+    for (mode, deadline) in task_modes {
+        let Some(idx) = modes.iter().position(|m| m == mode) else {
+            return Err(syn::Error::new(
+                mode.span(),
+                format!("Task `{name}` is active in undeclared mode `{mode}`"),
+            ));
+        };
+
+        if deadlines[idx].is_some() {
+            return Err(syn::Error::new(
+                mode.span(),
+                format!("Task `{name}` lists mode `{mode}` more than once"),
+            ));
         }
+        if deadline.is_some() && task.server.is_some() {
+            return Err(syn::Error::new(
+                mode.span(),
+                format!(
+                    "Task `{name}` is served by a server, whose period is its deadline in every mode"
+                ),
+            ));
+        }
+        deadlines[idx] = Some(deadline.unwrap_or(task.deadline_us));
+    }
 
-        tasks
+    if deadlines.iter().all(Option::is_none) {
+        return Err(syn::Error::new(
+            name.span(),
+            format!("Task `{name}` isn't active in any mode"),
+        ));
     }
+
+    Ok(deadlines)
 }
 
 /// returns the index of the `attr_name` attribute if found in the attribute
@@ -0,0 +1,271 @@
+//! Machine-readable summary of the schedule computed by the EDF pass.
+//!
+//! When the `RTIC_EDF_SCHEDULE_REPORT` environment variable is set, the report
+//! is written as JSON into the `target` directory each time the pass runs, so
+//! that scheduling configurations can be diffed between builds.
+
+use std::{env, fs, path::PathBuf};
+
+use quote::ToTokens;
+use serde::Serialize;
+
+use super::{
+    demand::{DemandTest, demand_test},
+    modes::mode_analysis,
+    parse::App,
+    srp::{Schedulability, SrpAnalysis},
+};
+use crate::types::Deadline;
+
+/// Set this environment variable when building to write the schedule report
+pub const REPORT_ENV_VAR: &str = "RTIC_EDF_SCHEDULE_REPORT";
+
+#[derive(Debug, Serialize)]
+pub struct ScheduleReport {
+    pub app: String,
+    pub time_source: String,
+    pub timestamper_priority: u16,
+    pub run_queue_len: usize,
+    pub wait_queue_len: usize,
+    /// Tasks, sorted by dispatcher index
+    pub tasks: Vec<TaskReport>,
+    /// Shared resources, in declaration order
+    pub resources: Vec<ResourceReport>,
+    /// Baker's EDF+SRP test, if every WCET and blocking critical section is
+    /// annotated
+    pub schedulability: Option<Schedulability>,
+    /// Processor demand test, under the same conditions
+    pub demand: Option<DemandTest>,
+    /// Operating modes, if the app declares any
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub modes: Vec<ModeReport>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ModeReport {
+    pub name: String,
+    /// Relative deadline of every priority level, by run queue index
+    pub level_deadlines_us: Vec<Deadline>,
+    /// Tasks active in the mode, with their deadline in that mode
+    pub tasks: Vec<ModeTaskReport>,
+    /// Processor demand test of the mode
+    pub demand: Option<DemandTest>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ModeTaskReport {
+    pub name: String,
+    pub deadline_us: Deadline,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TaskReport {
+    pub name: String,
+    pub deadline_us: Deadline,
+    pub min_interarrival_us: Option<u32>,
+    /// Maximum number of active jobs
+    pub max_jobs: u16,
+    pub priority: u16,
+    pub rq_idx: u16,
+    pub dispatcher_idx: u16,
+    pub dispatcher: String,
+    pub timestamper: String,
+    pub shared: Vec<String>,
+    /// SRP preemption level, higher for shorter deadlines
+    pub wcet_us: Option<u32>,
+    pub preemption_level: u16,
+    /// Maximum blocking, if every blocking critical section is annotated
+    pub blocking_us: Option<u32>,
+    /// Critical sections of tasks with a lower preemption level which can
+    /// block this task. At most one of them blocks any given job.
+    pub blockers: Vec<BlockerReport>,
+    /// Constant bandwidth server of an aperiodic task
+    pub server: Option<ServerReport>,
+    /// Execution budget of every job, enforced at runtime
+    pub budget_us: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ServerReport {
+    pub budget_us: u32,
+    pub period_us: u32,
+    /// Share of the CPU reserved for the task
+    pub bandwidth: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BlockerReport {
+    pub task: String,
+    pub resource: String,
+    pub cs_us: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ResourceReport {
+    pub name: String,
+    /// Highest priority of the tasks accessing the resource, or `None` if it
+    /// is never accessed
+    pub ceiling: Option<u16>,
+    /// Highest SRP preemption level of the tasks accessing the resource
+    pub preemption_ceiling: Option<u16>,
+    /// Tasks accessing the resource
+    pub tasks: Vec<String>,
+}
+
+impl ScheduleReport {
+    pub fn new(app: &App) -> Self {
+        let srp = SrpAnalysis::from_app(app);
+
+        let tasks = app
+            .tasks
+            .iter()
+            .zip(srp.tasks.iter())
+            .map(|(t, levels)| TaskReport {
+                name: t.task_struct.ident.to_string(),
+                deadline_us: t.deadline_us,
+                min_interarrival_us: t.min_interarrival_us,
+                max_jobs: t.max_jobs(),
+                priority: t.dispatcher_priority,
+                rq_idx: t.rq_idx,
+                dispatcher_idx: t.dispatcher_idx,
+                dispatcher: path_to_string(&t.dispatcher),
+                timestamper: path_to_string(&t.timestamper_binding),
+                shared: t.shared.iter().map(ToString::to_string).collect(),
+                wcet_us: t.wcet_us,
+                preemption_level: levels.preemption_level,
+                blocking_us: levels.blocking_us,
+                blockers: levels
+                    .blockers
+                    .iter()
+                    .map(|b| BlockerReport {
+                        task: b.task.to_string(),
+                        resource: b.resource.to_string(),
+                        cs_us: b.cs_us,
+                    })
+                    .collect(),
+                server: t.server.map(|s| ServerReport {
+                    budget_us: s.budget_us,
+                    period_us: s.period_us,
+                    bandwidth: s.budget_us as f64 / s.period_us as f64,
+                }),
+                budget_us: t.budget_us,
+            })
+            .collect();
+
+        let resources = srp
+            .resources
+            .iter()
+            .map(|res| ResourceReport {
+                name: res.name.to_string(),
+                ceiling: res.priority_ceiling,
+                preemption_ceiling: res.ceiling,
+                tasks: res.tasks.iter().map(ToString::to_string).collect(),
+            })
+            .collect();
+
+        let modes = app
+            .app_parameters
+            .modes
+            .iter()
+            .enumerate()
+            .map(|(mode, name)| ModeReport {
+                name: name.to_string(),
+                level_deadlines_us: app.level_deadlines(mode),
+                tasks: app
+                    .tasks
+                    .iter()
+                    .filter_map(|t| {
+                        Some(ModeTaskReport {
+                            name: t.task_struct.ident.to_string(),
+                            deadline_us: t.mode_deadlines[mode]?,
+                        })
+                    })
+                    .collect(),
+                demand: demand_test(&mode_analysis(app, mode)),
+            })
+            .collect();
+
+        Self {
+            app: app.mod_ident.to_string(),
+            time_source: path_to_string(&app.time_source),
+            timestamper_priority: app.timestamper_priority,
+            run_queue_len: app.dispatcher_priorities().len(),
+            wait_queue_len: app.wait_queue_len(),
+            tasks,
+            resources,
+            schedulability: srp.schedulability(),
+            demand: demand_test(&srp),
+            modes,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("Failed to serialize the schedule report")
+    }
+
+    /// Write the report to `<target dir>/<crate or bin name>_schedule.json`.
+    ///
+    /// Failing to write the report only emits a warning, so that it never
+    /// breaks a build.
+    pub fn write(&self) {
+        let Some(target_dir) = find_target_dir() else {
+            eprintln!(
+                "warning: failed to locate the `target` directory, not writing the EDF schedule report"
+            );
+            return;
+        };
+
+        let name = env::var("CARGO_BIN_NAME")
+            .or_else(|_| env::var("CARGO_CRATE_NAME"))
+            .unwrap_or_else(|_| self.app.clone());
+        let out_path = target_dir.join(format!("{name}_schedule.json"));
+
+        if let Err(e) = fs::write(&out_path, self.to_json()) {
+            eprintln!(
+                "warning: failed to write the EDF schedule report to {}: {e}",
+                out_path.display()
+            );
+        }
+    }
+}
+
+/// Render a path or type without the spaces inserted by the tokenizer
+fn path_to_string(path: &impl ToTokens) -> String {
+    path.to_token_stream()
+        .to_string()
+        .split_whitespace()
+        .collect()
+}
+
+/// Traverses the parent directories of `OUT_DIR` until it finds the `target`
+/// directory. Falls back to `CARGO_TARGET_DIR`, then to the closest `target`
+/// directory above the crate being compiled.
+fn find_target_dir() -> Option<PathBuf> {
+    if let Some(out_dir) = env::var_os("OUT_DIR") {
+        let mut path = PathBuf::from(out_dir);
+
+        while path.file_name().is_some_and(|name| name != "target") {
+            path.pop();
+        }
+
+        if path.file_name().is_some_and(|name| name == "target") {
+            return Some(path);
+        }
+    }
+
+    if let Some(target_dir) = env::var_os("CARGO_TARGET_DIR") {
+        return Some(target_dir.into());
+    }
+
+    let mut path = PathBuf::from(env::var_os("CARGO_MANIFEST_DIR")?);
+    loop {
+        let target_dir = path.join("target");
+        if target_dir.is_dir() {
+            return Some(target_dir);
+        }
+
+        if !path.pop() {
+            return None;
+        }
+    }
+}
@@ -0,0 +1,337 @@
+//! Stack Resource Policy (SRP) analysis.
+//!
+//! Under SRP, every task has a static preemption level, which is higher for
+//! shorter relative deadlines, and every shared resource has a ceiling: the
+//! highest preemption level of the tasks accessing it. A job may only preempt
+//! the running one if its preemption level is higher than the ceiling of every
+//! locked resource. A job is then blocked at most once, for the duration of a
+//! single critical section of a task with a lower preemption level.
+//!
+//! Locks are implemented by rtic-core, with BASEPRI ceilings computed from the
+//! dispatcher priorities assigned by the EDF pass. The wait queue orders jobs
+//! by absolute deadline, but a job can only start once its dispatcher's
+//! priority is above BASEPRI. The priority ceilings must therefore enforce the
+//! SRP ceilings exactly: [`SrpAnalysis::verify`] checks that a task can preempt
+//! the holder of a resource if and only if its preemption level is higher than
+//! the resource's ceiling.
+//!
+//! With annotated critical section lengths (`cs_us`), the blocking of every
+//! preemption level is bounded by its longest blocking critical section. With
+//! annotated WCETs (`wcet_us`) as well, [`SrpAnalysis::schedulability`] runs
+//! Baker's test: the task set is schedulable if, for every preemption level
+//! `k`, `sum(C_i / min(D_i, T_i) for D_i <= D_k) + B_k / D_k <= 1`, where
+//! `T_i` is the minimum inter-arrival time of the task (`min_interarrival_us`).
+//! The exact test is [`demand_test`](super::demand::demand_test).
+//!
+//! Nothing limits the release rate of a task which doesn't declare a minimum
+//! inter-arrival time: its next job can be released as soon as the previous
+//! one completes. Such a task set can't be analyzed.
+
+use std::collections::BTreeSet;
+
+use proc_macro2::Ident;
+use serde::Serialize;
+
+use crate::types::Deadline;
+
+use super::parse::App;
+
+/// Task parameters relevant to the analysis
+#[derive(Debug, Clone)]
+pub struct SrpTask {
+    pub name: Ident,
+    pub deadline_us: Deadline,
+    /// Priority of the task's dispatcher
+    pub priority: u16,
+    /// Shared resources accessed by the task
+    pub shared: Vec<Ident>,
+    pub min_interarrival_us: Option<u32>,
+    pub wcet_us: Option<u32>,
+    /// Length of the longest critical section on each annotated resource
+    pub cs_us: Vec<(Ident, u32)>,
+}
+
+impl SrpTask {
+    fn cs_us(&self, resource: &Ident) -> Option<u32> {
+        self.cs_us
+            .iter()
+            .find(|(r, _)| r == resource)
+            .map(|(_, len)| *len)
+    }
+}
+
+/// A critical section which can block a task
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Blocker {
+    /// Task with a lower preemption level, holding the resource
+    pub task: Ident,
+    pub resource: Ident,
+    /// Length of the critical section, if annotated
+    pub cs_us: Option<u32>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TaskLevel {
+    pub name: Ident,
+    pub deadline_us: Deadline,
+    pub min_interarrival_us: Option<u32>,
+    pub wcet_us: Option<u32>,
+    pub preemption_level: u16,
+    pub priority: u16,
+    /// Critical sections which can block the task. SRP guarantees that at
+    /// most one of them blocks any given job.
+    pub blockers: Vec<Blocker>,
+    /// Longest critical section among the blockers, or `None` if one of them
+    /// isn't annotated
+    pub blocking_us: Option<u32>,
+}
+
+impl TaskLevel {
+    /// Minimum inter-arrival time, or `None` if the release rate of the task
+    /// isn't bounded
+    pub fn period_us(&self) -> Option<u32> {
+        self.min_interarrival_us
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ResourceCeiling {
+    pub name: Ident,
+    /// Highest preemption level of the tasks accessing the resource, or
+    /// `None` if it is never accessed
+    pub ceiling: Option<u16>,
+    /// Highest dispatcher priority of the tasks accessing the resource, which
+    /// is the BASEPRI ceiling used to lock it
+    pub priority_ceiling: Option<u16>,
+    /// Tasks accessing the resource
+    pub tasks: Vec<Ident>,
+}
+
+/// Result of the SRP analysis
+#[derive(Debug, Clone)]
+pub struct SrpAnalysis {
+    /// Tasks, in the order they were given
+    pub tasks: Vec<TaskLevel>,
+    /// Shared resources, in the order they were given
+    pub resources: Vec<ResourceCeiling>,
+}
+
+impl SrpAnalysis {
+    pub fn from_app(app: &App) -> Self {
+        let tasks: Vec<_> = app
+            .tasks
+            .iter()
+            .map(|t| SrpTask {
+                name: t.task_struct.ident.clone(),
+                deadline_us: t.deadline_us,
+                priority: t.dispatcher_priority,
+                shared: t.shared.clone(),
+                min_interarrival_us: t.analyzed_min_interarrival_us(),
+                wcet_us: t.analyzed_wcet_us(),
+                cs_us: t.cs_us.clone(),
+            })
+            .collect();
+
+        Self::new(&tasks, &app.shared_resources())
+    }
+
+    pub fn new(tasks: &[SrpTask], resources: &[Ident]) -> Self {
+        let levels = preemption_levels(tasks);
+
+        let resources: Vec<_> = resources
+            .iter()
+            .map(|res| {
+                let accessors: Vec<_> = tasks
+                    .iter()
+                    .zip(levels.iter())
+                    .filter(|(t, _)| t.shared.contains(res))
+                    .collect();
+
+                ResourceCeiling {
+                    name: res.clone(),
+                    ceiling: accessors.iter().map(|(_, level)| **level).max(),
+                    priority_ceiling: accessors.iter().map(|(t, _)| t.priority).max(),
+                    tasks: accessors.iter().map(|(t, _)| t.name.clone()).collect(),
+                }
+            })
+            .collect();
+
+        let tasks = tasks
+            .iter()
+            .zip(levels.iter())
+            .map(|(task, &level)| {
+                let blockers = tasks
+                    .iter()
+                    .zip(levels.iter())
+                    .filter(|(_, other_level)| **other_level < level)
+                    .flat_map(|(other, _)| {
+                        resources
+                            .iter()
+                            .filter(|r| r.ceiling.is_some_and(|c| c >= level))
+                            .filter(|r| other.shared.contains(&r.name))
+                            .map(|r| Blocker {
+                                task: other.name.clone(),
+                                resource: r.name.clone(),
+                                cs_us: other.cs_us(&r.name),
+                            })
+                    })
+                    .collect::<Vec<_>>();
+
+                let blocking_us = blockers
+                    .iter()
+                    .try_fold(0, |max, b| Some(u32::max(max, b.cs_us?)));
+
+                TaskLevel {
+                    name: task.name.clone(),
+                    deadline_us: task.deadline_us,
+                    min_interarrival_us: task.min_interarrival_us,
+                    wcet_us: task.wcet_us,
+                    preemption_level: level,
+                    priority: task.priority,
+                    blockers,
+                    blocking_us,
+                }
+            })
+            .collect();
+
+        Self { tasks, resources }
+    }
+
+    /// Check that the priority ceilings used by the locks enforce the SRP
+    /// ceilings, so that blocking is bounded by a single critical section.
+    ///
+    /// A task whose priority is above the priority ceiling of a resource, but
+    /// whose preemption level isn't above the resource's ceiling, could
+    /// preempt the resource's holder while a more urgent job waits on it: an
+    /// unbounded priority inversion. The converse blocks the task on
+    /// resources SRP guarantees it never waits for. Locking a resource must
+    /// also never mask the timestamper interrupts, which would delay the
+    /// arrival timestamps of every task.
+    pub fn verify(&self, timestamper_priority: u16) -> syn::Result<()> {
+        for res in self.resources.iter() {
+            let (Some(ceiling), Some(priority_ceiling)) = (res.ceiling, res.priority_ceiling)
+            else {
+                continue;
+            };
+
+            if priority_ceiling >= timestamper_priority {
+                return Err(syn::Error::new(
+                    res.name.span(),
+                    format!(
+                        "Locking resource `{}` (priority ceiling {priority_ceiling}) would mask the timestamper interrupts (priority {timestamper_priority})",
+                        res.name
+                    ),
+                ));
+            }
+
+            for task in self.tasks.iter() {
+                let preempts_holder = task.priority > priority_ceiling;
+                let above_ceiling = task.preemption_level > ceiling;
+
+                if preempts_holder && !above_ceiling {
+                    return Err(syn::Error::new(
+                        task.name.span(),
+                        format!(
+                            "Task `{}` (priority {}, preemption level {}) can preempt a task holding resource `{}` (priority ceiling {priority_ceiling}, SRP ceiling {ceiling}), which would allow unbounded priority inversion",
+                            task.name, task.priority, task.preemption_level, res.name
+                        ),
+                    ));
+                }
+
+                if !preempts_holder && above_ceiling {
+                    return Err(syn::Error::new(
+                        task.name.span(),
+                        format!(
+                            "Task `{}` (priority {}, preemption level {}) is blocked by resource `{}` (priority ceiling {priority_ceiling}, SRP ceiling {ceiling}), which its preemption level allows it to preempt",
+                            task.name, task.priority, task.preemption_level, res.name
+                        ),
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Run Baker's test. Returns `None` if a WCET, a minimum inter-arrival
+    /// time or a blocking critical section length isn't annotated.
+    pub fn schedulability(&self) -> Option<Schedulability> {
+        let mut levels: Vec<LevelLoad> = Vec::new();
+
+        let mut by_deadline: Vec<_> = self.tasks.iter().collect();
+        by_deadline.sort_by_key(|t| t.deadline_us);
+
+        let mut density = 0.0;
+        for task in by_deadline {
+            density += task.wcet_us? as f64 / task.period_us()?.min(task.deadline_us) as f64;
+            let blocking_us = task.blocking_us?;
+
+            match levels.last_mut() {
+                Some(level) if level.preemption_level == task.preemption_level => {
+                    level.blocking_us = level.blocking_us.max(blocking_us);
+                }
+                _ => levels.push(LevelLoad {
+                    preemption_level: task.preemption_level,
+                    deadline_us: task.deadline_us,
+                    blocking_us,
+                    density: 0.0,
+                    load: 0.0,
+                }),
+            }
+
+            // Tasks of a level share its deadline, and are therefore sorted
+            // next to each other
+            let level = levels.last_mut().unwrap();
+            level.density = density;
+            level.load = density + level.blocking_us as f64 / level.deadline_us as f64;
+        }
+
+        let schedulable = levels.iter().all(|l| l.load <= 1.0);
+        Some(Schedulability {
+            levels,
+            schedulable,
+        })
+    }
+
+    /// Returns the analysis of the task named `name`, if any
+    pub fn task(&self, name: &Ident) -> Option<&TaskLevel> {
+        self.tasks.iter().find(|t| t.name == *name)
+    }
+}
+
+/// Baker's test at a single preemption level
+#[derive(Debug, Clone, Serialize)]
+pub struct LevelLoad {
+    pub preemption_level: u16,
+    pub deadline_us: Deadline,
+    /// Sum of `C_i / min(D_i, T_i)` over the tasks with a deadline no longer than this
+    /// level's
+    pub density: f64,
+    /// Maximum blocking of the level
+    pub blocking_us: u32,
+    /// `density + blocking_us / deadline_us`, which must not exceed 1
+    pub load: f64,
+}
+
+/// Result of Baker's test, by increasing deadline
+#[derive(Debug, Clone, Serialize)]
+pub struct Schedulability {
+    pub levels: Vec<LevelLoad>,
+    pub schedulable: bool,
+}
+
+/// Preemption levels, by inverse relative deadline: the longest deadline gets
+/// level 1, and tasks with equal deadlines share a level.
+fn preemption_levels(tasks: &[SrpTask]) -> Vec<u16> {
+    let deadlines: BTreeSet<Deadline> = tasks.iter().map(|t| t.deadline_us).collect();
+
+    tasks
+        .iter()
+        .map(|t| {
+            let longer = deadlines.range(t.deadline_us..).count();
+            longer
+                .try_into()
+                .expect("Unsupported preemption level: over u16::MAX")
+        })
+        .collect()
+}
@@ -0,0 +1,102 @@
+use quote::format_ident;
+use syn::{Path, parse_quote};
+
+use crate::{assignment::assign_priorities, types::Deadline};
+
+fn tasks(deadlines: &[(&str, Deadline)]) -> Vec<(proc_macro2::Ident, Deadline)> {
+    deadlines
+        .iter()
+        .map(|(name, dl)| (format_ident!("{name}"), *dl))
+        .collect()
+}
+
+fn dispatchers(n: usize) -> Vec<Path> {
+    (0..n)
+        .map(|i| {
+            let ident = format_ident!("DISP{i}");
+            parse_quote!(#ident)
+        })
+        .collect()
+}
+
+/// Returns (name, priority, rq_idx, dispatcher_idx) in dispatcher order
+fn summary(deadlines: &[(&str, Deadline)]) -> Vec<(String, u16, u16, u16)> {
+    assign_priorities(&tasks(deadlines), &dispatchers(deadlines.len()))
+        .tasks
+        .into_iter()
+        .map(|t| (t.name.to_string(), t.priority, t.rq_idx, t.dispatcher_idx))
+        .collect()
+}
+
+#[test]
+fn longest_deadline_gets_lowest_priority() {
+    let assignment = summary(&[("fast", 1_000), ("slow", 100_000), ("medium", 10_000)]);
+
+    assert_eq!(
+        assignment,
+        [
+            ("slow".into(), 1, 0, 0),
+            ("medium".into(), 2, 1, 1),
+            ("fast".into(), 3, 2, 2),
+        ]
+    );
+}
+
+#[test]
+fn equal_deadlines_share_a_priority() {
+    let assignment = assign_priorities(
+        &tasks(&[("a", 5_000), ("b", 20_000), ("c", 5_000), ("d", 1_000)]),
+        &dispatchers(4),
+    );
+
+    assert_eq!(assignment.num_priorities(), 3);
+
+    let a = assignment.get(&format_ident!("a")).unwrap();
+    let c = assignment.get(&format_ident!("c")).unwrap();
+    assert_eq!(a.priority, c.priority);
+    assert_eq!(a.rq_idx, c.rq_idx);
+    assert_ne!(a.dispatcher, c.dispatcher);
+}
+
+#[test]
+fn independent_of_declaration_order() {
+    let forward = summary(&[("a", 5_000), ("b", 5_000), ("c", 5_000), ("d", 9_000)]);
+    let backward = summary(&[("d", 9_000), ("c", 5_000), ("b", 5_000), ("a", 5_000)]);
+
+    assert_eq!(forward, backward);
+    assert_eq!(
+        forward,
+        [
+            ("d".into(), 1, 0, 0),
+            ("a".into(), 2, 1, 1),
+            ("b".into(), 2, 1, 2),
+            ("c".into(), 2, 1, 3),
+        ]
+    );
+}
+
+#[test]
+fn dispatchers_are_assigned_in_order() {
+    let assignment = assign_priorities(&tasks(&[("a", 1), ("b", 2)]), &dispatchers(3));
+
+    let dispatchers: Vec<_> = assignment
+        .tasks
+        .iter()
+        .map(|t| t.dispatcher.get_ident().unwrap().to_string())
+        .collect();
+    assert_eq!(dispatchers, ["DISP0", "DISP1"]);
+}
+
+#[test]
+fn no_tasks() {
+    let assignment = assign_priorities(&[], &dispatchers(2));
+
+    assert!(assignment.tasks.is_empty());
+    assert_eq!(assignment.num_priorities(), 0);
+}
+
+#[test]
+#[should_panic(expected = "at least as many dispatchers as there are tasks")]
+fn not_enough_dispatchers() {
+    assign_priorities(&tasks(&[("a", 1), ("b", 2)]), &dispatchers(1));
+}
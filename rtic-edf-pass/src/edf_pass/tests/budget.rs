@@ -0,0 +1,81 @@
+use proc_macro2::TokenStream;
+use rtic_core::parse_utils::RticAttr;
+use syn::{ItemMod, parse_quote};
+
+use super::{budget_args, default_args, edf_pass};
+use crate::edf_pass::{parse::App, report::ScheduleReport};
+
+fn parse(args: TokenStream, app: ItemMod) -> syn::Result<App> {
+    let args = RticAttr::parse_from_tokens(args).unwrap();
+    App::parse(&edf_pass(), &args, app)
+}
+
+#[test]
+fn budgets_are_per_task() {
+    let app = parse(
+        budget_args(),
+        parse_quote! {
+            mod app {
+                #[task(deadline_us = 1_000, binds = IRQ0, wcet_us = 200, budget_us = 250)]
+                pub struct Control;
+
+                #[task(deadline_us = 10_000, binds = IRQ1)]
+                pub struct Logger;
+            }
+        },
+    )
+    .unwrap();
+
+    let budgets: Vec<_> = app
+        .tasks
+        .iter()
+        .map(|t| (t.task_struct.ident.to_string(), t.budget_us))
+        .collect();
+    assert_eq!(
+        budgets,
+        [("Logger".into(), None), ("Control".into(), Some(250))]
+    );
+
+    // The budget is enforced, and not used by the analysis
+    assert_eq!(app.tasks[1].wcet_us, Some(200));
+    assert!(!app.tasks[1].params.elements.contains_key("budget_us"));
+
+    let report = ScheduleReport::new(&app);
+    assert_eq!(report.tasks[1].budget_us, Some(250));
+    assert!(report.to_json().contains("\"budget_us\": 250"));
+}
+
+#[test]
+fn reject_budgets_without_a_timer() {
+    let err = parse(
+        default_args(),
+        parse_quote! {
+            mod app {
+                #[task(deadline_us = 1_000, binds = IRQ0, budget_us = 250)]
+                pub struct Control;
+            }
+        },
+    )
+    .err()
+    .unwrap()
+    .to_string();
+
+    assert!(
+        err.contains("requires the `budget_timer` and `budget_overrun`"),
+        "{err}"
+    );
+}
+
+#[test]
+#[should_panic(expected = "is shorter than its WCET")]
+fn reject_budgets_shorter_than_the_wcet() {
+    let _ = parse(
+        budget_args(),
+        parse_quote! {
+            mod app {
+                #[task(deadline_us = 1_000, binds = IRQ0, wcet_us = 300, budget_us = 250)]
+                pub struct Control;
+            }
+        },
+    );
+}
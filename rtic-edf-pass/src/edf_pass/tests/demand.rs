@@ -0,0 +1,237 @@
+use quote::format_ident;
+use rtic_core::parse_utils::RticAttr;
+use syn::{ItemMod, parse_quote};
+
+use super::{default_args, edf_pass};
+use crate::edf_pass::{
+    demand::{Overload, demand_test},
+    parse::App,
+    srp::{SrpAnalysis, SrpTask},
+};
+
+/// (name, C, D, T)
+fn analysis(tasks: &[(&str, u32, u32, u32)]) -> SrpAnalysis {
+    let tasks: Vec<_> = tasks
+        .iter()
+        .enumerate()
+        .map(|(i, &(name, wcet_us, deadline_us, period_us))| SrpTask {
+            name: format_ident!("{name}"),
+            deadline_us,
+            priority: i as u16 + 1,
+            shared: vec![],
+            min_interarrival_us: Some(period_us),
+            wcet_us: Some(wcet_us),
+            cs_us: vec![],
+        })
+        .collect();
+
+    SrpAnalysis::new(&tasks, &[])
+}
+
+fn parse(app: ItemMod) -> App {
+    let args = RticAttr::parse_from_tokens(default_args()).unwrap();
+    App::parse(&edf_pass(), &args, app).unwrap()
+}
+
+#[test]
+fn overlapping_jobs_size_the_wait_queue() {
+    let app = parse(parse_quote! {
+        mod app {
+            #[task(deadline_us = 10_000, binds = IRQ0)]
+            pub struct Sporadic;
+
+            #[task(deadline_us = 2_000, binds = IRQ1, min_interarrival_us = 5_000)]
+            pub struct Constrained;
+
+            #[task(deadline_us = 20_000, binds = IRQ2, min_interarrival_us = 5_000, overlapping_jobs = true)]
+            pub struct Arbitrary;
+
+            #[task(deadline_us = 21_000, binds = IRQ3, min_interarrival_us = 5_000, overlapping_jobs = true)]
+            pub struct Rounded;
+        }
+    });
+
+    let max_jobs: Vec<_> = app
+        .tasks
+        .iter()
+        .map(|t| (t.task_struct.ident.to_string(), t.max_jobs()))
+        .collect();
+
+    assert_eq!(
+        max_jobs,
+        [
+            ("Rounded".into(), 5),
+            ("Arbitrary".into(), 4),
+            ("Sporadic".into(), 1),
+            ("Constrained".into(), 1),
+        ]
+    );
+    assert_eq!(app.wait_queue_len(), 11);
+}
+
+#[test]
+fn implicit_deadlines() {
+    let test = demand_test(&analysis(&[
+        ("A", 2_000, 10_000, 10_000),
+        ("B", 4_000, 5_000, 5_000),
+    ]))
+    .unwrap();
+
+    assert!((test.utilization - 1.0).abs() < 1e-9);
+    assert_eq!(test.overload, None);
+    assert!(test.schedulable);
+
+    let test = demand_test(&analysis(&[
+        ("A", 2_100, 10_000, 10_000),
+        ("B", 4_000, 5_000, 5_000),
+    ]))
+    .unwrap();
+    assert!(!test.schedulable);
+    assert_eq!(
+        test.overload,
+        Some(Overload {
+            interval_us: 10_000,
+            demand_us: 10_100,
+            blocking_us: 0,
+        })
+    );
+}
+
+#[test]
+fn constrained_deadlines() {
+    // The density (2/4 + 4/6) exceeds 1, but the jobs fit in their windows
+    let analysis = analysis(&[("A", 2_000, 4_000, 10_000), ("B", 4_000, 6_000, 10_000)]);
+    assert!(!analysis.schedulability().unwrap().schedulable);
+
+    assert!(demand_test(&analysis).unwrap().schedulable);
+
+    let test = demand_test(&self::analysis(&[
+        ("A", 2_000, 4_000, 10_000),
+        ("B", 5_000, 6_000, 10_000),
+    ]))
+    .unwrap();
+    assert_eq!(
+        test.overload,
+        Some(Overload {
+            interval_us: 6_000,
+            demand_us: 7_000,
+            blocking_us: 0,
+        })
+    );
+}
+
+#[test]
+fn arbitrary_deadlines() {
+    // Up to 4 jobs of A are active at once
+    let test = demand_test(&analysis(&[
+        ("A", 3_000, 20_000, 5_000),
+        ("B", 1_000, 4_000, 5_000),
+    ]))
+    .unwrap();
+
+    assert!((test.utilization - 0.8).abs() < 1e-9);
+    assert_eq!(test.horizon_us, 20_000);
+    assert!(test.schedulable);
+
+    let test = demand_test(&analysis(&[
+        ("A", 4_500, 20_000, 5_000),
+        ("B", 1_000, 4_000, 5_000),
+    ]))
+    .unwrap();
+    assert!(test.utilization > 1.0);
+    assert!(!test.schedulable);
+    assert!(test.overload.is_some());
+}
+
+#[test]
+fn spare_time() {
+    let test = demand_test(&analysis(&[
+        ("A", 1_000, 4_000, 4_000),
+        ("B", 2_000, 10_000, 10_000),
+    ]))
+    .unwrap();
+    assert_eq!(test.min_spare_us, 3_000);
+    assert_eq!(test.spare_offset_us, 0.0);
+
+    // A constrained deadline leaves less spare time in the first interval, and
+    // delays the growth of the spare time
+    let test = demand_test(&analysis(&[
+        ("A", 1_000, 2_000, 5_000),
+        ("B", 2_000, 10_000, 10_000),
+    ]))
+    .unwrap();
+    assert_eq!(test.min_spare_us, 1_000);
+    assert!((test.spare_offset_us - 600.0).abs() < 1e-9);
+
+    let test = demand_test(&analysis(&[
+        ("A", 6_000, 10_000, 10_000),
+        ("B", 4_000, 5_000, 5_000),
+    ]))
+    .unwrap();
+    assert!(!test.schedulable);
+    assert_eq!(test.min_spare_us, 0);
+}
+
+#[test]
+fn demand_includes_blocking() {
+    let app = |slow_cs_us: u32| {
+        let slow_cs_us = proc_macro2::Literal::u32_unsuffixed(slow_cs_us);
+
+        parse(parse_quote! {
+            mod app {
+                #[shared]
+                struct Shared {
+                    x: u32,
+                }
+
+                #[task(deadline_us = 2_000, binds = IRQ0, min_interarrival_us = 10_000, shared = [x], wcet_us = 1_000)]
+                pub struct Fast;
+
+                #[task(deadline_us = 20_000, binds = IRQ1, min_interarrival_us = 20_000, shared = [x], wcet_us = 3_000, cs_us = [x = #slow_cs_us])]
+                pub struct Slow;
+            }
+        })
+    };
+
+    let test = demand_test(&SrpAnalysis::from_app(&app(1_000))).unwrap();
+    assert!(test.schedulable);
+    assert_eq!(test.min_spare_us, 0);
+
+    // The blocking is left out of the spare time
+    let test = demand_test(&SrpAnalysis::from_app(&app(500))).unwrap();
+    assert_eq!(test.min_spare_us, 500);
+    assert_eq!(test.max_blocking_us, 500);
+    assert_eq!(test.spare_offset_us, 800.0 + 500.0);
+
+    let test = demand_test(&SrpAnalysis::from_app(&app(1_500))).unwrap();
+    assert_eq!(
+        test.overload,
+        Some(Overload {
+            interval_us: 2_000,
+            demand_us: 1_000,
+            blocking_us: 1_500,
+        })
+    );
+}
+
+#[test]
+#[should_panic(expected = "`min_interarrival_us` must be greater than 0")]
+fn zero_interarrival_time() {
+    parse(parse_quote! {
+        mod app {
+            #[task(deadline_us = 1_000, binds = IRQ0, min_interarrival_us = 0)]
+            pub struct A;
+        }
+    });
+}
+
+#[test]
+#[should_panic(expected = "opt in with `overlapping_jobs = true`")]
+fn overlapping_jobs_need_an_opt_in() {
+    parse(parse_quote! {
+        mod app {
+            #[task(deadline_us = 1_000, binds = IRQ0, modes = [normal, degraded = 3_000], min_interarrival_us = 2_000)]
+            pub struct A;
+        }
+    });
+}
@@ -0,0 +1,337 @@
+//! Tests for the EDF pass. The generated code is snapshot-tested.
+//!
+//! Review snapshot changes with `cargo insta review`.
+
+mod assignment;
+mod budget;
+mod demand;
+mod modes;
+mod server;
+mod srp;
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use rtic_core::{RticPass, parse_utils::RticAttr};
+use syn::{Item, ItemMod, MetaNameValue, Token, parse_quote, punctuated::Punctuated};
+
+use super::{codegen::Instrumentation, parse::App, report::ScheduleReport};
+use crate::EdfPass;
+
+/// The pass, generating the same code whatever the features of this crate,
+/// so that the snapshots don't depend on them
+fn edf_pass() -> EdfPass {
+    EdfPass {
+        instrumentation: Instrumentation::default(),
+        ..EdfPass::new(1, 8, parse_quote!(::cortex_m_edf_rtic::monotonic::Dwt))
+    }
+}
+
+/// Run the pass on an app and pretty-print the resulting module
+fn expand(args: TokenStream, app_mod: ItemMod) -> String {
+    expand_with(&edf_pass(), args, app_mod)
+}
+
+fn expand_with(pass: &EdfPass, args: TokenStream, app_mod: ItemMod) -> String {
+    let (_, mut expanded) = pass.run_pass(args, app_mod).unwrap();
+    sort_task_attributes(&mut expanded);
+
+    prettyplease::unparse(&syn::File {
+        shebang: None,
+        attrs: vec![],
+        items: vec![Item::Mod(expanded)],
+    })
+}
+
+/// Task attribute arguments are emitted in hash map order. Sort them so that
+/// the snapshots are stable.
+fn sort_task_attributes(app_mod: &mut ItemMod) {
+    let Some((_, items)) = app_mod.content.as_mut() else {
+        return;
+    };
+
+    for item in items {
+        let Item::Struct(strct) = item else {
+            continue;
+        };
+
+        for attr in strct.attrs.iter_mut().filter(|a| a.path().is_ident("task")) {
+            let mut args: Vec<MetaNameValue> = attr
+                .parse_args_with(Punctuated::<MetaNameValue, Token![,]>::parse_terminated)
+                .unwrap()
+                .into_iter()
+                .collect();
+            args.sort_by_key(|arg| arg.path.get_ident().unwrap().to_string());
+
+            *attr = parse_quote!(#[task(#(#args),*)]);
+        }
+    }
+}
+
+fn default_args() -> TokenStream {
+    quote! {
+        device = pac,
+        dispatchers = [DISP0, DISP1, DISP2, DISP3],
+        cpu_freq = 48_000_000,
+    }
+}
+
+/// [`default_args`] with a budget timer and an overrun handler
+fn budget_args() -> TokenStream {
+    quote! {
+        device = pac,
+        dispatchers = [DISP0, DISP1, DISP2, DISP3],
+        cpu_freq = 48_000_000,
+        budget_timer = my_timer::Budget,
+        budget_overrun = crate::on_overrun,
+    }
+}
+
+#[test]
+fn distinct_deadlines() {
+    let app: ItemMod = parse_quote! {
+        mod app {
+            #[shared]
+            struct Shared {}
+
+            #[task(deadline_us = 1_000, binds = IRQ0)]
+            pub struct Fast;
+
+            #[task(deadline_us = 100_000, binds = IRQ1)]
+            pub struct Slow;
+
+            #[task(deadline_us = 10_000, binds = IRQ2)]
+            pub struct Medium;
+        }
+    };
+
+    insta::assert_snapshot!(expand(default_args(), app));
+}
+
+#[test]
+fn equal_deadlines_share_a_priority() {
+    let app: ItemMod = parse_quote! {
+        mod app {
+            #[shared]
+            struct Shared {}
+
+            #[task(deadline_us = 5_000, binds = IRQ0)]
+            pub struct First;
+
+            #[task(deadline_us = 20_000, binds = IRQ1)]
+            pub struct Second;
+
+            #[task(deadline_us = 5_000, binds = IRQ2)]
+            pub struct Third;
+        }
+    };
+
+    insta::assert_snapshot!(expand(default_args(), app));
+}
+
+#[test]
+fn shared_resources_and_monotonic() {
+    let args = quote! {
+        device = pac,
+        dispatchers = [DISP0, DISP1],
+        cpu_freq = 48_000_000,
+        monotonic = my_timer::Timer,
+    };
+
+    let app: ItemMod = parse_quote! {
+        mod app {
+            #[shared]
+            struct Shared {
+                x: u32,
+            }
+
+            #[task(deadline_us = 2_000, binds = IRQ0, shared = [x])]
+            pub struct Producer;
+
+            #[task(deadline_us = 8_000, binds = IRQ1, shared = [x])]
+            pub struct Consumer;
+        }
+    };
+
+    insta::assert_snapshot!(expand(args, app));
+}
+
+#[test]
+fn operating_modes() {
+    let args = quote! {
+        device = pac,
+        dispatchers = [DISP0, DISP1],
+        cpu_freq = 48_000_000,
+        modes = [normal, low_power],
+    };
+
+    let app: ItemMod = parse_quote! {
+        mod app {
+            #[shared]
+            struct Shared {}
+
+            #[task(deadline_us = 1_000, binds = IRQ0, modes = [normal, low_power = 4_000])]
+            pub struct Control;
+
+            #[task(deadline_us = 10_000, binds = IRQ1, modes = [normal])]
+            pub struct Telemetry;
+        }
+    };
+
+    insta::assert_snapshot!(expand(args, app));
+}
+
+#[test]
+fn constant_bandwidth_server() {
+    let app: ItemMod = parse_quote! {
+        mod app {
+            #[shared]
+            struct Shared {}
+
+            #[task(deadline_us = 1_000, binds = IRQ0)]
+            pub struct Control;
+
+            #[task(binds = IRQ1, server = [budget_us = 2_000, period_us = 20_000], wcet_us = 1_000)]
+            pub struct Logger;
+        }
+    };
+
+    insta::assert_snapshot!(expand(budget_args(), app));
+}
+
+#[test]
+fn execution_budgets() {
+    let args = quote! {
+        device = pac,
+        dispatchers = [DISP0, DISP1],
+        cpu_freq = 48_000_000,
+        budget_timer = ::cortex_m_edf_rtic::monotonic::DwtBudget,
+        budget_overrun = crate::on_overrun,
+    };
+
+    let app: ItemMod = parse_quote! {
+        mod app {
+            #[shared]
+            struct Shared {}
+
+            #[task(deadline_us = 1_000, binds = IRQ0, wcet_us = 200, budget_us = 250)]
+            pub struct Control;
+
+            #[task(deadline_us = 10_000, binds = IRQ1)]
+            pub struct Logger;
+        }
+    };
+
+    insta::assert_snapshot!(expand(args, app));
+}
+
+#[test]
+fn instrumentation() {
+    let pass = EdfPass {
+        instrumentation: Instrumentation {
+            stats: true,
+            response_times: true,
+        },
+        ..edf_pass()
+    };
+
+    let app: ItemMod = parse_quote! {
+        mod app {
+            #[shared]
+            struct Shared {}
+
+            #[task(deadline_us = 1_000, binds = IRQ0)]
+            pub struct A;
+        }
+    };
+
+    insta::assert_snapshot!(expand_with(&pass, default_args(), app));
+}
+
+#[test]
+fn slack_query() {
+    let app: ItemMod = parse_quote! {
+        mod app {
+            #[shared]
+            struct Shared {}
+
+            #[task(deadline_us = 1_000, binds = IRQ0, min_interarrival_us = 1_000, wcet_us = 200)]
+            pub struct Control;
+
+            #[task(deadline_us = 10_000, binds = IRQ1, min_interarrival_us = 5_000, overlapping_jobs = true, wcet_us = 1_000)]
+            pub struct Logger;
+        }
+    };
+
+    insta::assert_snapshot!(expand(default_args(), app));
+}
+
+#[test]
+fn schedule_report() {
+    let app: ItemMod = parse_quote! {
+        mod app {
+            #[shared]
+            struct Shared {
+                x: u32,
+                y: u32,
+                unused: u32,
+            }
+
+            #[task(deadline_us = 2_000, binds = IRQ0, min_interarrival_us = 2_000, shared = [x, y], wcet_us = 500, cs_us = [x = 50, y = 20])]
+            pub struct Fast;
+
+            #[task(deadline_us = 8_000, binds = IRQ1, min_interarrival_us = 4_000, overlapping_jobs = true, shared = [x], wcet_us = 2_000, cs_us = [x = 100])]
+            pub struct Slow;
+
+            #[task(deadline_us = 4_000, binds = IRQ2, min_interarrival_us = 4_000, shared = [y], wcet_us = 1_000, cs_us = [y = 80])]
+            pub struct Medium;
+        }
+    };
+
+    let args = RticAttr::parse_from_tokens(default_args()).unwrap();
+    let app = App::parse(&edf_pass(), &args, app).unwrap();
+
+    insta::assert_snapshot!(ScheduleReport::new(&app).to_json());
+}
+
+#[test]
+#[should_panic(expected = "Exceeded number of priorities for this platform")]
+fn too_many_priority_levels() {
+    let pass = EdfPass::new(1, 3, parse_quote!(::cortex_m_edf_rtic::monotonic::Dwt));
+
+    let app: ItemMod = parse_quote! {
+        mod app {
+            #[task(deadline_us = 1_000, binds = IRQ0)]
+            pub struct A;
+
+            #[task(deadline_us = 2_000, binds = IRQ1)]
+            pub struct B;
+
+            #[task(deadline_us = 3_000, binds = IRQ2)]
+            pub struct C;
+        }
+    };
+
+    let _ = pass.run_pass(default_args(), app);
+}
+
+#[test]
+#[should_panic(expected = "at least as many dispatchers as there are tasks")]
+fn not_enough_dispatchers() {
+    let args = quote! {
+        device = pac,
+        dispatchers = [DISP0],
+        cpu_freq = 48_000_000,
+    };
+
+    let app: ItemMod = parse_quote! {
+        mod app {
+            #[task(deadline_us = 1_000, binds = IRQ0)]
+            pub struct A;
+
+            #[task(deadline_us = 2_000, binds = IRQ1)]
+            pub struct B;
+        }
+    };
+
+    let _ = edf_pass().run_pass(args, app);
+}
@@ -0,0 +1,198 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use rtic_core::parse_utils::RticAttr;
+use syn::{ItemMod, parse_quote};
+
+use super::edf_pass;
+use crate::edf_pass::{modes::mode_analysis, parse::App, report::ScheduleReport};
+
+fn mode_args() -> TokenStream {
+    quote! {
+        device = pac,
+        dispatchers = [DISP0, DISP1, DISP2, DISP3],
+        cpu_freq = 48_000_000,
+        modes = [normal, degraded, low_power],
+    }
+}
+
+fn parse(app: ItemMod) -> syn::Result<App> {
+    let args = RticAttr::parse_from_tokens(mode_args()).unwrap();
+    App::parse(&edf_pass(), &args, app)
+}
+
+fn parse_err(app: ItemMod) -> String {
+    parse(app).err().expect("expected a mode error").to_string()
+}
+
+/// A control loop which slows down in degraded mode, and a logger which only
+/// runs in normal mode
+fn modes_app() -> ItemMod {
+    parse_quote! {
+        mod app {
+            #[task(deadline_us = 1_000, binds = IRQ0, modes = [normal, degraded = 2_000], min_interarrival_us = 1_000, overlapping_jobs = true)]
+            pub struct Control;
+
+            #[task(deadline_us = 5_000, binds = IRQ1, modes = [normal])]
+            pub struct Logger;
+
+            #[task(deadline_us = 20_000, binds = IRQ2)]
+            pub struct Watchdog;
+        }
+    }
+}
+
+#[test]
+fn tasks_are_active_in_their_modes() {
+    let app = parse(modes_app()).unwrap();
+
+    let modes: Vec<_> = app
+        .tasks
+        .iter()
+        .map(|t| {
+            (
+                t.task_struct.ident.to_string(),
+                t.mode_deadlines.clone(),
+                t.modes_mask(),
+            )
+        })
+        .collect();
+
+    assert_eq!(
+        modes,
+        [
+            (
+                "Watchdog".into(),
+                vec![Some(20_000), Some(20_000), Some(20_000)],
+                0b111
+            ),
+            ("Logger".into(), vec![Some(5_000), None, None], 0b001),
+            (
+                "Control".into(),
+                vec![Some(1_000), Some(2_000), None],
+                0b011
+            ),
+        ]
+    );
+
+    // Jobs of `Control` overlap in degraded mode
+    assert_eq!(app.tasks[2].max_jobs(), 2);
+    assert_eq!(app.wait_queue_len(), 4);
+}
+
+#[test]
+fn inactive_levels_keep_their_declared_deadline() {
+    let app = parse(modes_app()).unwrap();
+
+    assert_eq!(app.num_modes(), 3);
+    assert_eq!(app.level_deadlines(0), [20_000, 5_000, 1_000]);
+    assert_eq!(app.level_deadlines(1), [20_000, 5_000, 2_000]);
+    assert_eq!(app.level_deadlines(2), [20_000, 5_000, 1_000]);
+
+    let analyzed: Vec<_> = (0..3)
+        .map(|mode| {
+            mode_analysis(&app, mode)
+                .tasks
+                .iter()
+                .map(|t| (t.name.to_string(), t.deadline_us))
+                .collect::<Vec<_>>()
+        })
+        .collect();
+
+    assert_eq!(
+        analyzed[1],
+        [("Watchdog".into(), 20_000), ("Control".into(), 2_000)]
+    );
+    assert_eq!(analyzed[2], [("Watchdog".into(), 20_000)]);
+}
+
+#[test]
+fn report_lists_modes() {
+    let report = ScheduleReport::new(&parse(modes_app()).unwrap());
+
+    let modes: Vec<_> = report
+        .modes
+        .iter()
+        .map(|m| (m.name.as_str(), m.tasks.len()))
+        .collect();
+    assert_eq!(modes, [("normal", 3), ("degraded", 2), ("low_power", 1)]);
+    assert!(report.to_json().contains("\"level_deadlines_us\""));
+}
+
+#[test]
+fn apps_without_modes_have_a_single_one() {
+    let args = RticAttr::parse_from_tokens(super::default_args()).unwrap();
+    let app = App::parse(
+        &edf_pass(),
+        &args,
+        parse_quote! {
+            mod app {
+                #[task(deadline_us = 1_000, binds = IRQ0)]
+                pub struct A;
+            }
+        },
+    )
+    .unwrap();
+
+    assert_eq!(app.num_modes(), 1);
+    assert_eq!(app.tasks[0].modes_mask(), 1);
+    assert!(ScheduleReport::new(&app).modes.is_empty());
+}
+
+#[test]
+fn reject_mode_deadlines_out_of_priority_order() {
+    // `Slow` would become more urgent than `Fast` in degraded mode
+    let err = parse_err(parse_quote! {
+        mod app {
+            #[task(deadline_us = 1_000, binds = IRQ0)]
+            pub struct Fast;
+
+            #[task(deadline_us = 10_000, binds = IRQ1, modes = [normal, degraded = 1_000])]
+            pub struct Slow;
+        }
+    });
+
+    assert!(err.contains("must stay shorter"), "{err}");
+    assert!(err.contains("in mode `degraded`"), "{err}");
+}
+
+#[test]
+fn reject_diverging_deadlines_on_a_shared_level() {
+    let err = parse_err(parse_quote! {
+        mod app {
+            #[task(deadline_us = 1_000, binds = IRQ0)]
+            pub struct A;
+
+            #[task(deadline_us = 1_000, binds = IRQ1, modes = [normal = 500])]
+            pub struct B;
+        }
+    });
+
+    assert!(err.contains("share a priority level"), "{err}");
+}
+
+#[test]
+fn reject_undeclared_modes() {
+    let err = parse_err(parse_quote! {
+        mod app {
+            #[task(deadline_us = 1_000, binds = IRQ0, modes = [turbo])]
+            pub struct A;
+        }
+    });
+    assert!(err.contains("undeclared mode `turbo`"), "{err}");
+
+    let args = RticAttr::parse_from_tokens(super::default_args()).unwrap();
+    let err = App::parse(
+        &edf_pass(),
+        &args,
+        parse_quote! {
+            mod app {
+                #[task(deadline_us = 1_000, binds = IRQ0, modes = [normal])]
+                pub struct A;
+            }
+        },
+    )
+    .err()
+    .unwrap()
+    .to_string();
+    assert!(err.contains("the app doesn't declare any"), "{err}");
+}
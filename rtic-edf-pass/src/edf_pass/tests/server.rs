@@ -0,0 +1,153 @@
+use rtic_core::parse_utils::RticAttr;
+use syn::{ItemMod, parse_quote};
+
+use super::{budget_args, default_args, edf_pass};
+use crate::edf_pass::{
+    demand::demand_test, parse::App, parse::ast::ServerParams, report::ScheduleReport,
+    srp::SrpAnalysis,
+};
+
+fn parse(app: ItemMod) -> App {
+    let args = RticAttr::parse_from_tokens(budget_args()).unwrap();
+    App::parse(&edf_pass(), &args, app).unwrap()
+}
+
+/// A control loop and a best-effort logger reserving 10% of the CPU, whose
+/// jobs run for up to 1 ms
+fn served_app() -> ItemMod {
+    parse_quote! {
+        mod app {
+            #[task(deadline_us = 1_000, binds = IRQ0, min_interarrival_us = 1_000, wcet_us = 800)]
+            pub struct Control;
+
+            #[task(binds = IRQ1, server = [budget_us = 2_000, period_us = 20_000], wcet_us = 1_000)]
+            pub struct Logger;
+        }
+    }
+}
+
+#[test]
+fn served_tasks_are_analyzed_from_their_server() {
+    let app = parse(served_app());
+    let logger = app
+        .tasks
+        .iter()
+        .find(|t| t.task_struct.ident == "Logger")
+        .unwrap();
+
+    assert_eq!(
+        logger.server,
+        Some(ServerParams {
+            budget_us: 2_000,
+            period_us: 20_000
+        })
+    );
+    assert_eq!(logger.deadline_us, 20_000);
+    assert_eq!(logger.min_interarrival_us, None);
+    assert_eq!(logger.wcet_us, Some(1_000));
+    assert_eq!(logger.max_jobs(), 1);
+    // The server gets its own priority level, below the control loop
+    assert_eq!(logger.dispatcher_priority, 1);
+
+    // 80% for the control loop, and the server budget every server period,
+    // whatever the WCET of the logger
+    let test = demand_test(&SrpAnalysis::from_app(&app)).unwrap();
+    assert!((test.utilization - 0.9).abs() < 1e-9);
+    assert!(test.schedulable);
+
+    let report = ScheduleReport::new(&app);
+    let server = report.tasks[0].server.as_ref().unwrap();
+    assert!((server.bandwidth - 0.1).abs() < 1e-9);
+    assert!(report.tasks[1].server.is_none());
+}
+
+#[test]
+#[should_panic(expected = "must declare its WCET")]
+fn reject_served_tasks_without_a_wcet() {
+    parse(parse_quote! {
+        mod app {
+            #[task(binds = IRQ1, server = [budget_us = 2_000, period_us = 20_000])]
+            pub struct Logger;
+        }
+    });
+}
+
+#[test]
+#[should_panic(expected = "is shorter than its WCET")]
+fn reject_server_budgets_shorter_than_the_wcet() {
+    parse(parse_quote! {
+        mod app {
+            #[task(binds = IRQ1, server = [budget_us = 2_000, period_us = 20_000], wcet_us = 3_000)]
+            pub struct Logger;
+        }
+    });
+}
+
+#[test]
+#[should_panic(expected = "Please remove `budget_us`")]
+fn reject_budgets_of_served_tasks() {
+    parse(parse_quote! {
+        mod app {
+            #[task(binds = IRQ1, server = [budget_us = 2_000, period_us = 20_000], wcet_us = 1_000, budget_us = 1_000)]
+            pub struct Logger;
+        }
+    });
+}
+
+#[test]
+fn reject_servers_without_a_budget_timer() {
+    let args = RticAttr::parse_from_tokens(default_args()).unwrap();
+    let err = App::parse(&edf_pass(), &args, served_app())
+        .err()
+        .unwrap()
+        .to_string();
+
+    assert!(
+        err.contains("requires the `budget_timer` and `budget_overrun`"),
+        "{err}"
+    );
+}
+
+#[test]
+#[should_panic(expected = "Please remove `min_interarrival_us`")]
+fn reject_interarrival_time_of_served_tasks() {
+    parse(parse_quote! {
+        mod app {
+            #[task(binds = IRQ0, server = [budget_us = 100, period_us = 1_000], min_interarrival_us = 500, wcet_us = 100)]
+            pub struct A;
+        }
+    });
+}
+
+#[test]
+#[should_panic(expected = "whose period is its deadline")]
+fn reject_deadline_of_served_tasks() {
+    parse(parse_quote! {
+        mod app {
+            #[task(deadline_us = 1_000, binds = IRQ0, server = [budget_us = 100, period_us = 1_000], wcet_us = 100)]
+            pub struct A;
+        }
+    });
+}
+
+#[test]
+#[should_panic(expected = "no longer than its period")]
+fn reject_budget_over_the_period() {
+    parse(parse_quote! {
+        mod app {
+            #[task(binds = IRQ0, server = [budget_us = 2_000, period_us = 1_000])]
+            pub struct A;
+        }
+    });
+}
+
+#[test]
+#[should_panic(expected = "must specify `budget_us` and `period_us`")]
+fn reject_incomplete_server() {
+    parse(parse_quote! {
+        mod app {
+            #[task(binds = IRQ0, server = [budget_us = 100])]
+            pub struct A;
+        }
+    });
+}
@@ -0,0 +1,372 @@
+use proc_macro2::Ident;
+use quote::format_ident;
+use rtic_core::{RticPass, parse_utils::RticAttr};
+use syn::{ItemMod, parse_quote};
+
+use super::{default_args, edf_pass};
+use crate::{
+    edf_pass::{
+        demand::demand_test,
+        parse::App,
+        srp::{Blocker, SrpAnalysis, SrpTask},
+    },
+    types::Deadline,
+};
+
+fn task(name: &str, deadline_us: Deadline, priority: u16, shared: &[&str]) -> SrpTask {
+    SrpTask {
+        name: format_ident!("{name}"),
+        deadline_us,
+        priority,
+        shared: idents(shared),
+        min_interarrival_us: Some(deadline_us),
+        wcet_us: None,
+        cs_us: vec![],
+    }
+}
+
+fn idents(names: &[&str]) -> Vec<Ident> {
+    names.iter().map(|n| format_ident!("{n}")).collect()
+}
+
+fn analyze(app: ItemMod) -> SrpAnalysis {
+    let args = RticAttr::parse_from_tokens(default_args()).unwrap();
+    SrpAnalysis::from_app(&App::parse(&edf_pass(), &args, app).unwrap())
+}
+
+/// Returns (task, resource) pairs
+fn blockers(analysis: &SrpAnalysis, task: &str) -> Vec<(String, String)> {
+    analysis
+        .task(&format_ident!("{task}"))
+        .unwrap()
+        .blockers
+        .iter()
+        .map(|Blocker { task, resource, .. }| (task.to_string(), resource.to_string()))
+        .collect()
+}
+
+/// An app in the shape of the QEMU examples: two tasks sharing a resource, a
+/// third one at an intermediate deadline which doesn't access it, and a
+/// fourth one sharing a deadline with the most urgent task
+fn shared_app() -> ItemMod {
+    parse_quote! {
+        mod app {
+            #[shared]
+            struct Shared {
+                x: u32,
+                y: u32,
+            }
+
+            #[task(deadline_us = 100_000, binds = IRQ0, shared = [x])]
+            pub struct Slow;
+
+            #[task(deadline_us = 50_000, binds = IRQ1)]
+            pub struct Medium;
+
+            #[task(deadline_us = 10_000, binds = IRQ2, shared = [x])]
+            pub struct Fast;
+
+            #[task(deadline_us = 10_000, binds = IRQ3, shared = [y])]
+            pub struct Sibling;
+        }
+    }
+}
+
+/// An app in the shape of the wait queue example, without shared resources
+fn unshared_app() -> ItemMod {
+    parse_quote! {
+        mod app {
+            #[shared]
+            struct Shared {
+                unused: u32,
+            }
+
+            #[task(deadline_us = 50_000, binds = IRQ0)]
+            pub struct A;
+
+            #[task(deadline_us = 300_000, binds = IRQ1)]
+            pub struct B;
+        }
+    }
+}
+
+#[test]
+fn preemption_levels_follow_deadlines() {
+    let analysis = analyze(shared_app());
+
+    let levels: Vec<_> = analysis
+        .tasks
+        .iter()
+        .map(|t| (t.name.to_string(), t.preemption_level, t.priority))
+        .collect();
+
+    assert_eq!(
+        levels,
+        [
+            ("Slow".into(), 1, 1),
+            ("Medium".into(), 2, 2),
+            ("Fast".into(), 3, 3),
+            ("Sibling".into(), 3, 3),
+        ]
+    );
+
+    let ceilings: Vec<_> = analysis
+        .resources
+        .iter()
+        .map(|r| (r.name.to_string(), r.ceiling, r.priority_ceiling))
+        .collect();
+    assert_eq!(
+        ceilings,
+        [
+            ("x".into(), Some(3), Some(3)),
+            ("y".into(), Some(3), Some(3))
+        ]
+    );
+}
+
+#[test]
+fn blocking_by_lower_preemption_levels() {
+    let analysis = analyze(shared_app());
+
+    // Slow's critical section on `x` raises the system ceiling above Medium's
+    // level, so Medium can be blocked without accessing `x`
+    assert_eq!(blockers(&analysis, "Medium"), [("Slow".into(), "x".into())]);
+    assert_eq!(blockers(&analysis, "Fast"), [("Slow".into(), "x".into())]);
+    // `y` is only accessed at the highest level
+    assert_eq!(
+        blockers(&analysis, "Sibling"),
+        [("Slow".into(), "x".into())]
+    );
+    assert_eq!(blockers(&analysis, "Slow"), []);
+}
+
+#[test]
+fn pass_accepts_example_apps() {
+    for app in [shared_app(), unshared_app()] {
+        let args = RticAttr::parse_from_tokens(default_args()).unwrap();
+        let parsed = App::parse(&edf_pass(), &args, app.clone()).unwrap();
+        SrpAnalysis::from_app(&parsed)
+            .verify(parsed.timestamper_priority)
+            .unwrap();
+
+        edf_pass().run_pass(default_args(), app).unwrap();
+    }
+}
+
+#[test]
+fn reject_unbounded_priority_inversion() {
+    // `Medium` has a longer deadline than `Fast`, but a higher priority: it
+    // could preempt `Slow` while it holds `x` and `Fast` waits for it
+    let analysis = SrpAnalysis::new(
+        &[
+            task("Slow", 100_000, 1, &["x"]),
+            task("Medium", 50_000, 3, &[]),
+            task("Fast", 10_000, 2, &["x"]),
+        ],
+        &idents(&["x"]),
+    );
+
+    let err = analysis.verify(8).unwrap_err().to_string();
+    assert!(err.contains("unbounded priority inversion"), "{err}");
+    assert!(err.contains("`Medium`"), "{err}");
+}
+
+#[test]
+fn reject_blocking_beyond_srp() {
+    let analysis = SrpAnalysis::new(
+        &[
+            task("Slow", 100_000, 1, &["x"]),
+            task("Medium", 50_000, 3, &["x"]),
+            task("Fast", 10_000, 2, &[]),
+        ],
+        &idents(&["x"]),
+    );
+
+    let err = analysis.verify(8).unwrap_err().to_string();
+    assert!(err.contains("Task `Fast`"), "{err}");
+    assert!(
+        err.contains("its preemption level allows it to preempt"),
+        "{err}"
+    );
+}
+
+#[test]
+fn reject_ceiling_masking_timestampers() {
+    let analysis = SrpAnalysis::new(
+        &[task("A", 10_000, 2, &["x"]), task("B", 20_000, 1, &["x"])],
+        &idents(&["x"]),
+    );
+
+    assert!(analysis.verify(3).is_ok());
+
+    let err = analysis.verify(2).unwrap_err().to_string();
+    assert!(
+        err.contains("would mask the timestamper interrupts"),
+        "{err}"
+    );
+}
+
+/// The shared app, annotated with WCETs and critical section lengths
+fn annotated_app(slow_wcet_us: u32) -> ItemMod {
+    let slow_wcet_us = proc_macro2::Literal::u32_unsuffixed(slow_wcet_us);
+
+    parse_quote! {
+        mod app {
+            #[shared]
+            struct Shared {
+                x: u32,
+                y: u32,
+            }
+
+            #[task(deadline_us = 100_000, binds = IRQ0, shared = [x], min_interarrival_us = 100_000, wcet_us = #slow_wcet_us, cs_us = [x = 3_000])]
+            pub struct Slow;
+
+            #[task(deadline_us = 50_000, binds = IRQ1, shared = [y], min_interarrival_us = 50_000, wcet_us = 10_000, cs_us = [y = 1_000])]
+            pub struct Medium;
+
+            #[task(deadline_us = 10_000, binds = IRQ2, shared = [x, y], min_interarrival_us = 10_000, wcet_us = 2_000, cs_us = [x = 500, y = 200])]
+            pub struct Fast;
+        }
+    }
+}
+
+#[test]
+fn blocking_is_the_longest_blocking_critical_section() {
+    let analysis = analyze(annotated_app(20_000));
+
+    let blocking: Vec<_> = analysis
+        .tasks
+        .iter()
+        .map(|t| (t.name.to_string(), t.blocking_us))
+        .collect();
+
+    assert_eq!(
+        blocking,
+        [
+            ("Slow".into(), Some(0)),
+            ("Medium".into(), Some(3_000)),
+            ("Fast".into(), Some(3_000)),
+        ]
+    );
+
+    // Without the critical section lengths, the blocking is unknown
+    let analysis = analyze(shared_app());
+    assert_eq!(
+        analysis.task(&format_ident!("Fast")).unwrap().blocking_us,
+        None
+    );
+    assert_eq!(
+        analysis.task(&format_ident!("Slow")).unwrap().blocking_us,
+        Some(0)
+    );
+    assert!(analysis.schedulability().is_none());
+}
+
+#[test]
+fn baker_test() {
+    let test = analyze(annotated_app(20_000)).schedulability().unwrap();
+
+    let loads: Vec<_> = test
+        .levels
+        .iter()
+        .map(|l| (l.preemption_level, l.deadline_us, l.blocking_us))
+        .collect();
+    assert_eq!(
+        loads,
+        [(3, 10_000, 3_000), (2, 50_000, 3_000), (1, 100_000, 0)]
+    );
+
+    // 2/10 + 3/10
+    assert!((test.levels[0].load - 0.5).abs() < 1e-9);
+    // 2/10 + 10/50 + 3/50
+    assert!((test.levels[1].load - 0.46).abs() < 1e-9);
+    // 2/10 + 10/50 + 20/100
+    assert!((test.levels[2].load - 0.6).abs() < 1e-9);
+    assert!(test.schedulable);
+
+    // Blocking only matters at the levels which can be blocked
+    let test = analyze(annotated_app(55_000)).schedulability().unwrap();
+    assert!((test.levels[2].density - 0.95).abs() < 1e-9);
+    assert!(test.schedulable);
+
+    let analysis = SrpAnalysis::new(
+        &[
+            SrpTask {
+                wcet_us: Some(6_000),
+                ..task("Fast", 10_000, 2, &["x"])
+            },
+            SrpTask {
+                wcet_us: Some(5_000),
+                cs_us: vec![(format_ident!("x"), 5_000)],
+                ..task("Slow", 100_000, 1, &["x"])
+            },
+        ],
+        &idents(&["x"]),
+    );
+
+    let test = analysis.schedulability().unwrap();
+    // 6/10 + 5/10: blocked by the whole of Slow
+    assert!((test.levels[0].load - 1.1).abs() < 1e-9);
+    assert!(!test.schedulable);
+}
+
+#[test]
+fn unbounded_release_rates_cant_be_analyzed() {
+    // Without a minimum inter-arrival time, a job can be released as soon as the
+    // previous one completes
+    let analysis = SrpAnalysis::new(
+        &[SrpTask {
+            wcet_us: Some(1_000),
+            min_interarrival_us: None,
+            ..task("Fast", 10_000, 1, &[])
+        }],
+        &[],
+    );
+
+    assert!(analysis.schedulability().is_none());
+    assert!(demand_test(&analysis).is_none());
+}
+
+#[test]
+fn timing_annotations_are_removed() {
+    let expanded = edf_pass()
+        .run_pass(default_args(), annotated_app(20_000))
+        .unwrap()
+        .1;
+
+    let expanded = quote::quote!(#expanded).to_string();
+    assert!(!expanded.contains("wcet_us"));
+    assert!(!expanded.contains("cs_us"));
+}
+
+#[test]
+#[should_panic(expected = "isn't in its `shared` resources")]
+fn critical_section_on_unshared_resource() {
+    analyze(parse_quote! {
+        mod app {
+            #[shared]
+            struct Shared {
+                x: u32,
+            }
+
+            #[task(deadline_us = 10_000, binds = IRQ0, cs_us = [x = 100])]
+            pub struct A;
+        }
+    });
+}
+
+#[test]
+#[should_panic(expected = "is longer than its WCET")]
+fn critical_section_longer_than_wcet() {
+    analyze(parse_quote! {
+        mod app {
+            #[shared]
+            struct Shared {
+                x: u32,
+            }
+
+            #[task(deadline_us = 10_000, binds = IRQ0, shared = [x], wcet_us = 50, cs_us = [x = 100])]
+            pub struct A;
+        }
+    });
+}
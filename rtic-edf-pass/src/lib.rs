@@ -9,6 +9,7 @@ pub use edf_pass::*;
 
 pub mod scheduler;
 pub mod task;
+pub mod time;
 pub mod types;
 
 pub mod export;
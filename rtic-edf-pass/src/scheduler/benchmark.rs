@@ -1,18 +1,220 @@
+//! Cycle-accurate timing probes, enabled by the `benchmark` feature.
+//!
+//! A probe measures the number of DWT cycles spent in a scope:
+//!
+//! ```ignore
+//! let _probe = benchmark::probe(Probe::Enqueue);
+//! // Measured code
+//! ```
+//!
+//! Every [`Probe`] accumulates its sample count, minimum, maximum and total
+//! duration in a fixed table. The start of a measurement lives in its
+//! [`ProbeGuard`], so probes can be nested and preempted by other probes. A
+//! measurement which is preempted includes the time spent in the preempting
+//! code.
+//!
+//! Recording a measurement takes a critical section. So that a probe nested in
+//! another one (such as [`Probe::Enqueue`] within [`Probe::Schedule`]) doesn't
+//! skew the outer measurement, the cycles spent recording are accumulated, and
+//! the ones accrued during a measurement are subtracted from it. What remains
+//! is the few cycles taken to enter and leave the inner probe.
+//!
+//! Call [`report`] from a low priority context (for example the idle task) to
+//! log the table, which the `bench-runner` host crate parses.
+
 use core::sync::atomic::{AtomicU32, Ordering};
 
-static CYCLES: AtomicU32 = AtomicU32::new(0);
+/// Scheduler operations measured by the probes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum Probe {
+    /// A whole call to `Scheduler::schedule`
+    Schedule = 0,
+    /// Inserting a job in the wait queue
+    Enqueue = 1,
+    /// Popping a job from the wait queue
+    Pop = 2,
+    /// A whole call to `Scheduler::dispatcher_exit`
+    DispatcherExit = 3,
+}
+
+impl Probe {
+    /// Number of probes
+    pub const COUNT: usize = 4;
+
+    pub const ALL: [Probe; Self::COUNT] = [
+        Self::Schedule,
+        Self::Enqueue,
+        Self::Pop,
+        Self::DispatcherExit,
+    ];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Schedule => "schedule",
+            Self::Enqueue => "enqueue",
+            Self::Pop => "pop",
+            Self::DispatcherExit => "dispatcher_exit",
+        }
+    }
+}
+
+/// Accumulated measurements of a single probe. Only updated inside critical
+/// sections.
+struct ProbeStats {
+    count: AtomicU32,
+    min: AtomicU32,
+    max: AtomicU32,
+    // 64-bit total, split because ARMv7-M has no 64-bit atomics
+    total_lo: AtomicU32,
+    total_hi: AtomicU32,
+}
+
+impl ProbeStats {
+    const fn new() -> Self {
+        Self {
+            count: AtomicU32::new(0),
+            min: AtomicU32::new(u32::MAX),
+            max: AtomicU32::new(0),
+            total_lo: AtomicU32::new(0),
+            total_hi: AtomicU32::new(0),
+        }
+    }
+}
+
+/// A consistent copy of a probe's measurements, in CPU cycles
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ProbeSnapshot {
+    pub count: u32,
+    /// `u32::MAX` if there are no samples
+    pub min: u32,
+    pub max: u32,
+    pub total: u64,
+}
+
+static PROBES: [ProbeStats; Probe::COUNT] = [const { ProbeStats::new() }; Probe::COUNT];
+
+/// Total cycles spent recording measurements, wrapping. Only updated inside
+/// critical sections.
+static BOOKKEEPING: AtomicU32 = AtomicU32::new(0);
+
+/// Measures the cycles elapsed until it is dropped
+#[must_use = "the measurement ends when the guard is dropped"]
+pub struct ProbeGuard {
+    probe: Probe,
+    start: u32,
+    /// Value of [`BOOKKEEPING`] at the start
+    bookkeeping: u32,
+}
+
+impl Drop for ProbeGuard {
+    #[inline(always)]
+    fn drop(&mut self) {
+        let end = cycles();
 
+        critical_section::with(|_| {
+            let bookkeeping = BOOKKEEPING.load(Ordering::Relaxed);
+            // Leave out the cycles spent recording the probes nested in this one
+            let nested = bookkeeping.wrapping_sub(self.bookkeeping);
+            record(
+                self.probe,
+                end.wrapping_sub(self.start).wrapping_sub(nested),
+            );
+
+            BOOKKEEPING.store(
+                bookkeeping.wrapping_add(cycles().wrapping_sub(end)),
+                Ordering::Relaxed,
+            );
+        })
+    }
+}
+
+/// Start measuring a probe
 #[inline(always)]
-pub fn begin_trace() {
-    let dwt = unsafe { cortex_m::peripheral::Peripherals::steal() }.DWT;
-    CYCLES.store(dwt.cyccnt.read(), Ordering::SeqCst);
+pub fn probe(probe: Probe) -> ProbeGuard {
+    ProbeGuard {
+        probe,
+        bookkeeping: BOOKKEEPING.load(Ordering::Relaxed),
+        start: cycles(),
+    }
+}
+
+/// Returns the measurements of a probe
+pub fn snapshot(probe: Probe) -> ProbeSnapshot {
+    let stats = &PROBES[probe as usize];
+
+    critical_section::with(|_| ProbeSnapshot {
+        count: stats.count.load(Ordering::Relaxed),
+        min: stats.min.load(Ordering::Relaxed),
+        max: stats.max.load(Ordering::Relaxed),
+        total: (stats.total_hi.load(Ordering::Relaxed) as u64) << 32
+            | stats.total_lo.load(Ordering::Relaxed) as u64,
+    })
+}
+
+/// Clear the measurements of every probe
+pub fn reset() {
+    critical_section::with(|_| {
+        for stats in PROBES.iter() {
+            stats.count.store(0, Ordering::Relaxed);
+            stats.min.store(u32::MAX, Ordering::Relaxed);
+            stats.max.store(0, Ordering::Relaxed);
+            stats.total_lo.store(0, Ordering::Relaxed);
+            stats.total_hi.store(0, Ordering::Relaxed);
+        }
+    })
+}
+
+/// Log the measurements of every probe which has samples, as
+/// `[PROBE] <name>: count=<n> min=<n> max=<n> total=<n>`
+pub fn report() {
+    for probe in Probe::ALL {
+        let s = snapshot(probe);
+        if s.count == 0 {
+            continue;
+        }
+
+        defmt::info!(
+            "[PROBE] {=str}: count={=u32} min={=u32} max={=u32} total={=u64}",
+            probe.name(),
+            s.count,
+            s.min,
+            s.max,
+            s.total
+        );
+    }
 }
 
 #[inline(always)]
-pub fn print_trace() {
-    let dwt = unsafe { cortex_m::peripheral::Peripherals::steal() }.DWT;
-    let now = dwt.cyccnt.read();
-    let prev = CYCLES.load(Ordering::SeqCst);
-    let cycles = now - prev;
-    defmt::debug!("[TIMING]: {} cycles", cycles);
+fn cycles() -> u32 {
+    cortex_m::peripheral::DWT::cycle_count()
+}
+
+/// Must be called inside a critical section
+#[inline]
+fn record(probe: Probe, elapsed: u32) {
+    let stats = &PROBES[probe as usize];
+
+    let count = stats.count.load(Ordering::Relaxed);
+    stats.count.store(count.wrapping_add(1), Ordering::Relaxed);
+
+    if elapsed < stats.min.load(Ordering::Relaxed) {
+        stats.min.store(elapsed, Ordering::Relaxed);
+    }
+
+    if elapsed > stats.max.load(Ordering::Relaxed) {
+        stats.max.store(elapsed, Ordering::Relaxed);
+    }
+
+    let (lo, carry) = stats
+        .total_lo
+        .load(Ordering::Relaxed)
+        .overflowing_add(elapsed);
+    stats.total_lo.store(lo, Ordering::Relaxed);
+    if carry {
+        let hi = stats.total_hi.load(Ordering::Relaxed);
+        stats.total_hi.store(hi.wrapping_add(1), Ordering::Relaxed);
+    }
 }
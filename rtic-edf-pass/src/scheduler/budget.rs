@@ -0,0 +1,163 @@
+use core::sync::atomic::{AtomicU16, Ordering};
+
+use critical_section::CriticalSection;
+
+use crate::types::{AtomicTimestamp, Deadline, Timestamp};
+
+/// The running job has no budget, or already overran it
+const NO_BUDGET: Timestamp = Timestamp::MAX;
+
+/// Budget of a job preempted at some priority level
+struct Preempted {
+    expiry: AtomicTimestamp,
+    owner: AtomicU16,
+    /// Time at which the preempting job started
+    since: AtomicTimestamp,
+}
+
+/// Execution budget enforcement.
+///
+/// Tasks declaring a `budget_us` get their budget armed in a [`BudgetTimer`]
+/// when their dispatcher starts a job, and served tasks get the remaining
+/// budget of their [`Server`](super::Server). A job doesn't consume its budget while
+/// it is preempted: the dispatchers of every task, with a budget or not, save
+/// the budget of the job they preempt, and give it back postponed by the
+/// time they ran once they complete. Only one job runs at every priority
+/// level at once, so one slot per level is enough, like in the
+/// [`RunQueue`](super::RunQueue). The time spent in the timestampers and in
+/// the scheduler is charged to the running job.
+///
+/// Unlike deadlines, expiries wrap around with the time source (the DWT cycle
+/// counter wraps every 35 s at 120 MHz), and are compared through wrapping
+/// differences. Budgets must therefore be shorter than half of the time
+/// source's range.
+///
+/// Only updated inside critical sections.
+///
+/// [`BudgetTimer`]: crate::time::BudgetTimer
+pub struct Budgets<const N: usize> {
+    /// Budget expiry of the running job, or `NO_BUDGET`
+    expiry: AtomicTimestamp,
+    /// Dispatcher index of the running job
+    owner: AtomicU16,
+    /// Job preempted by the running job of every level, by run queue index
+    preempted: [Preempted; N],
+}
+
+#[allow(clippy::new_without_default)]
+impl<const N: usize> Budgets<N> {
+    pub const fn new() -> Self {
+        Self {
+            expiry: AtomicTimestamp::new(NO_BUDGET),
+            owner: AtomicU16::new(0),
+            preempted: [const {
+                Preempted {
+                    expiry: AtomicTimestamp::new(NO_BUDGET),
+                    owner: AtomicU16::new(0),
+                    since: AtomicTimestamp::new(0),
+                }
+            }; N],
+        }
+    }
+
+    /// Returns the budget expiry of the running job, if it has a budget left
+    #[inline]
+    pub fn expiry(&self) -> Option<Timestamp> {
+        match self.expiry.load(Ordering::Relaxed) {
+            NO_BUDGET => None,
+            expiry => Some(expiry),
+        }
+    }
+
+    /// Returns the budget left to the running job at `now`, which is 0 once it
+    /// expired
+    #[inline]
+    pub fn remaining(&self, now: Timestamp) -> Deadline {
+        self.expiry().map_or(0, |expiry| {
+            (expiry.wrapping_sub(now) as i32).max(0) as Deadline
+        })
+    }
+
+    /// Start a job of the task with dispatcher index `dispatcher_idx`, and
+    /// return the expiry the budget timer must be set to
+    pub(super) fn enter(
+        &self,
+        rq_idx: u16,
+        dispatcher_idx: u16,
+        budget: Option<Deadline>,
+        now: Timestamp,
+        _cs: &CriticalSection<'_>,
+    ) -> Option<Timestamp> {
+        let preempted = &self.preempted[rq_idx as usize];
+        preempted
+            .expiry
+            .store(self.expiry.load(Ordering::Relaxed), Ordering::Relaxed);
+        preempted
+            .owner
+            .store(self.owner.load(Ordering::Relaxed), Ordering::Relaxed);
+        preempted.since.store(now, Ordering::Relaxed);
+
+        let expiry = budget.map(|b| not_no_budget(now.wrapping_add(b)));
+        self.expiry
+            .store(expiry.unwrap_or(NO_BUDGET), Ordering::Relaxed);
+        self.owner.store(dispatcher_idx, Ordering::Relaxed);
+        expiry
+    }
+
+    /// Complete the job running at `rq_idx`, giving the preempted job its
+    /// budget back, and return the expiry the budget timer must be set to
+    pub(super) fn exit(
+        &self,
+        rq_idx: u16,
+        now: Timestamp,
+        _cs: &CriticalSection<'_>,
+    ) -> Option<Timestamp> {
+        let preempted = &self.preempted[rq_idx as usize];
+        let elapsed = now.wrapping_sub(preempted.since.load(Ordering::Relaxed));
+
+        let expiry = match preempted.expiry.load(Ordering::Relaxed) {
+            NO_BUDGET => None,
+            expiry => Some(not_no_budget(expiry.wrapping_add(elapsed))),
+        };
+        self.expiry
+            .store(expiry.unwrap_or(NO_BUDGET), Ordering::Relaxed);
+        self.owner
+            .store(preempted.owner.load(Ordering::Relaxed), Ordering::Relaxed);
+        expiry
+    }
+
+    /// Give the running job a new budget, once the previous one expired, and
+    /// return the expiry the budget timer must be set to
+    pub(super) fn recharge(
+        &self,
+        budget: Deadline,
+        now: Timestamp,
+        _cs: &CriticalSection<'_>,
+    ) -> Option<Timestamp> {
+        let expiry = not_no_budget(now.wrapping_add(budget));
+        self.expiry.store(expiry, Ordering::Relaxed);
+        Some(expiry)
+    }
+
+    /// Handle an expiry of the budget timer. Returns the dispatcher index of
+    /// the running job if it overran its budget, which is then only reported
+    /// once.
+    pub(super) fn expire(&self, now: Timestamp, _cs: &CriticalSection<'_>) -> Option<u16> {
+        if (now.wrapping_sub(self.expiry()?) as i32) < 0 {
+            return None;
+        }
+
+        self.expiry.store(NO_BUDGET, Ordering::Relaxed);
+        Some(self.owner.load(Ordering::Relaxed))
+    }
+}
+
+/// Move an expiry which happens to wrap onto [`NO_BUDGET`] one tick earlier
+#[inline]
+fn not_no_budget(expiry: Timestamp) -> Timestamp {
+    if expiry == NO_BUDGET {
+        NO_BUDGET - 1
+    } else {
+        expiry
+    }
+}
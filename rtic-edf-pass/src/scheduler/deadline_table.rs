@@ -0,0 +1,125 @@
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use crate::types::Deadline;
+
+/// Reasons for rejecting a relative deadline change
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DeadlineError {
+    /// Other tasks share the task's priority level. Since every priority level
+    /// has a single relative deadline, their deadlines can't diverge.
+    SharedPriorityLevel,
+    /// The deadline must stay strictly between the deadlines of the adjacent
+    /// priority levels, so that the static priority order still matches the
+    /// deadline order
+    OutOfOrder {
+        /// Deadline of the next higher priority level, or 0
+        shorter: Deadline,
+        /// Deadline of the next lower priority level, or `Deadline::MAX`
+        longer: Deadline,
+    },
+    /// Jobs of the task are active. They keep the previous deadline, so a
+    /// shorter deadline would let the next job preempt them on their own
+    /// priority level.
+    ActiveJobs,
+    /// The deadline would let more jobs of the task overlap than
+    /// [`EdfTaskBinding::MAX_JOBS`](crate::task::EdfTaskBinding::MAX_JOBS), the
+    /// number its timestamper and wait queue are sized for
+    TooManyJobs {
+        /// Longest accepted deadline, `MAX_JOBS * MIN_INTERARRIVAL`
+        longest: Deadline,
+    },
+    /// The deadline doesn't fit in the time source's tick range
+    TooLong,
+    /// The task is served by a constant bandwidth server, whose period is its
+    /// relative deadline
+    Server,
+}
+
+/// Current relative deadline of every priority level, in ticks.
+///
+/// Deadlines are initialized with the ones assigned at compile time. They are
+/// read by the timestampers when a task arrives, and only change through
+/// [`Scheduler::set_relative_deadline`](super::Scheduler::set_relative_deadline)
+/// and mode changes, which replace the whole table with the deadlines of the
+/// new mode.
+/// Priorities are static, so a change must preserve the order of the
+/// deadlines of the priority levels.
+pub struct DeadlineTable<const N: usize> {
+    /// Indexed by run queue index, the lowest priority (longest deadline)
+    /// first
+    deadlines: [AtomicU32; N],
+    /// Number of tasks of every priority level
+    tasks_per_level: [u16; N],
+}
+
+impl<const N: usize> DeadlineTable<N> {
+    pub const fn new(deadlines: [Deadline; N], tasks_per_level: [u16; N]) -> Self {
+        let mut table = [const { AtomicU32::new(0) }; N];
+
+        let mut i = 0;
+        while i < N {
+            table[i] = AtomicU32::new(deadlines[i]);
+            i += 1;
+        }
+
+        Self {
+            deadlines: table,
+            tasks_per_level,
+        }
+    }
+
+    /// Returns the relative deadline of a priority level
+    #[inline]
+    pub fn get(&self, rq_idx: u16) -> Deadline {
+        self.deadlines[rq_idx as usize].load(Ordering::Relaxed)
+    }
+
+    /// Check that `deadline` can replace the deadline of a priority level
+    pub(super) fn check(&self, rq_idx: u16, deadline: Deadline) -> Result<(), DeadlineError> {
+        let idx = rq_idx as usize;
+
+        if self.tasks_per_level[idx] > 1 && deadline != self.get(rq_idx) {
+            return Err(DeadlineError::SharedPriorityLevel);
+        }
+
+        let shorter = match self.deadlines.get(idx + 1) {
+            Some(d) => d.load(Ordering::Relaxed),
+            None => 0,
+        };
+        let longer = match idx.checked_sub(1) {
+            Some(i) => self.deadlines[i].load(Ordering::Relaxed),
+            None => Deadline::MAX,
+        };
+
+        if deadline <= shorter || deadline >= longer {
+            return Err(DeadlineError::OutOfOrder { shorter, longer });
+        }
+
+        Ok(())
+    }
+
+    /// Must only be called after a successful [`check`](Self::check), in the
+    /// same critical section
+    #[inline]
+    pub(super) fn set(&self, rq_idx: u16, deadline: Deadline) {
+        self.deadlines[rq_idx as usize].store(deadline, Ordering::Relaxed);
+    }
+
+    /// Returns whether every deadline is the one in `deadlines`
+    #[inline]
+    pub(super) fn matches(&self, deadlines: &[Deadline; N]) -> bool {
+        self.deadlines
+            .iter()
+            .zip(deadlines)
+            .all(|(slot, deadline)| slot.load(Ordering::Relaxed) == *deadline)
+    }
+
+    /// Replace every deadline, for example with the deadlines of a new mode
+    #[inline]
+    pub(super) fn load(&self, deadlines: &[Deadline; N]) {
+        for (slot, deadline) in self.deadlines.iter().zip(deadlines) {
+            slot.store(*deadline, Ordering::Relaxed);
+        }
+    }
+}
@@ -0,0 +1,105 @@
+//! Single-writer histograms with power-of-two buckets.
+
+use core::sync::atomic::{AtomicU32, Ordering};
+
+/// Number of buckets in a [`Histogram`]
+pub const NUM_BUCKETS: usize = 32;
+
+/// Histogram of `u32` values. Bucket `i` counts the values in
+/// `[2^i, 2^(i+1))`, except bucket 0 which also counts 0.
+///
+/// Must only be written from a single context, as it is updated with plain
+/// load/store pairs.
+pub struct Histogram {
+    buckets: [AtomicU32; NUM_BUCKETS],
+    count: AtomicU32,
+    min: AtomicU32,
+    max: AtomicU32,
+}
+
+/// A copy of a [`Histogram`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct HistogramSnapshot {
+    pub buckets: [u32; NUM_BUCKETS],
+    pub count: u32,
+    /// `u32::MAX` if no values were recorded
+    pub min: u32,
+    pub max: u32,
+}
+
+#[allow(clippy::new_without_default)]
+impl Histogram {
+    pub const fn new() -> Self {
+        Self {
+            buckets: [const { AtomicU32::new(0) }; NUM_BUCKETS],
+            count: AtomicU32::new(0),
+            min: AtomicU32::new(u32::MAX),
+            max: AtomicU32::new(0),
+        }
+    }
+
+    /// Returns the index of the bucket `value` falls into
+    #[inline]
+    pub const fn bucket(value: u32) -> usize {
+        (u32::BITS - value.leading_zeros()).saturating_sub(1) as usize
+    }
+
+    #[inline]
+    pub fn record(&self, value: u32) {
+        let bucket = &self.buckets[Self::bucket(value)];
+        bucket.store(
+            bucket.load(Ordering::Relaxed).wrapping_add(1),
+            Ordering::Relaxed,
+        );
+        self.count.store(
+            self.count.load(Ordering::Relaxed).wrapping_add(1),
+            Ordering::Relaxed,
+        );
+
+        if value < self.min.load(Ordering::Relaxed) {
+            self.min.store(value, Ordering::Relaxed);
+        }
+        if value > self.max.load(Ordering::Relaxed) {
+            self.max.store(value, Ordering::Relaxed);
+        }
+    }
+
+    /// Returns a copy of the histogram. Wrap the call in a critical section to
+    /// get a consistent copy.
+    pub fn snapshot(&self) -> HistogramSnapshot {
+        HistogramSnapshot {
+            buckets: core::array::from_fn(|i| self.buckets[i].load(Ordering::Relaxed)),
+            count: self.count.load(Ordering::Relaxed),
+            min: self.min.load(Ordering::Relaxed),
+            max: self.max.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl HistogramSnapshot {
+    /// Returns an upper bound of the `p`th percentile (`0.0..=1.0`), ie the
+    /// upper bound of the bucket the percentile falls into. Returns `None` if
+    /// the histogram is empty.
+    pub fn percentile_upper_bound(&self, p: f32) -> Option<u32> {
+        if self.count == 0 {
+            return None;
+        }
+
+        let rank = ((self.count as f32 * p) as u32).clamp(1, self.count);
+        let mut seen = 0;
+        for (i, count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                let upper = if i + 1 >= NUM_BUCKETS {
+                    u32::MAX
+                } else {
+                    (1 << (i + 1)) - 1
+                };
+                return Some(upper.min(self.max));
+            }
+        }
+
+        Some(self.max)
+    }
+}
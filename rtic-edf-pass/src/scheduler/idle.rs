@@ -0,0 +1,30 @@
+use crate::types::{Deadline, Timestamp};
+
+/// What the scheduler knows about the upcoming work, for the idle loop to
+/// choose how deeply, and for how long, the core can sleep.
+///
+/// Arrivals are signalled by interrupts, so the scheduler can't predict
+/// them: the idle loop must sleep in a mode the timestamper interrupts can
+/// wake the core up from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct IdleHint {
+    /// Time at which the hint was taken, in ticks of the time source
+    pub now: Timestamp,
+    /// Earliest absolute deadline of the released jobs, if any is still
+    /// active
+    pub next_deadline: Option<Timestamp>,
+    /// Earliest time at which an arrival throttled by a constant bandwidth
+    /// server can be released. The core must be awake by then for it to be
+    /// released on time.
+    pub wake_up: Option<Timestamp>,
+}
+
+impl IdleHint {
+    /// Returns the time left until the core must be awake, saturating at 0,
+    /// or `None` if it can sleep until the next interrupt
+    #[inline]
+    pub fn sleep_budget(&self) -> Option<Deadline> {
+        self.wake_up.map(|t| t.saturating_sub(self.now))
+    }
+}
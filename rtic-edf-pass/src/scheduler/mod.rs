@@ -1,13 +1,31 @@
 use crate::{
     task::{EdfTaskBinding, ScheduledTask, Task},
-    types::Timestamp,
+    types::{Deadline, Timestamp},
 };
 
 mod run_queue;
 pub use run_queue::RunQueue;
 
+mod deadline_table;
+pub use deadline_table::{DeadlineError, DeadlineTable};
+
 pub type WaitQueue<const N: usize> = priority_queue::PriorityQueue<ScheduledTask, N>;
 
+mod modes;
+pub use modes::{Modes, assert_level_order};
+
+mod server;
+pub use server::Server;
+
+mod budget;
+pub use budget::Budgets;
+
+mod idle;
+pub use idle::IdleHint;
+
+mod slack;
+pub use slack::SpareTime;
+
 mod system_deadline;
 pub use system_deadline::SystemDeadline;
 
@@ -16,21 +34,108 @@ pub use critical_section::CriticalSection;
 #[cfg(feature = "benchmark")]
 pub mod benchmark;
 
+#[cfg(feature = "stats")]
+pub mod stats;
+
+#[cfg(feature = "trace")]
+pub mod trace;
+
+pub mod histogram;
+
+#[cfg(feature = "response-time")]
+pub mod response_time;
+
+#[cfg(test)]
+mod tests;
+
 /// EDF scheduler. This trait is implemented at the `rtic-edf-pass` codegen
 /// step.
 pub trait Scheduler<const NUM_DISPATCH_PRIOS: usize, const Q_LEN: usize>: Sized {
     fn now() -> Timestamp;
     fn pend_dispatcher(idx: u16);
 
+    /// Unmask the timestamper interrupts of the tasks active in `mode` which
+    /// were stopped by a mode change, discarding the arrivals signalled in the
+    /// meantime, and mask the timestamper interrupts of the other tasks.
+    ///
+    /// Only called at an idle instant, when the timestamper interrupt of every
+    /// task is either unmasked, or masked because of a mode change.
+    fn switch_timestampers(mode: u16);
+
+    /// Release the arrivals throttled by the constant bandwidth servers which
+    /// caught up with `now`, by unmasking and pending their timestamper
+    /// interrupts
+    fn resume_servers(cs: &CriticalSection<'_>, now: Timestamp);
+
+    /// Returns the earliest time at which an arrival throttled by a constant
+    /// bandwidth server can be released, if any is throttled
+    #[inline]
+    fn next_server_resume(_cs: &CriticalSection<'_>) -> Option<Timestamp> {
+        None
+    }
+
+    /// Returns the constant bandwidth server of the task with dispatcher index
+    /// `dispatcher_idx`, if it is served
+    #[inline]
+    fn server(_dispatcher_idx: u16) -> Option<&'static Server> {
+        None
+    }
+
+    /// Returns the worst-case spare time of the tasks active in `mode`, if every
+    /// task declares a WCET
+    #[inline]
+    fn spare_time(_mode: u16) -> Option<SpareTime> {
+        None
+    }
+
+    /// Start the budget timer, if a task declares an execution budget or is
+    /// served
+    ///
+    /// # Safety
+    ///
+    /// Must be called exactly once, during initialization and before any task
+    /// can be scheduled.
+    #[inline]
+    unsafe fn start_budget_timer() {}
+
+    /// Arm the budget timer to expire at `expiry`, or disarm it
+    #[inline]
+    fn set_budget_timer(_expiry: Option<Timestamp>) {}
+
     fn run_queue(&self) -> &RunQueue<NUM_DISPATCH_PRIOS>;
     fn system_deadline(&self) -> &SystemDeadline;
     fn wait_queue(&self) -> &WaitQueue<Q_LEN>;
+    fn deadlines(&self) -> &DeadlineTable<NUM_DISPATCH_PRIOS>;
+    fn modes(&self) -> &Modes<NUM_DISPATCH_PRIOS>;
+
+    /// Execution budgets of the running jobs, if a task declares one or is
+    /// served
+    #[inline]
+    fn budgets(&self) -> Option<&Budgets<NUM_DISPATCH_PRIOS>> {
+        None
+    }
+
+    /// Returns the sum of [`task_demand`](Self::task_demand) over every task,
+    /// or `None` if a task doesn't declare a WCET
+    #[inline]
+    fn demand(&self, _cs: &CriticalSection<'_>, _window: Deadline) -> Option<Deadline> {
+        None
+    }
+
+    #[cfg(feature = "stats")]
+    fn stats(&self) -> &stats::SchedulerStats;
+
+    #[cfg(feature = "response-time")]
+    fn response_times(&self) -> &response_time::ResponseTimes;
 
     /// Signal to the scheduler that a task wants to run.
     ///
     /// This function must be run either inside a critical section, or at the
     /// highest interrupt priority on the system.
     fn schedule(&self, cs: CriticalSection<'_>, task: Task) {
+        #[cfg(feature = "benchmark")]
+        let _probe = benchmark::probe(benchmark::Probe::Schedule);
+
         #[cfg(feature = "defmt")]
         let rel_dl = task.rel_deadline();
 
@@ -38,6 +143,11 @@ pub trait Scheduler<const NUM_DISPATCH_PRIOS: usize, const Q_LEN: usize>: Sized
         let task = task.into_scheduled(now);
         let sys_dl = self.system_deadline().load();
 
+        Self::resume_servers(&cs, now);
+
+        #[cfg(feature = "trace")]
+        trace::record(trace::EventKind::Arrival, task.dispatcher_index(), now);
+
         #[cfg(feature = "defmt")]
         defmt::trace!(
             "[SCHEDULE] now: {}, rel dl: {}, abs dl: {}, sys dl: {}, dispatcher idx: {}, run queue idx: {}",
@@ -61,12 +171,39 @@ pub trait Scheduler<const NUM_DISPATCH_PRIOS: usize, const Q_LEN: usize>: Sized
         if task.abs_deadline() < sys_dl {
             #[cfg(feature = "defmt")]
             defmt::trace!("[DIRECT EXECUTE]");
+
+            #[cfg(feature = "trace")]
+            trace::record(
+                trace::EventKind::DirectExecute,
+                task.dispatcher_index(),
+                now,
+            );
+
+            #[cfg(feature = "stats")]
+            self.stats()
+                .task(task.dispatcher_index())
+                .record_arrival(true);
+
             execute(self, &cs, task);
         } else {
             {
                 #[cfg(feature = "defmt")]
                 defmt::trace!("[ENQUEUE]");
 
+                #[cfg(feature = "trace")]
+                trace::record(trace::EventKind::Enqueue, task.dispatcher_index(), now);
+
+                #[cfg(feature = "stats")]
+                {
+                    self.stats()
+                        .task(task.dispatcher_index())
+                        .record_arrival(false);
+                    self.stats().record_enqueue();
+                }
+
+                #[cfg(feature = "benchmark")]
+                let _probe = benchmark::probe(benchmark::Probe::Enqueue);
+
                 self.wait_queue()
                     .insert(task)
                     .expect("Queue ran out of space");
@@ -74,10 +211,341 @@ pub trait Scheduler<const NUM_DISPATCH_PRIOS: usize, const Q_LEN: usize>: Sized
         }
     }
 
+    /// Change the relative deadline of task `T`, in ticks of the time source.
+    ///
+    /// The new deadline applies to the jobs released afterwards. It must keep
+    /// the task's priority level between the adjacent ones in deadline order,
+    /// and the level must not be shared with other tasks. A shorter deadline
+    /// is only accepted while no job of the task is active. A task with a
+    /// minimum inter-arrival time can't overlap more than `T::MAX_JOBS` jobs,
+    /// which bounds its deadline. The deadline of a task served by a constant
+    /// bandwidth server is its server period, and can't change.
+    ///
+    /// The schedulability analysis done at compile time only holds for the
+    /// deadlines declared in the app.
+    fn set_relative_deadline<T: EdfTaskBinding>(
+        &self,
+        deadline: Deadline,
+    ) -> Result<(), DeadlineError> {
+        // The timestampers read the table at the highest priority
+        critical_section::with(|_| {
+            let table = self.deadlines();
+            if T::server().is_some() {
+                return Err(DeadlineError::Server);
+            }
+            table.check(T::RUN_QUEUE_IDX, deadline)?;
+
+            if let Some(min_interarrival) = T::MIN_INTERARRIVAL {
+                let longest = min_interarrival.saturating_mul(T::MAX_JOBS as Deadline);
+                if deadline > longest {
+                    return Err(DeadlineError::TooManyJobs { longest });
+                }
+            }
+
+            if deadline < table.get(T::RUN_QUEUE_IDX) && T::active_jobs().get() > 0 {
+                return Err(DeadlineError::ActiveJobs);
+            }
+
+            #[cfg(feature = "defmt")]
+            defmt::trace!(
+                "[SET DEADLINE] run queue idx: {}, rel dl: {}",
+                T::RUN_QUEUE_IDX,
+                deadline
+            );
+
+            table.set(T::RUN_QUEUE_IDX, deadline);
+            Ok(())
+        })
+    }
+
+    /// Request a switch to operating mode `mode`.
+    ///
+    /// The switch happens at the next idle instant, when no job is active: it
+    /// may be immediate if called from the idle loop, and happens after the
+    /// calling job completes at the earliest if called from a task. Until
+    /// then, only the tasks active in both the current and the requested mode
+    /// keep releasing jobs. The switch replaces the relative deadlines,
+    /// including the ones changed by
+    /// [`set_relative_deadline`](Self::set_relative_deadline), with the
+    /// deadlines of the new mode.
+    fn request_mode(&self, mode: u16) {
+        critical_section::with(|cs| {
+            self.modes().request(mode);
+
+            #[cfg(feature = "defmt")]
+            defmt::trace!(
+                "[REQUEST MODE] current: {}, requested: {}",
+                self.modes().current(),
+                mode
+            );
+
+            switch_mode_if_idle(self, cs);
+        })
+    }
+
+    /// Cancel the jobs of task `T` waiting in the wait queue, and return how
+    /// many were cancelled.
+    ///
+    /// Jobs which were already handed over to their dispatcher, including the
+    /// running one, can't be cancelled. Like when a job completes, the
+    /// timestamper of `T` is unpended and unmasked, so that the next arrival
+    /// can be released.
+    fn cancel_pending_jobs<T: EdfTaskBinding>(&self) -> u16 {
+        critical_section::with(|cs| {
+            let cancelled = self
+                .wait_queue()
+                .remove_if(|task| task.dispatcher_index() == T::DISPATCHER_IDX)
+                as u16;
+
+            #[cfg(feature = "defmt")]
+            defmt::trace!(
+                "[CANCEL] dispatcher idx: {}, cancelled jobs: {}",
+                T::DISPATCHER_IDX,
+                cancelled
+            );
+
+            #[cfg(feature = "trace")]
+            let now = Self::now();
+
+            for _ in 0..cancelled {
+                T::active_jobs().complete(&cs);
+
+                #[cfg(feature = "stats")]
+                self.stats().record_dequeue();
+
+                #[cfg(feature = "trace")]
+                trace::record(trace::EventKind::Cancel, T::DISPATCHER_IDX, now);
+            }
+
+            // The cancelled jobs are the most recent ones
+            #[cfg(any(feature = "stats", feature = "response-time"))]
+            T::arrivals().cancel(cancelled, &cs);
+
+            if cancelled == 0 {
+                return 0;
+            }
+
+            T::unpend_timestamper_interrupt();
+            if self.modes().releases(T::MODES) {
+                unsafe {
+                    T::unmask_timestamper_interrupt();
+                }
+            }
+
+            // A dispatcher exit preempted while popping the wait queue gets nothing out
+            // of its pop, which is left to us
+            if let Some(task) = self.wait_queue().pop() {
+                execute_or_requeue(self, &cs, task);
+            }
+
+            switch_mode_if_idle(self, cs);
+            cancelled
+        })
+    }
+
+    /// Release the arrivals throttled by constant bandwidth servers which
+    /// caught up. This otherwise only happens when a task arrives or
+    /// completes.
+    fn poll_servers(&self) {
+        critical_section::with(|cs| Self::resume_servers(&cs, Self::now()));
+    }
+
+    /// Returns what the scheduler knows about the upcoming work: the earliest
+    /// deadline out of the running jobs and the wait queue, and the earliest
+    /// throttled server arrival.
+    fn idle_hint(&self, cs: CriticalSection<'_>) -> IdleHint {
+        IdleHint {
+            now: Self::now(),
+            next_deadline: earliest_deadline(self, cs),
+            wake_up: Self::next_server_resume(&cs),
+        }
+    }
+
+    /// Run one iteration of the idle loop: call `sleep` with the current
+    /// [`IdleHint`], then release the server arrivals which caught up while
+    /// sleeping.
+    ///
+    /// `sleep` runs inside a critical section, so that no arrival can happen
+    /// between the time the hint is taken and the core going to sleep. A
+    /// Cortex-M core still wakes up from WFI on an interrupt masked by the
+    /// critical section, which then runs once this function returns.
+    fn idle(&self, sleep: impl FnOnce(IdleHint)) {
+        critical_section::with(|cs| {
+            let hint = self.idle_hint(cs);
+
+            #[cfg(feature = "defmt")]
+            defmt::trace!("[IDLE] {}", hint);
+
+            sleep(hint);
+            Self::resume_servers(&cs, Self::now());
+        })
+    }
+
+    /// Returns the worst-case execution time of the active jobs of task `T`,
+    /// and of the jobs it can release from now on which are due within
+    /// `window`, or `None` if `T` doesn't declare a WCET or a minimum
+    /// inter-arrival time.
+    ///
+    /// Every active job counts for a whole WCET, whatever it already executed.
+    /// The jobs to come are counted like in the processor demand test, as if
+    /// the task released one right now: `max(0, floor((window - D) / T) + 1)`,
+    /// `D` being the current relative deadline of the task and `T` its minimum
+    /// inter-arrival time. None are counted
+    /// if the task can't release jobs in the current mode.
+    fn task_demand<T: EdfTaskBinding>(
+        &self,
+        _cs: &CriticalSection<'_>,
+        window: Deadline,
+    ) -> Option<Deadline> {
+        let wcet = T::WCET?;
+        let min_interarrival = T::MIN_INTERARRIVAL?;
+        let mut jobs = T::active_jobs().get() as Deadline;
+
+        let deadline = self.deadlines().get(T::RUN_QUEUE_IDX);
+        if self.modes().releases(T::MODES) && window >= deadline {
+            let released = (window - deadline) / min_interarrival + 1;
+            jobs = jobs.saturating_add(released);
+        }
+
+        Some(jobs.saturating_mul(wcet))
+    }
+
+    /// Returns how long the processor can be taken away from the tasks right
+    /// now without any job missing its deadline, in ticks, so that a task or
+    /// the idle loop can decide whether to do optional work.
+    ///
+    /// The slack is computed from the declared WCETs, and is conservative:
+    ///
+    /// - every active job, including the calling one, counts for its whole
+    ///   WCET, whatever it already executed
+    /// - every task is assumed to release its next job right now, and the
+    ///   following ones as often as it can, on top of its active jobs
+    /// - the work done within the slack is assumed to delay every job, as if it
+    ///   ran at the highest priority or with interrupts disabled
+    /// - a job which isn't released yet can block the jobs due by the earliest
+    ///   deadline with its longest critical section
+    ///
+    /// Before the earliest deadline of the active jobs, the time left must
+    /// cover the jobs which can be released and due by then, and the blocking.
+    /// Past it, the worst-case spare time computed at compile time, which
+    /// accounts for the blocking, must cover the active jobs on top of them.
+    ///
+    /// Returns 0 if a task doesn't declare a WCET or a minimum inter-arrival
+    /// time, or if a relative deadline was changed through
+    /// [`set_relative_deadline`](Self::set_relative_deadline), since the
+    /// spare time is computed with the deadlines declared in the app.
+    fn slack(&self) -> Deadline {
+        critical_section::with(|cs| {
+            let mode = self.modes().current();
+            if !self.deadlines().matches(self.modes().deadlines(mode)) {
+                return 0;
+            }
+
+            let (Some(spare), Some(active)) = (Self::spare_time(mode), self.demand(&cs, 0)) else {
+                return 0;
+            };
+
+            let Some(deadline) = earliest_deadline(self, cs) else {
+                return spare.min();
+            };
+
+            let window = deadline.saturating_sub(Self::now());
+            let Some(demand) = self.demand(&cs, window) else {
+                return 0;
+            };
+            // Spare time in the intervals ending at the earliest deadline or later, once
+            // the active jobs are done
+            let spare_from_deadline = window
+                .saturating_sub(demand - active)
+                .saturating_sub(spare.blocking())
+                .min(spare.after(window))
+                .saturating_sub(active);
+
+            #[cfg(feature = "defmt")]
+            defmt::trace!(
+                "[SLACK] dl: {}, active demand: {}, spare from dl: {}",
+                deadline,
+                active,
+                spare_from_deadline
+            );
+
+            spare.min().min(spare_from_deadline)
+        })
+    }
+
+    /// Handle an expiry of the budget timer. Returns the dispatcher index of
+    /// the running job if it overran its budget, or if the budget of its server
+    /// ran out. The server deadline is then postponed, and the recharged budget
+    /// armed again.
+    ///
+    /// The job keeps running: it is up to the caller to react to the overrun.
+    fn budget_expired(&self) -> Option<u16> {
+        let budgets = self.budgets()?;
+
+        critical_section::with(|cs| {
+            let now = Self::now();
+            let Some(task) = budgets.expire(now, &cs) else {
+                // Spurious expiry, the running job's budget was armed since
+                Self::set_budget_timer(budgets.expiry());
+                return None;
+            };
+
+            if let Some(server) = Self::server(task) {
+                server.exhaust(&cs);
+                Self::set_budget_timer(budgets.recharge(server.budget(), now, &cs));
+            }
+
+            #[cfg(feature = "defmt")]
+            defmt::trace!("[BUDGET OVERRUN] dispatcher idx: {}", task);
+
+            #[cfg(feature = "stats")]
+            self.stats().task(task).record_overrun();
+
+            Some(task)
+        })
+    }
+
+    /// Dispatcher entry
+    ///
+    /// This function must be called at the top of a dispatcher, before the task
+    /// executes.
+    #[inline]
+    fn dispatcher_entry<T: EdfTaskBinding>(&self) {
+        #[cfg(any(feature = "trace", feature = "response-time"))]
+        let now = Self::now();
+
+        #[cfg(feature = "trace")]
+        trace::record(trace::EventKind::Dispatch, T::DISPATCHER_IDX, now);
+
+        #[cfg(feature = "response-time")]
+        self.response_times()
+            .task(T::DISPATCHER_IDX)
+            .record_start(now);
+
+        // Arm the budget of this job, or what is left of its server's, pausing the
+        // one of the preempted job
+        if let Some(budgets) = self.budgets() {
+            critical_section::with(|cs| {
+                let budget = match T::server() {
+                    Some(server) => Some(server.remaining()),
+                    None => T::BUDGET,
+                };
+                let expiry = budgets.enter(
+                    T::RUN_QUEUE_IDX,
+                    T::DISPATCHER_IDX,
+                    budget,
+                    Self::now(),
+                    &cs,
+                );
+                Self::set_budget_timer(expiry);
+            });
+        }
+    }
+
     /// Check for missed deadlines.
     ///
     /// This function must be called at the top of a dispatcher, before the task
-    /// executes.z
+    /// executes.
     #[inline]
     fn check_missed_deadline(&self, _rq_idx: u16) {
         // The dispatcher runs at its own priority (lower than the timestamper prio).
@@ -85,14 +553,14 @@ pub trait Scheduler<const NUM_DISPATCH_PRIOS: usize, const Q_LEN: usize>: Sized
         // DL are in sync.
         #[cfg(feature = "check-missed-deadlines")]
         critical_section::with(|_| {
-            let prev_deadline = self.run_queue().get(_rq_idx);
+            let _prev_deadline = self.run_queue().get(_rq_idx);
             let _abs_dl = self.system_deadline().load();
 
             #[cfg(feature = "defmt")]
             defmt::trace!(
                 "[DISPATCHER ENTRY] sys dl: {}, task dl: {}",
                 _abs_dl,
-                prev_deadline
+                _prev_deadline
             );
 
             // Optionally assert that the deadline hasn't been missed
@@ -122,9 +590,7 @@ pub trait Scheduler<const NUM_DISPATCH_PRIOS: usize, const Q_LEN: usize>: Sized
 
             #[cfg(all(not(feature = "defmt"), feature = "check-missed-deadlines"))]
             assert!(Self::now() <= _abs_dl, "Missed deadline");
-
-            prev_deadline
-        })
+        });
     }
 
     /// Dispatcher exit
@@ -137,15 +603,26 @@ pub trait Scheduler<const NUM_DISPATCH_PRIOS: usize, const Q_LEN: usize>: Sized
     ///    has a shorter deadline than the currently running task, or if its
     ///    dispatcher is ready to accept a new task.
     ///
-    /// The `prev_deadline` argument must be the timestamp returned by the
-    /// `dispatcher_entry` call that happened immediately before the task
-    /// was run.
-    ///
     /// Unfortunately has to be generic over [`EdfTaskBinding`] because of the
     /// interrupt unmasking associated function, which means it will get
     /// monomorphized.
     #[inline]
     fn dispatcher_exit<T: EdfTaskBinding>(&self, rq_idx: u16) {
+        #[cfg(feature = "benchmark")]
+        let _probe = benchmark::probe(benchmark::Probe::DispatcherExit);
+
+        // Resume the budget of the preempted job first, so that the completed job
+        // can't overrun anymore. A server keeps what is left of the job's budget.
+        if let Some(budgets) = self.budgets() {
+            critical_section::with(|cs| {
+                let now = Self::now();
+                if let Some(server) = T::server() {
+                    server.complete(budgets.remaining(now), &cs);
+                }
+                Self::set_budget_timer(budgets.exit(T::RUN_QUEUE_IDX, now, &cs));
+            });
+        }
+
         let wq = self.wait_queue();
         let prev_deadline = self.run_queue().get(rq_idx);
 
@@ -157,9 +634,38 @@ pub trait Scheduler<const NUM_DISPATCH_PRIOS: usize, const Q_LEN: usize>: Sized
             T::RUN_QUEUE_IDX,
         );
 
+        // Until it is restored, the system deadline is the one of the job that just
+        // completed
+        #[cfg(any(feature = "stats", feature = "trace", feature = "response-time"))]
+        let (now, _abs_dl) = (Self::now(), self.system_deadline().load());
+
+        #[cfg(any(feature = "stats", feature = "response-time"))]
+        let _arrival = T::arrivals().pop();
+
+        #[cfg(feature = "response-time")]
+        self.response_times()
+            .task(T::DISPATCHER_IDX)
+            .record_finish(_arrival, now);
+
+        #[cfg(feature = "stats")]
+        self.stats()
+            .task(T::DISPATCHER_IDX)
+            .record_completion(_arrival, now, _abs_dl);
+
+        #[cfg(feature = "trace")]
+        {
+            trace::record(trace::EventKind::Complete, T::DISPATCHER_IDX, now);
+            if (_abs_dl.wrapping_sub(now) as i32) < 0 {
+                trace::record(trace::EventKind::Miss, T::DISPATCHER_IDX, now);
+            }
+        }
+
         // Restore previous deadline
         self.system_deadline().store(prev_deadline);
 
+        // The timestamper may preempt us to release the next job of this task
+        critical_section::with(|cs| T::active_jobs().complete(&cs));
+
         // The timestamper -> scheduler jump means that we will have exited the
         // timestamper interrupt while the interrupt source is still pending (because
         // the task itself -ie, the user code- must act upon it to clear the interrupt
@@ -167,10 +673,22 @@ pub trait Scheduler<const NUM_DISPATCH_PRIOS: usize, const Q_LEN: usize>: Sized
         // clear the flag). Therefore the timestamper interrupt will have been
         // erroneously re-pended as soon as it is exited, which would lead to the task
         // being scheduled+executed twice if we didn't manually unpend it.
+        //
+        // Tasks with overlapping jobs only mask their timestamper once `T::MAX_JOBS`
+        // jobs are active. No legitimate arrival can be pending then: with a minimum
+        // inter-arrival time `T`, the next job can't be released before the
+        // deadline of this one.
+        //
+        // Tasks stopped by a pending mode change keep their timestamper masked until
+        // they are resumed by the switch.
         T::unpend_timestamper_interrupt();
-        unsafe {
-            T::unmask_timestamper_interrupt();
-        }
+        critical_section::with(|_| {
+            if self.modes().releases(T::MODES) {
+                unsafe {
+                    T::unmask_timestamper_interrupt();
+                }
+            }
+        });
 
         // It's possible that a task showed up in the queue as the previous (just
         // completed) task was running. So we need to check if it would preempt
@@ -182,33 +700,124 @@ pub trait Scheduler<const NUM_DISPATCH_PRIOS: usize, const Q_LEN: usize>: Sized
         // if the run queue's slot is already full for this priority level, it
         // is guaranteed to have a shorter deadline than any dequeued task on
         // the same prio level.
-        let next_task = wq.pop();
+        let next_task = {
+            #[cfg(feature = "benchmark")]
+            let _probe = benchmark::probe(benchmark::Probe::Pop);
+
+            wq.pop()
+        };
 
         critical_section::with(|cs| {
             if let Some(task) = next_task {
-                let sys_dl = self.system_deadline().load();
-
-                if task.abs_deadline() < sys_dl {
-                    #[cfg(feature = "defmt")]
-                    defmt::trace!(
-                        "[DEQUEUE TASK] now: {}, sys dl: {}, task dispatcher: {}, task run queue idx: {}, task dl: {}",
-                        Self::now(),
-                        sys_dl,
-                        task.dispatcher_index(),
-                        task.rq_index(),
-                        task.abs_deadline(),
-                    );
-
-                    execute(self, &cs, task);
-                } else {
-                    // Task isn't ready to run. Put it back into queue.
-                    wq.insert(task).expect("Queue ran out of space");
-                }
+                execute_or_requeue(self, &cs, task);
             }
+
+            Self::resume_servers(&cs, Self::now());
+            switch_mode_if_idle(self, cs);
         });
     }
 }
 
+/// Execute a task popped from the wait queue if its deadline is shorter than
+/// the system deadline, or put it back into the queue.
+///
+/// **Note**: This function is excluded from the [`Scheduler`] trait in order to
+/// avoid it being callable from within an RTIC app.
+#[inline]
+fn execute_or_requeue<S, const D_LEN: usize, const Q_LEN: usize>(
+    scheduler: &S,
+    cs: &critical_section::CriticalSection<'_>,
+    task: ScheduledTask,
+) where
+    S: Scheduler<D_LEN, Q_LEN>,
+{
+    let sys_dl = scheduler.system_deadline().load();
+
+    if task.abs_deadline() < sys_dl {
+        #[cfg(feature = "defmt")]
+        defmt::trace!(
+            "[DEQUEUE TASK] now: {}, sys dl: {}, task dispatcher: {}, task run queue idx: {}, task dl: {}",
+            S::now(),
+            sys_dl,
+            task.dispatcher_index(),
+            task.rq_index(),
+            task.abs_deadline(),
+        );
+
+        #[cfg(feature = "stats")]
+        scheduler.stats().record_dequeue();
+
+        #[cfg(feature = "trace")]
+        trace::record(
+            trace::EventKind::DirectExecute,
+            task.dispatcher_index(),
+            S::now(),
+        );
+
+        execute(scheduler, cs, task);
+    } else {
+        // Task isn't ready to run. Put it back into queue.
+        scheduler
+            .wait_queue()
+            .insert(task)
+            .expect("Queue ran out of space");
+    }
+}
+
+/// Returns the earliest absolute deadline out of the running jobs and the wait
+/// queue, if any job is active.
+///
+/// **Note**: This function is excluded from the [`Scheduler`] trait in order to
+/// avoid it being callable from within an RTIC app.
+#[inline]
+fn earliest_deadline<S, const D_LEN: usize, const Q_LEN: usize>(
+    scheduler: &S,
+    cs: critical_section::CriticalSection<'_>,
+) -> Option<Timestamp>
+where
+    S: Scheduler<D_LEN, Q_LEN>,
+{
+    let running = Some(scheduler.system_deadline().load()).filter(|&dl| dl != Timestamp::MAX);
+    let queued = scheduler
+        .wait_queue()
+        .min_ref(cs)
+        .map(ScheduledTask::abs_deadline);
+
+    running.into_iter().chain(queued).min()
+}
+
+/// Switch to the requested mode, if any, if no job is active.
+///
+/// Every released job is either in the wait queue, or has been executed and
+/// set the system deadline. Once they have all completed, the system deadline
+/// is back to its initial value.
+///
+/// **Note**: This function is excluded from the [`Scheduler`] trait in order to
+/// avoid it being callable from within an RTIC app.
+#[inline]
+fn switch_mode_if_idle<S, const D_LEN: usize, const Q_LEN: usize>(
+    scheduler: &S,
+    cs: critical_section::CriticalSection<'_>,
+) where
+    S: Scheduler<D_LEN, Q_LEN>,
+{
+    let modes = scheduler.modes();
+    if modes.pending().is_none()
+        || scheduler.system_deadline().load() != Timestamp::MAX
+        || scheduler.wait_queue().len(cs) != 0
+    {
+        return;
+    }
+
+    if let Some(mode) = modes.take_pending() {
+        #[cfg(feature = "defmt")]
+        defmt::trace!("[SWITCH MODE] mode: {}", mode);
+
+        scheduler.deadlines().load(modes.deadlines(mode));
+        S::switch_timestampers(mode);
+    }
+}
+
 /// Execute a task
 ///
 /// This function performs the follwing:
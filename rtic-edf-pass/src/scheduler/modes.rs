@@ -0,0 +1,113 @@
+use core::sync::atomic::{AtomicU16, Ordering};
+
+use crate::types::Deadline;
+
+/// No mode change is pending
+const NO_MODE_CHANGE: u16 = u16::MAX;
+
+/// Panics if, in a mode, the deadlines of the priority levels aren't strictly
+/// decreasing with the run queue index.
+///
+/// The order is checked in microseconds when the app is parsed, but a coarse
+/// time source can round two distinct deadlines down to the same number of
+/// ticks. The generated code therefore checks it again, at compile time, once
+/// the deadlines are converted into ticks.
+pub const fn assert_level_order<const N: usize>(deadlines: &[[Deadline; N]]) {
+    let mut mode = 0;
+    while mode < deadlines.len() {
+        let mut level = 1;
+        while level < N {
+            assert!(
+                deadlines[mode][level] < deadlines[mode][level - 1],
+                "Two priority levels get the same deadline in ticks of the time source"
+            );
+            level += 1;
+        }
+        mode += 1;
+    }
+}
+
+/// Operating modes of the task set.
+///
+/// Every mode has its own set of active tasks and relative deadlines, declared
+/// in the app. A mode change requested through
+/// [`Scheduler::request_mode`](super::Scheduler::request_mode) stays pending
+/// until the next idle instant, when no job is active. Meanwhile, tasks which
+/// aren't active in both the current and the requested mode stop releasing
+/// jobs, so that the jobs of the current mode drain.
+///
+/// Only updated inside critical sections.
+pub struct Modes<const N: usize> {
+    /// Relative deadline of every priority level in every mode, in ticks,
+    /// indexed by run queue index
+    deadlines: &'static [[Deadline; N]],
+    current: AtomicU16,
+    /// Requested mode, or `NO_MODE_CHANGE`
+    pending: AtomicU16,
+}
+
+impl<const N: usize> Modes<N> {
+    /// The first mode is the initial one
+    pub const fn new(deadlines: &'static [[Deadline; N]]) -> Self {
+        assert!(
+            !deadlines.is_empty() && deadlines.len() <= u32::BITS as usize,
+            "Unsupported number of modes"
+        );
+
+        Self {
+            deadlines,
+            current: AtomicU16::new(0),
+            pending: AtomicU16::new(NO_MODE_CHANGE),
+        }
+    }
+
+    #[inline]
+    pub fn current(&self) -> u16 {
+        self.current.load(Ordering::Relaxed)
+    }
+
+    /// Returns the requested mode, if a mode change is pending
+    #[inline]
+    pub fn pending(&self) -> Option<u16> {
+        match self.pending.load(Ordering::Relaxed) {
+            NO_MODE_CHANGE => None,
+            mode => Some(mode),
+        }
+    }
+
+    /// Returns whether a task active in the `modes` bit set may release jobs:
+    /// it must be active in the current mode, and in the requested one if a
+    /// mode change is pending
+    #[inline]
+    pub fn releases(&self, modes: u32) -> bool {
+        let active = |mode: u16| modes & (1 << mode) != 0;
+        active(self.current()) && self.pending().is_none_or(active)
+    }
+
+    /// Returns the relative deadlines of the priority levels in `mode`
+    #[inline]
+    pub fn deadlines(&self, mode: u16) -> &'static [Deadline; N] {
+        &self.deadlines[mode as usize]
+    }
+
+    /// Record a mode change request, replacing the pending one if any.
+    ///
+    /// Requesting the current mode while a change is pending still goes
+    /// through a switch at the next idle instant, which resumes the tasks
+    /// stopped in the meantime.
+    pub(super) fn request(&self, mode: u16) {
+        assert!((mode as usize) < self.deadlines.len(), "Unknown mode");
+
+        if mode != self.current() || self.pending().is_some() {
+            self.pending.store(mode, Ordering::Relaxed);
+        }
+    }
+
+    /// Make the requested mode current, and return it
+    pub(super) fn take_pending(&self) -> Option<u16> {
+        let mode = self.pending()?;
+        self.current.store(mode, Ordering::Relaxed);
+        self.pending.store(NO_MODE_CHANGE, Ordering::Relaxed);
+        Some(mode)
+    }
+}
@@ -0,0 +1,120 @@
+//! Per-job timing measurements, enabled by the `response-time` feature.
+//!
+//! For every job, the scheduler records its arrival time (in the task's
+//! timestamper, see [`Arrivals`](crate::task::Arrivals)), its start time (when
+//! the dispatcher starts running it) and its finish time (when the dispatcher
+//! exits). From those, three histograms are maintained per task, in ticks of
+//! the time source:
+//!
+//! - release jitter: from arrival to start,
+//! - busy time: from start to finish. It includes the time during which the
+//!   job was preempted, so it only bounds its execution time from above,
+//! - response time: from arrival to finish.
+
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use super::histogram::{Histogram, HistogramSnapshot};
+use crate::types::Timestamp;
+
+pub struct TaskTimings {
+    // Written by the dispatcher
+    start: AtomicU32,
+    release_jitter: Histogram,
+    busy_time: Histogram,
+    response_time: Histogram,
+}
+
+/// A consistent copy of a task's [`TaskTimings`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TaskTimingsSnapshot {
+    pub release_jitter: HistogramSnapshot,
+    pub busy_time: HistogramSnapshot,
+    pub response_time: HistogramSnapshot,
+}
+
+#[allow(clippy::new_without_default)]
+impl TaskTimings {
+    pub const fn new() -> Self {
+        Self {
+            start: AtomicU32::new(0),
+            release_jitter: Histogram::new(),
+            busy_time: Histogram::new(),
+            response_time: Histogram::new(),
+        }
+    }
+
+    pub fn snapshot(&self) -> TaskTimingsSnapshot {
+        critical_section::with(|_| TaskTimingsSnapshot {
+            release_jitter: self.release_jitter.snapshot(),
+            busy_time: self.busy_time.snapshot(),
+            response_time: self.response_time.snapshot(),
+        })
+    }
+
+    /// Must be called from the task's dispatcher
+    #[inline]
+    pub(super) fn record_start(&self, now: Timestamp) {
+        self.start.store(now, Ordering::Relaxed);
+    }
+
+    /// Record the finish of a job, given its arrival time taken from the
+    /// task's [`Arrivals`](crate::task::Arrivals). Must be called from the
+    /// task's dispatcher.
+    #[inline]
+    pub(super) fn record_finish(&self, arrival: Option<Timestamp>, now: Timestamp) {
+        // Jobs of a task never preempt each other, so the start time is the one of
+        // this job
+        let start = self.start.load(Ordering::Relaxed);
+        self.busy_time.record(now.wrapping_sub(start));
+
+        if let Some(arrival) = arrival {
+            self.release_jitter.record(start.wrapping_sub(arrival));
+            self.response_time.record(now.wrapping_sub(arrival));
+        }
+    }
+}
+
+/// Timings of every task, indexed by dispatcher index.
+///
+/// The scheduler stores a `ResponseTimes<[TaskTimings; N]>` (`N` being the
+/// number of tasks), which is accessed as a `ResponseTimes`.
+pub struct ResponseTimes<T: ?Sized = [TaskTimings]> {
+    tasks: T,
+}
+
+#[allow(clippy::new_without_default)]
+impl<const N: usize> ResponseTimes<[TaskTimings; N]> {
+    pub const fn new() -> Self {
+        Self {
+            tasks: [const { TaskTimings::new() }; N],
+        }
+    }
+}
+
+impl ResponseTimes {
+    /// Returns the timings of a task, indexed by its dispatcher index
+    /// ([`EdfTaskBinding::DISPATCHER_IDX`](crate::task::EdfTaskBinding::DISPATCHER_IDX))
+    #[inline]
+    pub fn task(&self, dispatcher_idx: u16) -> &TaskTimings {
+        &self.tasks[dispatcher_idx as usize]
+    }
+
+    /// Returns the timings of every task, indexed by dispatcher index
+    #[inline]
+    pub fn tasks(&self) -> &[TaskTimings] {
+        &self.tasks
+    }
+
+    /// Print the histograms of every task over defmt
+    #[cfg(feature = "defmt")]
+    pub fn dump(&self) {
+        for (idx, task) in self.tasks.iter().enumerate() {
+            defmt::info!(
+                "[RESPONSE TIME] dispatcher idx: {}, {}",
+                idx,
+                task.snapshot()
+            );
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+use critical_section::CriticalSection;
+
+use crate::types::{AtomicTimestamp, Deadline, Timestamp};
+
+/// Bandwidth server of an aperiodic task, after the Constant Bandwidth Server
+/// (CBS).
+///
+/// The server has a budget `Q` and a period `T`, and keeps a server deadline
+/// `d` and a remaining budget `c`:
+///
+/// - When a job arrives at `r`, if `d <= r` or `c >= (d - r) * Q / T`, the
+///   server starts a new period: `d = r + T` and `c = Q`.
+/// - While a job runs, its dispatcher arms the remaining budget in the
+///   [`BudgetTimer`](crate::time::BudgetTimer), which doesn't run down while
+///   the job is preempted. Every time the budget runs out, the server deadline
+///   is postponed by `T`, the budget is recharged by `Q` and armed again, and
+///   the overrun handler of the app is called.
+/// - When a job completes, what is left of the armed budget becomes `c`.
+///
+/// Priorities are static, so every job is released with the relative deadline
+/// `T` of the task's priority level. Jobs therefore never get the earlier
+/// deadline the CBS may grant them. A job arriving while the server deadline
+/// is postponed more than one period ahead is throttled: it is only released
+/// once `d <= now + T`, at the first scheduling event after that time.
+///
+/// A running job is never suspended: once the budget runs out, it keeps the
+/// processor away from the other tasks until it completes, unless the overrun
+/// handler stops it. The server only delays the next jobs of the task. The
+/// schedulability analysis models the task as a sporadic task with a WCET `Q`
+/// and a minimum inter-arrival time `T`, which only holds if the overrun
+/// handler is never called.
+///
+/// Jobs of the task are never active at once, so the server is always idle
+/// when a job arrives.
+///
+/// Only updated inside critical sections, or by the task's dispatcher.
+pub struct Server {
+    budget: Deadline,
+    period: Deadline,
+    deadline: AtomicTimestamp,
+    remaining: AtomicU32,
+    /// An arrival was throttled
+    waiting: AtomicBool,
+}
+
+impl Server {
+    /// Create a server with a budget and period expressed in ticks
+    pub const fn new(budget: Deadline, period: Deadline) -> Self {
+        assert!(
+            budget > 0 && budget <= period,
+            "The server budget must be greater than 0, and no longer than its period"
+        );
+
+        Self {
+            budget,
+            period,
+            deadline: AtomicTimestamp::new(0),
+            remaining: AtomicU32::new(0),
+            waiting: AtomicBool::new(false),
+        }
+    }
+
+    /// Returns the budget `Q` of the server
+    #[inline]
+    pub fn budget(&self) -> Deadline {
+        self.budget
+    }
+
+    /// Returns the server deadline
+    #[inline]
+    pub fn deadline(&self) -> Timestamp {
+        self.deadline.load(Ordering::Relaxed)
+    }
+
+    /// Returns the remaining budget
+    #[inline]
+    pub fn remaining(&self) -> Deadline {
+        self.remaining.load(Ordering::Relaxed)
+    }
+
+    /// Returns whether the server deadline is postponed more than one period
+    /// ahead of `now`
+    #[inline]
+    pub fn throttled(&self, now: Timestamp) -> bool {
+        self.deadline() > now.saturating_add(self.period)
+    }
+
+    /// Returns the time at which a throttled arrival can be released, if any
+    /// is throttled
+    #[inline]
+    pub fn resumes_at(&self, _cs: &CriticalSection<'_>) -> Option<Timestamp> {
+        self.waiting
+            .load(Ordering::Relaxed)
+            .then(|| self.deadline().saturating_sub(self.period))
+    }
+
+    /// Handle a job arrival. Returns `false` if the job is throttled, in which
+    /// case it must be released again through [`resume`](Self::resume).
+    pub fn admit(&self, now: Timestamp, _cs: &CriticalSection<'_>) -> bool {
+        if self.throttled(now) {
+            self.waiting.store(true, Ordering::Relaxed);
+            return false;
+        }
+
+        let deadline = self.deadline();
+        let new_period = deadline <= now
+            || self.remaining() as u64 * self.period as u64
+                >= (deadline - now) as u64 * self.budget as u64;
+
+        if new_period {
+            self.deadline
+                .store(now.saturating_add(self.period), Ordering::Relaxed);
+            self.remaining.store(self.budget, Ordering::Relaxed);
+        }
+
+        true
+    }
+
+    /// Handle the exhaustion of the budget: postpone the server deadline by one
+    /// period, and recharge the budget
+    pub fn exhaust(&self, _cs: &CriticalSection<'_>) {
+        self.deadline.store(
+            self.deadline().saturating_add(self.period),
+            Ordering::Relaxed,
+        );
+        self.remaining.store(self.budget, Ordering::Relaxed);
+    }
+
+    /// Handle the completion of a job, with `remaining` ticks left of the
+    /// budget armed for it
+    pub fn complete(&self, remaining: Deadline, cs: &CriticalSection<'_>) {
+        if remaining == 0 {
+            self.exhaust(cs);
+        } else {
+            self.remaining.store(remaining, Ordering::Relaxed);
+        }
+    }
+
+    /// Returns `true` if an arrival was throttled and can now be released
+    pub fn resume(&self, now: Timestamp, _cs: &CriticalSection<'_>) -> bool {
+        if self.waiting.load(Ordering::Relaxed) && !self.throttled(now) {
+            self.waiting.store(false, Ordering::Relaxed);
+            true
+        } else {
+            false
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use crate::types::Deadline;
+
+/// Worst-case spare time of the tasks of an operating mode, computed at
+/// compile time from their declared WCETs, deadlines and minimum
+/// inter-arrival times.
+///
+/// In an interval of length `t` starting at an idle instant, the tasks
+/// release at most `sum(dbf_i(t))` of execution time which is due within the
+/// interval (see the processor demand test), and leave the rest to spare.
+/// Since `dbf_i(t) <= t * U_i + max(0, (T_i - D_i) * U_i)`, the spare time
+/// grows at least at the rate `1 - U` with the length of the interval. The
+/// spare time also leaves room for the longest critical section which can
+/// block a job due within the interval (the SRP blocking `B(t)`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SpareTime {
+    /// Shortest spare time in an interval ending at an absolute deadline, in
+    /// ticks
+    min: Deadline,
+    /// `1 - U`, in millionths, rounded down
+    rate_ppm: u32,
+    /// `sum(max(0, (T_i - D_i) * U_i)) + max(B(t))`, in ticks, rounded up
+    offset: Deadline,
+    /// Longest critical section which can block a job, in ticks, rounded up
+    blocking: Deadline,
+}
+
+impl SpareTime {
+    pub const fn new(min: Deadline, rate_ppm: u32, offset: Deadline, blocking: Deadline) -> Self {
+        assert!(rate_ppm <= 1_000_000, "The spare time rate can't exceed 1");
+
+        Self {
+            min,
+            rate_ppm,
+            offset,
+            blocking,
+        }
+    }
+
+    /// Returns the shortest spare time in an interval ending at an absolute
+    /// deadline
+    #[inline]
+    pub fn min(&self) -> Deadline {
+        self.min
+    }
+
+    /// Returns a lower bound of the spare time in the intervals at least
+    /// `len` long ending at an absolute deadline
+    #[inline]
+    pub fn after(&self, len: Deadline) -> Deadline {
+        let grown = (len as u64 * self.rate_ppm as u64 / 1_000_000) as Deadline;
+        grown.saturating_sub(self.offset).max(self.min)
+    }
+
+    /// Returns the longest critical section which can block a job
+    #[inline]
+    pub fn blocking(&self) -> Deadline {
+        self.blocking
+    }
+}
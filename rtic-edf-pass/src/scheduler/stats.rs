@@ -0,0 +1,194 @@
+//! Runtime scheduling statistics, enabled by the `stats` feature.
+//!
+//! Every counter is only ever written from a single context (the task's
+//! timestamper, the task's dispatcher, or a critical section), so plain
+//! load/store pairs are enough to update them. This keeps the overhead down to
+//! a handful of memory accesses per job, and works on ARMv6-M.
+
+use core::sync::atomic::{AtomicI32, AtomicU32, Ordering};
+
+use crate::types::Timestamp;
+
+/// Statistics of a single task. All times are expressed in ticks of the time
+/// source.
+pub struct TaskStats {
+    // Written by the timestamper
+    activations: AtomicU32,
+    direct_dispatches: AtomicU32,
+    enqueued_dispatches: AtomicU32,
+
+    // Written by the dispatcher
+    max_response_time: AtomicU32,
+    min_slack: AtomicI32,
+    deadline_misses: AtomicU32,
+
+    // Written by the budget timer handler
+    budget_overruns: AtomicU32,
+}
+
+/// A consistent copy of a task's [`TaskStats`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TaskStatsSnapshot {
+    /// Number of times the task was released
+    pub activations: u32,
+    /// Number of jobs which were dispatched as soon as they were released
+    pub direct_dispatches: u32,
+    /// Number of jobs which had to wait in the wait queue
+    pub enqueued_dispatches: u32,
+    /// Longest time between a job's release and its completion
+    pub max_response_time: u32,
+    /// Smallest time left before a job's deadline when it completed. Negative
+    /// if a deadline was missed. `i32::MAX` if no job has completed yet.
+    pub min_slack: i32,
+    /// Number of jobs which completed after their deadline
+    pub deadline_misses: u32,
+    /// Number of jobs which ran past their execution budget
+    pub budget_overruns: u32,
+}
+
+#[allow(clippy::new_without_default)]
+impl TaskStats {
+    pub const fn new() -> Self {
+        Self {
+            activations: AtomicU32::new(0),
+            direct_dispatches: AtomicU32::new(0),
+            enqueued_dispatches: AtomicU32::new(0),
+            max_response_time: AtomicU32::new(0),
+            min_slack: AtomicI32::new(i32::MAX),
+            deadline_misses: AtomicU32::new(0),
+            budget_overruns: AtomicU32::new(0),
+        }
+    }
+
+    /// Returns a consistent copy of the statistics
+    pub fn snapshot(&self) -> TaskStatsSnapshot {
+        critical_section::with(|_| TaskStatsSnapshot {
+            activations: self.activations.load(Ordering::Relaxed),
+            direct_dispatches: self.direct_dispatches.load(Ordering::Relaxed),
+            enqueued_dispatches: self.enqueued_dispatches.load(Ordering::Relaxed),
+            max_response_time: self.max_response_time.load(Ordering::Relaxed),
+            min_slack: self.min_slack.load(Ordering::Relaxed),
+            deadline_misses: self.deadline_misses.load(Ordering::Relaxed),
+            budget_overruns: self.budget_overruns.load(Ordering::Relaxed),
+        })
+    }
+
+    /// Record a job release. Must be called from the task's timestamper.
+    #[inline]
+    pub(super) fn record_arrival(&self, direct: bool) {
+        increment(&self.activations);
+        if direct {
+            increment(&self.direct_dispatches);
+        } else {
+            increment(&self.enqueued_dispatches);
+        }
+    }
+
+    /// Record a job completion, given the arrival time of the job taken from
+    /// the task's [`Arrivals`](crate::task::Arrivals). Must be called from the
+    /// task's dispatcher.
+    #[inline]
+    pub(super) fn record_completion(
+        &self,
+        arrival: Option<Timestamp>,
+        now: Timestamp,
+        abs_deadline: Timestamp,
+    ) {
+        if let Some(arrival) = arrival {
+            let response_time = now.wrapping_sub(arrival);
+            if response_time > self.max_response_time.load(Ordering::Relaxed) {
+                self.max_response_time
+                    .store(response_time, Ordering::Relaxed);
+            }
+        }
+
+        let slack = abs_deadline.wrapping_sub(now) as i32;
+        if slack < self.min_slack.load(Ordering::Relaxed) {
+            self.min_slack.store(slack, Ordering::Relaxed);
+        }
+
+        if slack < 0 {
+            increment(&self.deadline_misses);
+        }
+    }
+
+    /// Record a budget overrun. Must be called inside a critical section.
+    #[inline]
+    pub(super) fn record_overrun(&self) {
+        increment(&self.budget_overruns);
+    }
+}
+
+/// Statistics of a whole scheduler, with one [`TaskStats`] per task.
+///
+/// The scheduler stores a `SchedulerStats<[TaskStats; N]>` (`N` being the
+/// number of tasks), which is accessed as a `SchedulerStats`.
+pub struct SchedulerStats<T: ?Sized = [TaskStats]> {
+    // Only written inside critical sections
+    wait_queue_len: AtomicU32,
+    max_wait_queue_len: AtomicU32,
+    tasks: T,
+}
+
+#[allow(clippy::new_without_default)]
+impl<const N: usize> SchedulerStats<[TaskStats; N]> {
+    pub const fn new() -> Self {
+        Self {
+            wait_queue_len: AtomicU32::new(0),
+            max_wait_queue_len: AtomicU32::new(0),
+            tasks: [const { TaskStats::new() }; N],
+        }
+    }
+}
+
+impl SchedulerStats {
+    /// Returns the statistics of a task, indexed by its dispatcher index
+    /// ([`EdfTaskBinding::DISPATCHER_IDX`](crate::task::EdfTaskBinding::DISPATCHER_IDX))
+    #[inline]
+    pub fn task(&self, dispatcher_idx: u16) -> &TaskStats {
+        &self.tasks[dispatcher_idx as usize]
+    }
+
+    /// Returns the statistics of every task, indexed by dispatcher index
+    #[inline]
+    pub fn tasks(&self) -> &[TaskStats] {
+        &self.tasks
+    }
+
+    /// Returns the highest number of jobs which have been waiting in the wait
+    /// queue at the same time
+    #[inline]
+    pub fn max_wait_queue_len(&self) -> u32 {
+        self.max_wait_queue_len.load(Ordering::Relaxed)
+    }
+
+    /// Record a job being added to the wait queue. Must be called inside a
+    /// critical section.
+    #[inline]
+    pub(super) fn record_enqueue(&self) {
+        let len = self.wait_queue_len.load(Ordering::Relaxed) + 1;
+        self.wait_queue_len.store(len, Ordering::Relaxed);
+
+        if len > self.max_wait_queue_len.load(Ordering::Relaxed) {
+            self.max_wait_queue_len.store(len, Ordering::Relaxed);
+        }
+    }
+
+    /// Record a job leaving the wait queue. Must be called inside a critical
+    /// section.
+    #[inline]
+    pub(super) fn record_dequeue(&self) {
+        let len = self.wait_queue_len.load(Ordering::Relaxed);
+        self.wait_queue_len
+            .store(len.saturating_sub(1), Ordering::Relaxed);
+    }
+}
+
+#[inline(always)]
+fn increment(counter: &AtomicU32) {
+    counter.store(
+        counter.load(Ordering::Relaxed).wrapping_add(1),
+        Ordering::Relaxed,
+    );
+}
@@ -0,0 +1,88 @@
+use super::sim::{Medium, MediumToo, Sim, Slow, Urgent};
+use crate::{scheduler::Scheduler, task::EdfTaskBinding, types::Timestamp};
+
+#[test]
+fn cancelled_jobs_leave_the_wait_queue() {
+    let sim = Sim::new();
+
+    // `Medium` runs, `MediumToo` and `Slow` wait behind it
+    sim.arrive::<Medium>();
+    assert_eq!(sim.pended(), [1]);
+    sim.start::<Medium>();
+
+    sim.set_now(5);
+    sim.arrive::<MediumToo>();
+    sim.arrive::<Slow>();
+    assert_eq!(sim.queued(), 2);
+    assert!(sim.masked::<Slow>());
+
+    // The next arrival of `Slow` stays pending
+    sim.arrive::<Slow>();
+    assert!(sim.arrival_pending::<Slow>());
+
+    assert_eq!(sim.cancel_pending_jobs::<Slow>(), 1);
+    assert_eq!(sim.queued(), 1);
+    assert_eq!(Slow::active_jobs().get(), 0);
+    assert!(!sim.masked::<Slow>() && !sim.arrival_pending::<Slow>());
+
+    // Nothing left to cancel
+    assert_eq!(sim.cancel_pending_jobs::<Slow>(), 0);
+
+    // The remaining job runs once `Medium` completes
+    sim.set_now(20);
+    sim.finish::<Medium>();
+    assert_eq!(sim.pended(), [2]);
+    sim.run::<MediumToo>(10);
+
+    assert_eq!(sim.queued(), 0);
+    assert_eq!(sim.system_deadline().load(), Timestamp::MAX);
+}
+
+#[test]
+fn dispatched_jobs_cant_be_cancelled() {
+    let sim = Sim::new();
+
+    sim.arrive::<Medium>();
+    assert_eq!(sim.cancel_pending_jobs::<Medium>(), 0);
+
+    // The job is still pended, and its timestamper stays masked until it
+    // completes
+    assert_eq!(sim.pended(), [1]);
+    assert_eq!(Medium::active_jobs().get(), 1);
+    assert!(sim.masked::<Medium>());
+
+    sim.run::<Medium>(10);
+    assert!(!sim.masked::<Medium>());
+}
+
+#[test]
+fn cancel_every_overlapping_job() {
+    let sim = Sim::new();
+
+    // Two jobs of `Urgent` wait behind the running one
+    sim.arrive::<Urgent>();
+    sim.start::<Urgent>();
+    sim.set_now(1);
+    sim.arrive::<Urgent>();
+    sim.set_now(2);
+    sim.arrive::<Slow>();
+    assert_eq!(sim.queued(), 2);
+    assert!(sim.masked::<Urgent>());
+
+    assert_eq!(sim.cancel_pending_jobs::<Urgent>(), 1);
+    assert_eq!(Urgent::active_jobs().get(), 1);
+    assert!(!sim.masked::<Urgent>());
+
+    // Cancelling unmasks the timestamper, which can release a new job
+    sim.set_now(3);
+    sim.arrive::<Urgent>();
+    assert_eq!(Urgent::active_jobs().get(), 2);
+
+    sim.set_now(5);
+    sim.finish::<Urgent>();
+    assert_eq!(sim.pended(), [3]);
+    sim.run::<Urgent>(2);
+    assert_eq!(sim.pended(), [0]);
+    sim.run::<Slow>(50);
+    assert_eq!(sim.queued(), 0);
+}
@@ -0,0 +1,59 @@
+use super::sim::{Medium, MediumToo, Sim, Slow};
+use crate::scheduler::{IdleHint, Scheduler};
+
+fn hint(sim: &Sim) -> IdleHint {
+    critical_section::with(|cs| sim.idle_hint(cs))
+}
+
+#[test]
+fn idle_hint_has_the_earliest_deadline() {
+    let sim = Sim::new();
+    assert_eq!(
+        hint(&sim),
+        IdleHint {
+            now: 0,
+            next_deadline: None,
+            wake_up: None,
+        }
+    );
+
+    // The running job has the earliest deadline
+    sim.arrive::<Medium>();
+    sim.start::<Medium>();
+    sim.set_now(5);
+    sim.arrive::<MediumToo>();
+    sim.arrive::<Slow>();
+    assert_eq!(hint(&sim).next_deadline, Some(100));
+
+    // Then the job leaving the wait queue, until only `Slow` is left
+    sim.set_now(20);
+    sim.finish::<Medium>();
+    assert_eq!(hint(&sim).next_deadline, Some(105));
+    sim.run::<MediumToo>(10);
+    assert_eq!(hint(&sim).next_deadline, Some(1_005));
+    sim.run::<Slow>(10);
+
+    let mut hints = vec![];
+    sim.idle(|hint| hints.push(hint));
+    assert_eq!(
+        hints,
+        [IdleHint {
+            now: 40,
+            next_deadline: None,
+            wake_up: None,
+        }]
+    );
+}
+
+#[test]
+fn sleep_budget_saturates() {
+    let hint = |wake_up| IdleHint {
+        now: 100,
+        next_deadline: None,
+        wake_up,
+    };
+
+    assert_eq!(hint(None).sleep_budget(), None);
+    assert_eq!(hint(Some(150)).sleep_budget(), Some(50));
+    assert_eq!(hint(Some(90)).sleep_budget(), Some(0));
+}
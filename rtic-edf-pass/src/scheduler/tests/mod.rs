@@ -0,0 +1,307 @@
+mod cancel;
+mod idle;
+mod sim;
+mod slack;
+#[cfg(feature = "stats")]
+mod stats;
+
+use super::{
+    Budgets, DeadlineError, DeadlineTable, Modes, Server, assert_level_order,
+    histogram::{Histogram, NUM_BUCKETS},
+};
+use crate::{task::ActiveJobs, types::Deadline};
+
+#[test]
+fn histogram_buckets() {
+    assert_eq!(Histogram::bucket(0), 0);
+    assert_eq!(Histogram::bucket(1), 0);
+    assert_eq!(Histogram::bucket(2), 1);
+    assert_eq!(Histogram::bucket(3), 1);
+    assert_eq!(Histogram::bucket(4), 2);
+    assert_eq!(Histogram::bucket(1023), 9);
+    assert_eq!(Histogram::bucket(1024), 10);
+    assert_eq!(Histogram::bucket(u32::MAX), NUM_BUCKETS - 1);
+}
+
+#[test]
+fn histogram_record() {
+    let histogram = Histogram::new();
+    assert_eq!(histogram.snapshot().percentile_upper_bound(0.5), None);
+
+    for value in [5, 6, 7, 100, 3000] {
+        histogram.record(value);
+    }
+
+    let snapshot = histogram.snapshot();
+    assert_eq!(snapshot.count, 5);
+    assert_eq!(snapshot.min, 5);
+    assert_eq!(snapshot.max, 3000);
+    assert_eq!(snapshot.buckets[2], 3);
+    assert_eq!(snapshot.buckets[6], 1);
+    assert_eq!(snapshot.buckets[11], 1);
+    assert_eq!(snapshot.buckets.iter().sum::<u32>(), 5);
+
+    assert_eq!(snapshot.percentile_upper_bound(0.5), Some(7));
+    assert_eq!(snapshot.percentile_upper_bound(0.8), Some(127));
+    assert_eq!(snapshot.percentile_upper_bound(1.0), Some(3000));
+}
+
+#[test]
+fn active_jobs() {
+    let jobs = ActiveJobs::new();
+
+    critical_section::with(|cs| {
+        assert_eq!(jobs.release(&cs), 1);
+        assert_eq!(jobs.release(&cs), 2);
+        jobs.complete(&cs);
+        assert_eq!(jobs.get(), 1);
+        jobs.complete(&cs);
+        jobs.complete(&cs);
+        assert_eq!(jobs.get(), 0);
+    });
+}
+
+#[cfg(any(feature = "stats", feature = "response-time"))]
+#[test]
+fn arrivals_are_popped_in_release_order() {
+    use crate::task::Arrivals;
+    use core::sync::atomic::AtomicU32;
+
+    let arrivals: &Arrivals = &Arrivals::<[AtomicU32; 3]>::new();
+    assert_eq!(arrivals.pop(), None);
+
+    critical_section::with(|cs| {
+        for now in 0..5 {
+            arrivals.push(now, &cs);
+            arrivals.push(now + 10, &cs);
+            assert_eq!(arrivals.pop(), Some(now));
+            assert_eq!(arrivals.pop(), Some(now + 10));
+            assert!(arrivals.is_empty());
+        }
+
+        // Arrivals past the capacity are dropped
+        for now in 0..4 {
+            arrivals.push(now, &cs);
+        }
+        assert_eq!(arrivals.len(), 3);
+
+        // Cancelled jobs are the most recent ones
+        arrivals.cancel(2, &cs);
+        arrivals.push(7, &cs);
+        assert_eq!(arrivals.pop(), Some(0));
+        assert_eq!(arrivals.pop(), Some(7));
+        assert_eq!(arrivals.pop(), None);
+    });
+}
+
+#[test]
+fn deadline_changes_keep_the_priority_order() {
+    // Levels by increasing priority: 100, 50 (shared by two tasks), 10
+    let table = DeadlineTable::new([100, 50, 10], [1, 2, 1]);
+
+    assert_eq!(table.check(0, 60), Ok(()));
+    assert_eq!(table.check(0, Deadline::MAX - 1), Ok(()));
+    assert_eq!(
+        table.check(0, 50),
+        Err(DeadlineError::OutOfOrder {
+            shorter: 50,
+            longer: Deadline::MAX,
+        })
+    );
+    assert_eq!(table.check(2, 1), Ok(()));
+    assert_eq!(
+        table.check(2, 0),
+        Err(DeadlineError::OutOfOrder {
+            shorter: 0,
+            longer: 50,
+        })
+    );
+
+    assert_eq!(table.check(1, 60), Err(DeadlineError::SharedPriorityLevel));
+    assert_eq!(table.check(1, 50), Ok(()));
+
+    table.set(2, 20);
+    assert_eq!(table.get(2), 20);
+    assert_eq!(
+        table.check(1, 50).and(table.check(0, 20)),
+        Err(DeadlineError::OutOfOrder {
+            shorter: 50,
+            longer: Deadline::MAX,
+        })
+    );
+}
+
+#[test]
+fn deadlines_cant_overlap_more_jobs() {
+    use super::Scheduler;
+    use sim::{Sim, Urgent};
+
+    // `Urgent` has room for 2 jobs, arriving at least 5 ticks apart
+    let sim = Sim::new();
+    assert_eq!(
+        sim.set_relative_deadline::<Urgent>(11),
+        Err(DeadlineError::TooManyJobs { longest: 10 })
+    );
+    assert_eq!(sim.set_relative_deadline::<Urgent>(8), Ok(()));
+    assert_eq!(sim.set_relative_deadline::<Urgent>(10), Ok(()));
+}
+
+#[test]
+fn mode_changes_stop_the_tasks_leaving_the_mode() {
+    static DEADLINES: [[Deadline; 2]; 3] = [[100, 10], [200, 10], [100, 50]];
+    // Bit sets of tasks active in modes 0 and 1, in every mode, and in mode 2
+    let (normal, everywhere, other) = (0b011, 0b111, 0b100);
+
+    let modes = Modes::new(&DEADLINES);
+    assert_eq!(modes.current(), 0);
+    assert_eq!(modes.pending(), None);
+    assert!(modes.releases(normal) && !modes.releases(other));
+
+    // Requesting the current mode is a no-op
+    modes.request(0);
+    assert_eq!(modes.pending(), None);
+
+    modes.request(2);
+    assert_eq!(modes.pending(), Some(2));
+    assert!(!modes.releases(normal));
+    assert!(modes.releases(everywhere));
+    // Tasks entering the mode wait for the switch
+    assert!(!modes.releases(other));
+
+    // Going back to the current mode still waits for the switch, to resume
+    // the tasks stopped in the meantime
+    modes.request(0);
+    assert_eq!(modes.pending(), Some(0));
+    modes.request(1);
+
+    assert_eq!(modes.take_pending(), Some(1));
+    assert_eq!(modes.current(), 1);
+    assert_eq!(modes.take_pending(), None);
+    assert_eq!(modes.deadlines(1), &[200, 10]);
+    assert!(modes.releases(normal) && !modes.releases(other));
+
+    let table = DeadlineTable::new(DEADLINES[0], [1, 1]);
+    table.load(modes.deadlines(2));
+    assert_eq!((table.get(0), table.get(1)), (100, 50));
+}
+
+#[test]
+#[should_panic(expected = "same deadline in ticks")]
+fn level_deadlines_must_stay_ordered_in_ticks() {
+    assert_level_order(&[[100, 10], [100, 50]]);
+    // e.g. 1_100 us and 1_000 us with a 1 kHz time source
+    assert_level_order(&[[100, 10], [1, 1]]);
+}
+
+#[test]
+fn server_overruns_postpone_its_deadline() {
+    let server = Server::new(10, 100);
+
+    critical_section::with(|cs| {
+        assert!(server.admit(0, &cs));
+        assert_eq!((server.deadline(), server.remaining()), (100, 10));
+
+        // The job completes with 6 ticks of its budget left
+        server.complete(6, &cs);
+        assert_eq!(server.remaining(), 6);
+
+        // Enough budget left to start a new period
+        assert!(server.admit(50, &cs));
+        assert_eq!((server.deadline(), server.remaining()), (150, 10));
+
+        // A job running for 25 ticks exhausts the budget twice
+        server.exhaust(&cs);
+        server.exhaust(&cs);
+        server.complete(5, &cs);
+        assert_eq!((server.deadline(), server.remaining()), (350, 5));
+
+        // Throttled until the deadline is within one period
+        assert_eq!(server.resumes_at(&cs), None);
+        assert!(!server.admit(100, &cs));
+        assert_eq!(server.resumes_at(&cs), Some(250));
+        assert!(!server.resume(249, &cs));
+        assert!(server.resume(250, &cs));
+        assert!(!server.resume(251, &cs));
+        assert_eq!(server.resumes_at(&cs), None);
+
+        // Not enough budget left to start a new period: the server deadline
+        // is kept
+        assert!(server.admit(250, &cs));
+        assert_eq!((server.deadline(), server.remaining()), (350, 5));
+
+        // Completing right as the budget expires exhausts it too
+        server.complete(0, &cs);
+        assert_eq!((server.deadline(), server.remaining()), (450, 10));
+    });
+}
+
+#[test]
+fn budgets_recharge_for_servers() {
+    let budgets: Budgets<1> = Budgets::new();
+
+    critical_section::with(|cs| {
+        assert_eq!(budgets.enter(0, 0, Some(10), 0, &cs), Some(10));
+        assert_eq!(budgets.remaining(4), 6);
+
+        // The server budget runs out, and is recharged
+        assert_eq!(budgets.expire(10, &cs), Some(0));
+        assert_eq!(budgets.remaining(12), 0);
+        assert_eq!(budgets.recharge(10, 12, &cs), Some(22));
+        assert_eq!(budgets.remaining(15), 7);
+        assert_eq!(budgets.expire(21, &cs), None);
+
+        // Left unhandled, an expiry leaves no budget
+        assert_eq!(budgets.remaining(30), 0);
+        assert_eq!(budgets.exit(0, 30, &cs), None);
+    });
+}
+
+#[test]
+fn budgets_pause_while_preempted() {
+    let budgets: Budgets<3> = Budgets::new();
+
+    critical_section::with(|cs| {
+        // Task 0 at level 0 starts with a budget of 100 ticks
+        assert_eq!(budgets.enter(0, 0, Some(100), 0, &cs), Some(100));
+
+        // Preempted by task 1 without a budget, itself preempted by task 2
+        assert_eq!(budgets.enter(1, 1, None, 10, &cs), None);
+        assert_eq!(budgets.enter(2, 2, Some(5), 20, &cs), Some(25));
+        assert_eq!(budgets.expire(24, &cs), None);
+
+        // Task 2 overruns, which is only reported once
+        assert_eq!(budgets.expire(25, &cs), Some(2));
+        assert_eq!(budgets.expire(26, &cs), None);
+        assert_eq!(budgets.expiry(), None);
+
+        // Task 1 has no budget to resume, task 0 gets back the 40 ticks it was
+        // preempted for
+        assert_eq!(budgets.exit(2, 30, &cs), None);
+        assert_eq!(budgets.exit(1, 50, &cs), Some(140));
+        assert_eq!(budgets.expire(139, &cs), None);
+        assert_eq!(budgets.expire(140, &cs), Some(0));
+
+        assert_eq!(budgets.exit(0, 150, &cs), None);
+    });
+}
+
+#[test]
+fn budgets_survive_the_timestamp_wrap() {
+    let budgets: Budgets<2> = Budgets::new();
+    let now = u32::MAX - 50;
+
+    critical_section::with(|cs| {
+        assert_eq!(budgets.enter(0, 0, Some(100), now, &cs), Some(49));
+        assert_eq!(budgets.expire(u32::MAX, &cs), None);
+        assert_eq!(budgets.expire(48, &cs), None);
+
+        // Preempted for 20 ticks across the wrap
+        assert_eq!(budgets.enter(1, 1, None, now + 40, &cs), None);
+        assert_eq!(budgets.exit(1, 9, &cs), Some(69));
+        assert_eq!(budgets.expire(68, &cs), None);
+        assert_eq!(budgets.expire(69, &cs), Some(0));
+
+        // An expiry landing on the sentinel value is kept, one tick earlier
+        assert_eq!(budgets.enter(1, 1, Some(50), now, &cs), Some(u32::MAX - 1));
+    });
+}
@@ -0,0 +1,315 @@
+//! Host simulator of the scheduler.
+//!
+//! [`Sim`] implements [`Scheduler`] like the generated code does, on top of a
+//! simulated clock, dispatchers and timestamper interrupts. Tests signal
+//! arrivals and run the pended dispatchers step by step, in the order the
+//! NVIC would. Preemptions are simulated by starting a job, then running a
+//! more urgent one before finishing it.
+//!
+//! The simulated hardware is thread-local, so that tests can run in
+//! parallel.
+
+use std::cell::{Cell, RefCell};
+
+#[cfg(any(feature = "stats", feature = "response-time"))]
+use crate::task::Arrivals;
+use crate::{
+    scheduler::{
+        CriticalSection, DeadlineTable, Modes, RunQueue, Scheduler, SpareTime, SystemDeadline,
+        WaitQueue,
+    },
+    task::{ActiveJobs, EdfTaskBinding, Task},
+    types::{Deadline, Timestamp},
+};
+
+/// Number of priority levels
+pub const LEVELS: usize = 3;
+pub const QUEUE_LEN: usize = 8;
+pub const NUM_TASKS: usize = 4;
+
+/// Relative deadline of every level, by run queue index
+pub const DEADLINES: [[Deadline; LEVELS]; 1] = [[1_000, 100, 10]];
+
+/// WCET of every task, by dispatcher index
+pub const WCETS: [Deadline; NUM_TASKS] = [100, 10, 10, 1];
+/// Minimum inter-arrival time of every task, by dispatcher index
+pub const MIN_INTERARRIVALS: [Option<Deadline>; NUM_TASKS] =
+    [Some(1_000), Some(100), Some(100), Some(5)];
+
+/// Worst-case spare time of the simulated app, with `U = 0.5`. The shortest
+/// spare time is in an interval of length 10, in which one job of [`Urgent`]
+/// is due. No critical section is shared between the tasks.
+pub const SPARE_TIME: SpareTime = SpareTime::new(9, 500_000, 0, 0);
+
+/// A task of the simulated app, with its dispatcher index, run queue index,
+/// and maximum number of active jobs
+pub struct SimTask<const IDX: u16, const RQ_IDX: u16, const MAX_JOBS: u16 = 1>;
+
+/// Task with the longest deadline
+pub type Slow = SimTask<0, 0>;
+/// Task with a medium deadline
+pub type Medium = SimTask<1, 1>;
+/// Task sharing its priority level with [`Medium`]
+pub type MediumToo = SimTask<2, 1>;
+/// Task with the shortest deadline, whose jobs can overlap
+pub type Urgent = SimTask<3, 2, 2>;
+
+#[derive(Default)]
+struct Hardware {
+    pended_dispatchers: Vec<u16>,
+    masked_timestampers: [bool; NUM_TASKS],
+    pending_timestampers: [bool; NUM_TASKS],
+}
+
+thread_local! {
+    static NOW: Cell<Timestamp> = const { Cell::new(0) };
+    static SPARE: Cell<SpareTime> = const { Cell::new(SPARE_TIME) };
+    static HARDWARE: RefCell<Hardware> = RefCell::default();
+    static ACTIVE_JOBS: Cell<[&'static ActiveJobs; NUM_TASKS]> =
+        Cell::new(leak_active_jobs());
+    #[cfg(any(feature = "stats", feature = "response-time"))]
+    static ARRIVALS: Cell<[&'static Arrivals; NUM_TASKS]> = Cell::new(leak_arrivals());
+}
+
+fn leak_active_jobs() -> [&'static ActiveJobs; NUM_TASKS] {
+    [(); NUM_TASKS].map(|_| &*Box::leak(Box::new(ActiveJobs::new())))
+}
+
+/// Room for the 2 overlapping jobs of [`Urgent`]
+#[cfg(any(feature = "stats", feature = "response-time"))]
+fn leak_arrivals() -> [&'static Arrivals; NUM_TASKS] {
+    [(); NUM_TASKS].map(|_| {
+        &*Box::leak(Box::new(
+            Arrivals::<[core::sync::atomic::AtomicU32; 2]>::new(),
+        )) as _
+    })
+}
+
+impl<const IDX: u16, const RQ_IDX: u16, const MAX_JOBS: u16> EdfTaskBinding
+    for SimTask<IDX, RQ_IDX, MAX_JOBS>
+{
+    const DISPATCHER_IDX: u16 = IDX;
+    const RUN_QUEUE_IDX: u16 = RQ_IDX;
+    const MAX_JOBS: u16 = MAX_JOBS;
+    const MODES: u32 = 1;
+    const WCET: Option<Deadline> = Some(WCETS[IDX as usize]);
+    const MIN_INTERARRIVAL: Option<Deadline> = MIN_INTERARRIVALS[IDX as usize];
+
+    fn active_jobs() -> &'static ActiveJobs {
+        ACTIVE_JOBS.get()[IDX as usize]
+    }
+
+    #[cfg(any(feature = "stats", feature = "response-time"))]
+    fn arrivals() -> &'static Arrivals {
+        ARRIVALS.get()[IDX as usize]
+    }
+
+    fn mask_timestamper_interrupt() {
+        HARDWARE.with_borrow_mut(|hw| hw.masked_timestampers[IDX as usize] = true);
+    }
+
+    fn unpend_timestamper_interrupt() {
+        HARDWARE.with_borrow_mut(|hw| hw.pending_timestampers[IDX as usize] = false);
+    }
+
+    unsafe fn unmask_timestamper_interrupt() {
+        HARDWARE.with_borrow_mut(|hw| hw.masked_timestampers[IDX as usize] = false);
+    }
+}
+
+pub struct Sim {
+    run_queue: RunQueue<LEVELS>,
+    system_deadline: SystemDeadline,
+    wait_queue: WaitQueue<QUEUE_LEN>,
+    deadlines: DeadlineTable<LEVELS>,
+    modes: Modes<LEVELS>,
+    #[cfg(feature = "stats")]
+    stats: super::super::stats::SchedulerStats<[super::super::stats::TaskStats; NUM_TASKS]>,
+    #[cfg(feature = "response-time")]
+    response_times: super::super::response_time::ResponseTimes<
+        [super::super::response_time::TaskTimings; NUM_TASKS],
+    >,
+}
+
+impl Scheduler<LEVELS, QUEUE_LEN> for Sim {
+    fn now() -> Timestamp {
+        NOW.get()
+    }
+
+    fn pend_dispatcher(idx: u16) {
+        HARDWARE.with_borrow_mut(|hw| {
+            if !hw.pended_dispatchers.contains(&idx) {
+                hw.pended_dispatchers.push(idx);
+            }
+        });
+    }
+
+    fn switch_timestampers(_mode: u16) {}
+
+    fn resume_servers(_cs: &CriticalSection<'_>, _now: Timestamp) {}
+
+    fn spare_time(_mode: u16) -> Option<SpareTime> {
+        Some(SPARE.get())
+    }
+
+    fn demand(&self, cs: &CriticalSection<'_>, window: Deadline) -> Option<Deadline> {
+        [
+            self.task_demand::<Slow>(cs, window)?,
+            self.task_demand::<Medium>(cs, window)?,
+            self.task_demand::<MediumToo>(cs, window)?,
+            self.task_demand::<Urgent>(cs, window)?,
+        ]
+        .into_iter()
+        .reduce(Deadline::saturating_add)
+    }
+
+    fn run_queue(&self) -> &RunQueue<LEVELS> {
+        &self.run_queue
+    }
+
+    fn system_deadline(&self) -> &SystemDeadline {
+        &self.system_deadline
+    }
+
+    fn wait_queue(&self) -> &WaitQueue<QUEUE_LEN> {
+        &self.wait_queue
+    }
+
+    fn deadlines(&self) -> &DeadlineTable<LEVELS> {
+        &self.deadlines
+    }
+
+    fn modes(&self) -> &Modes<LEVELS> {
+        &self.modes
+    }
+
+    #[cfg(feature = "stats")]
+    fn stats(&self) -> &super::super::stats::SchedulerStats {
+        &self.stats
+    }
+
+    #[cfg(feature = "response-time")]
+    fn response_times(&self) -> &super::super::response_time::ResponseTimes {
+        &self.response_times
+    }
+}
+
+impl Sim {
+    /// Create a simulator at time 0, resetting the simulated hardware
+    pub fn new() -> Self {
+        NOW.set(0);
+        SPARE.set(SPARE_TIME);
+        HARDWARE.set(Hardware::default());
+        ACTIVE_JOBS.set(leak_active_jobs());
+
+        Self {
+            run_queue: RunQueue::new(),
+            system_deadline: SystemDeadline::new(),
+            wait_queue: WaitQueue::new(),
+            deadlines: DeadlineTable::new(DEADLINES[0], [1, 2, 1]),
+            modes: Modes::new(&DEADLINES),
+            #[cfg(feature = "stats")]
+            stats: super::super::stats::SchedulerStats::new(),
+            #[cfg(feature = "response-time")]
+            response_times: super::super::response_time::ResponseTimes::new(),
+        }
+    }
+
+    pub fn set_now(&self, now: Timestamp) {
+        NOW.set(now);
+    }
+
+    /// Replace the worst-case spare time computed for the simulated app, e.g.
+    /// to model critical sections shared between the tasks
+    pub fn set_spare_time(&self, spare: SpareTime) {
+        SPARE.set(spare);
+    }
+
+    /// Signal an arrival of `T`. Its timestamper runs right away, unless it is
+    /// masked, in which case the arrival stays pending.
+    pub fn arrive<T: EdfTaskBinding>(&self) {
+        let idx = T::DISPATCHER_IDX as usize;
+        let masked = HARDWARE.with_borrow_mut(|hw| {
+            hw.pending_timestampers[idx] = true;
+            hw.masked_timestampers[idx]
+        });
+        if masked {
+            return;
+        }
+
+        T::unpend_timestamper_interrupt();
+
+        // Same as the generated timestamper
+        T::mask_timestamper_interrupt();
+        critical_section::with(|cs| {
+            if !self.modes().releases(T::MODES) {
+                return;
+            }
+
+            if T::active_jobs().release(&cs) < T::MAX_JOBS {
+                unsafe { T::unmask_timestamper_interrupt() };
+            }
+
+            #[cfg(any(feature = "stats", feature = "response-time"))]
+            T::arrivals().push(Self::now(), &cs);
+
+            self.schedule(
+                cs,
+                Task::new(
+                    self.deadlines().get(T::RUN_QUEUE_IDX),
+                    T::DISPATCHER_IDX,
+                    T::RUN_QUEUE_IDX,
+                ),
+            );
+        });
+    }
+
+    /// Start the job pended in the dispatcher of `T`
+    pub fn start<T: EdfTaskBinding>(&self) {
+        HARDWARE.with_borrow_mut(|hw| {
+            let pos = hw
+                .pended_dispatchers
+                .iter()
+                .position(|&idx| idx == T::DISPATCHER_IDX)
+                .expect("The dispatcher isn't pended");
+            hw.pended_dispatchers.remove(pos);
+        });
+
+        self.dispatcher_entry::<T>();
+    }
+
+    /// Complete the running job of `T`
+    pub fn finish<T: EdfTaskBinding>(&self) {
+        self.dispatcher_exit::<T>(T::RUN_QUEUE_IDX);
+    }
+
+    /// Run the job pended in the dispatcher of `T` for `exec` ticks, without
+    /// preemption
+    pub fn run<T: EdfTaskBinding>(&self, exec: Deadline) {
+        self.start::<T>();
+        self.set_now(Self::now() + exec);
+        self.finish::<T>();
+    }
+
+    /// Returns the pended dispatchers, by dispatcher index
+    pub fn pended(&self) -> Vec<u16> {
+        let mut pended = HARDWARE.with_borrow(|hw| hw.pended_dispatchers.clone());
+        pended.sort_unstable();
+        pended
+    }
+
+    /// Returns whether the timestamper of `T` is masked
+    pub fn masked<T: EdfTaskBinding>(&self) -> bool {
+        HARDWARE.with_borrow(|hw| hw.masked_timestampers[T::DISPATCHER_IDX as usize])
+    }
+
+    /// Returns whether an arrival of `T` is pending
+    pub fn arrival_pending<T: EdfTaskBinding>(&self) -> bool {
+        HARDWARE.with_borrow(|hw| hw.pending_timestampers[T::DISPATCHER_IDX as usize])
+    }
+
+    /// Returns the number of jobs in the wait queue
+    pub fn queued(&self) -> usize {
+        critical_section::with(|cs| self.wait_queue().len(cs))
+    }
+}
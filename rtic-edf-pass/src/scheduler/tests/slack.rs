@@ -0,0 +1,112 @@
+use super::sim::{Medium, SPARE_TIME, Sim, Slow, Urgent};
+use crate::{
+    scheduler::{Scheduler, SpareTime},
+    types::Deadline,
+};
+
+#[test]
+fn idle_slack_is_the_spare_time() {
+    let sim = Sim::new();
+    assert_eq!(sim.slack(), SPARE_TIME.min());
+    assert_eq!(sim.slack(), 9);
+}
+
+#[test]
+fn slack_shrinks_as_the_earliest_deadline_approaches() {
+    let sim = Sim::new();
+
+    // The job of `Urgent` and another one released right now are due at 10
+    sim.arrive::<Urgent>();
+    assert_eq!(sim.slack(), 8);
+
+    sim.start::<Urgent>();
+    sim.set_now(6);
+    assert_eq!(sim.slack(), 3);
+
+    sim.set_now(10);
+    assert_eq!(sim.slack(), 0);
+
+    sim.finish::<Urgent>();
+    assert_eq!(sim.slack(), 9);
+}
+
+#[test]
+fn active_jobs_count_for_their_whole_wcet() {
+    let sim = Sim::new();
+
+    sim.arrive::<Slow>();
+    sim.start::<Slow>();
+    assert_eq!(sim.slack(), 9);
+
+    // Past the deadline of `Medium`, the worst-case spare time can't cover both
+    // active jobs
+    sim.arrive::<Medium>();
+    assert_eq!(sim.slack(), 0);
+
+    sim.run::<Medium>(10);
+    sim.set_now(500);
+    assert_eq!(sim.slack(), 9);
+}
+
+#[test]
+fn work_within_the_slack_meets_every_deadline() {
+    let sim = Sim::new();
+
+    sim.arrive::<Slow>();
+    sim.start::<Slow>();
+    let slack = sim.slack();
+
+    // `Urgent` arrives right away, but the slack is used first
+    sim.arrive::<Urgent>();
+    sim.set_now(slack);
+    sim.run::<Urgent>(1);
+    assert!(Sim::now() <= 10);
+}
+
+#[test]
+fn work_within_the_slack_leaves_room_for_blocking() {
+    // `Slow` shares a resource with `Urgent`, which it locks for 5 ticks. The
+    // shortest spare time is now 10 - 1 - 5, and the offset accounts for the
+    // blocking too.
+    const CRITICAL_SECTION: Deadline = 5;
+
+    let sim = Sim::new();
+    sim.set_spare_time(SpareTime::new(4, 500_000, 5, CRITICAL_SECTION));
+
+    sim.arrive::<Urgent>();
+    assert_eq!(sim.slack(), 3);
+    sim.run::<Urgent>(1);
+
+    let slack = sim.slack();
+    assert_eq!(slack, 4);
+
+    // `Slow` locks the resource right before `Urgent` arrives, then the slack is
+    // used, and `Urgent` waits for the end of the critical section
+    sim.arrive::<Slow>();
+    sim.start::<Slow>();
+    sim.arrive::<Urgent>();
+    let deadline = Sim::now() + 10;
+    sim.set_now(Sim::now() + slack + CRITICAL_SECTION);
+    sim.run::<Urgent>(1);
+    assert!(Sim::now() <= deadline);
+}
+
+#[test]
+fn changed_deadlines_give_no_slack() {
+    let sim = Sim::new();
+
+    sim.set_relative_deadline::<Slow>(500).unwrap();
+    assert_eq!(sim.slack(), 0);
+
+    sim.set_relative_deadline::<Slow>(1_000).unwrap();
+    assert_eq!(sim.slack(), 9);
+}
+
+#[test]
+fn spare_time_grows_with_the_interval() {
+    let spare = SpareTime::new(9, 500_000, 20, 0);
+
+    assert_eq!(spare.after(10), 9);
+    assert_eq!(spare.after(100), 30);
+    assert_eq!(spare.after(u32::MAX), u32::MAX / 2 - 20);
+}
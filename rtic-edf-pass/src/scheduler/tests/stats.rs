@@ -0,0 +1,41 @@
+use super::sim::{Sim, Urgent};
+use crate::{scheduler::Scheduler, task::EdfTaskBinding};
+
+#[test]
+fn overlapping_jobs_keep_their_arrival_time() {
+    let sim = Sim::new();
+
+    sim.arrive::<Urgent>();
+    sim.start::<Urgent>();
+
+    // The second job arrives while the first one runs, and waits for it
+    sim.set_now(5);
+    sim.arrive::<Urgent>();
+    assert_eq!(Urgent::active_jobs().get(), 2);
+
+    sim.set_now(8);
+    sim.finish::<Urgent>();
+    let stats = sim.stats().task(Urgent::DISPATCHER_IDX).snapshot();
+    assert_eq!(stats.max_response_time, 8);
+    assert_eq!(stats.min_slack, 2);
+
+    sim.run::<Urgent>(4);
+    let stats = sim.stats().task(Urgent::DISPATCHER_IDX).snapshot();
+    assert_eq!(stats.activations, 2);
+    assert_eq!(stats.max_response_time, 8);
+    assert_eq!(stats.min_slack, 2);
+
+    #[cfg(feature = "response-time")]
+    {
+        let timings = sim.response_times().task(Urgent::DISPATCHER_IDX).snapshot();
+        assert_eq!(
+            (timings.response_time.min, timings.response_time.max),
+            (7, 8)
+        );
+        assert_eq!(
+            (timings.release_jitter.min, timings.release_jitter.max),
+            (0, 3)
+        );
+        assert_eq!((timings.busy_time.min, timings.busy_time.max), (4, 8));
+    }
+}
@@ -0,0 +1,279 @@
+//! Binary scheduling event trace, enabled by the `trace` feature.
+//!
+//! The scheduler records an [`Event`] every time a job arrives, is enqueued,
+//! is directly executed, is dispatched, completes or misses its deadline.
+//! Events are pushed to the global [`TRACE`] ring buffer, which is meant to be
+//! drained from a low priority context (for example the idle task) and
+//! forwarded to the host, for example over an RTT up channel:
+//!
+//! ```ignore
+//! let mut buf = [0; 64];
+//! let n = rtic_edf_pass::scheduler::trace::TRACE.drain(&mut buf);
+//! channel.write(&buf[..n]);
+//! ```
+//!
+//! Each event is encoded in [`Event::SIZE`] bytes: the event kind, a reserved
+//! byte, the task's dispatcher index (little-endian `u16`) and the timestamp
+//! in ticks of the time source (little-endian `u32`). Events are dropped when
+//! the buffer is full. The `edf-trace` host crate decodes the stream.
+//!
+//! The buffer is lock-free on targets with atomic compare-and-swap, such as
+//! ARMv7-M: a push reserves its slot by advancing the write index with a CAS,
+//! so recording an event never masks interrupts. ARMv6-M has no atomic
+//! read-modify-write instructions, so there, each push reserves its slot in a
+//! short critical section instead, which adds a few cycles of global interrupt
+//! masking to every scheduler event.
+
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use crate::types::Timestamp;
+
+/// Number of events held by [`TRACE`]
+pub const TRACE_BUFFER_LEN: usize = 256;
+
+/// Global trace buffer written by the scheduler
+pub static TRACE: TraceBuffer<TRACE_BUFFER_LEN> = TraceBuffer::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum EventKind {
+    /// A job was released by its timestamper
+    Arrival = 0,
+    /// A released job was put in the wait queue
+    Enqueue = 1,
+    /// A job was handed over to its dispatcher, either right after its
+    /// release or when leaving the wait queue
+    DirectExecute = 2,
+    /// A dispatcher started running a job
+    Dispatch = 3,
+    /// A job completed
+    Complete = 4,
+    /// A job completed after its deadline
+    Miss = 5,
+    /// A job waiting in the wait queue was cancelled
+    Cancel = 6,
+}
+
+impl EventKind {
+    pub fn from_u8(kind: u8) -> Option<Self> {
+        Some(match kind {
+            0 => Self::Arrival,
+            1 => Self::Enqueue,
+            2 => Self::DirectExecute,
+            3 => Self::Dispatch,
+            4 => Self::Complete,
+            5 => Self::Miss,
+            6 => Self::Cancel,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Event {
+    pub kind: EventKind,
+    /// Dispatcher index of the task
+    pub task: u16,
+    pub timestamp: Timestamp,
+}
+
+impl Event {
+    /// Size of an encoded event, in bytes
+    pub const SIZE: usize = 8;
+
+    pub fn encode(&self) -> [u8; Self::SIZE] {
+        let [t0, t1] = self.task.to_le_bytes();
+        let [ts0, ts1, ts2, ts3] = self.timestamp.to_le_bytes();
+        [self.kind as u8, 0, t0, t1, ts0, ts1, ts2, ts3]
+    }
+
+    /// Decode an event. Returns `None` if the event kind is unknown.
+    pub fn decode(bytes: &[u8; Self::SIZE]) -> Option<Self> {
+        Some(Self {
+            kind: EventKind::from_u8(bytes[0])?,
+            task: u16::from_le_bytes([bytes[2], bytes[3]]),
+            timestamp: u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]),
+        })
+    }
+
+    fn to_words(self) -> [u32; 2] {
+        let [b0, b1, b2, b3, b4, b5, b6, b7] = self.encode();
+        [
+            u32::from_le_bytes([b0, b1, b2, b3]),
+            u32::from_le_bytes([b4, b5, b6, b7]),
+        ]
+    }
+}
+
+/// A slot of a [`TraceBuffer`]
+struct Slot {
+    /// Sequence number: the index of the next event the slot can hold while it
+    /// is free, and that index plus one once the event is written
+    seq: AtomicU32,
+    words: [AtomicU32; 2],
+}
+
+/// Ring buffer of encoded events.
+///
+/// Events can be pushed from any context, including by a producer preempting
+/// another one. A producer first reserves a slot by advancing the head, then
+/// writes the event and publishes it through the slot's sequence number. A
+/// single consumer drains the published events in order, stopping at a slot
+/// which is reserved but not written yet.
+pub struct TraceBuffer<const N: usize> {
+    slots: [Slot; N],
+    /// Total number of slots reserved. Only written by producers.
+    head: AtomicU32,
+    /// Total number of events drained. Only written by the consumer.
+    tail: AtomicU32,
+    dropped: AtomicU32,
+}
+
+#[allow(clippy::new_without_default)]
+impl<const N: usize> TraceBuffer<N> {
+    pub const fn new() -> Self {
+        assert!(
+            N.is_power_of_two(),
+            "Trace buffer length must be a power of two"
+        );
+
+        let mut slots = [const {
+            Slot {
+                seq: AtomicU32::new(0),
+                words: [AtomicU32::new(0), AtomicU32::new(0)],
+            }
+        }; N];
+        let mut i = 0;
+        while i < N {
+            slots[i].seq = AtomicU32::new(i as u32);
+            i += 1;
+        }
+
+        Self {
+            slots,
+            head: AtomicU32::new(0),
+            tail: AtomicU32::new(0),
+            dropped: AtomicU32::new(0),
+        }
+    }
+
+    /// Push an event, or drop it if the buffer is full
+    #[inline]
+    pub fn push(&self, event: Event) {
+        let Some(head) = self.reserve() else {
+            self.count_drop();
+            return;
+        };
+
+        let slot = &self.slots[head as usize % N];
+        let [w0, w1] = event.to_words();
+        slot.words[0].store(w0, Ordering::Relaxed);
+        slot.words[1].store(w1, Ordering::Relaxed);
+        slot.seq.store(head.wrapping_add(1), Ordering::Release);
+    }
+
+    /// Reserve the slot at the head, and returns its index, or `None` if the
+    /// consumer hasn't freed it yet
+    #[cfg(target_has_atomic = "32")]
+    #[inline]
+    fn reserve(&self) -> Option<u32> {
+        let mut head = self.head.load(Ordering::Relaxed);
+        loop {
+            let seq = self.slots[head as usize % N].seq.load(Ordering::Acquire);
+            match seq.wrapping_sub(head) as i32 {
+                0 => match self.head.compare_exchange_weak(
+                    head,
+                    head.wrapping_add(1),
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => return Some(head),
+                    Err(current) => head = current,
+                },
+                // The slot still holds an event from the previous lap
+                diff if diff < 0 => return None,
+                // Another producer reserved the slot in the meantime
+                _ => head = self.head.load(Ordering::Relaxed),
+            }
+        }
+    }
+
+    /// Reserve the slot at the head, and returns its index, or `None` if the
+    /// consumer hasn't freed it yet
+    #[cfg(not(target_has_atomic = "32"))]
+    #[inline]
+    fn reserve(&self) -> Option<u32> {
+        critical_section::with(|_| {
+            let head = self.head.load(Ordering::Relaxed);
+            let seq = self.slots[head as usize % N].seq.load(Ordering::Acquire);
+            if seq != head {
+                return None;
+            }
+
+            self.head.store(head.wrapping_add(1), Ordering::Relaxed);
+            Some(head)
+        })
+    }
+
+    #[cfg(target_has_atomic = "32")]
+    #[inline]
+    fn count_drop(&self) {
+        self.dropped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[cfg(not(target_has_atomic = "32"))]
+    #[inline]
+    fn count_drop(&self) {
+        critical_section::with(|_| {
+            let dropped = self.dropped.load(Ordering::Relaxed);
+            self.dropped
+                .store(dropped.wrapping_add(1), Ordering::Relaxed);
+        });
+    }
+
+    /// Move as many whole encoded events as fit into `buf`, and returns the
+    /// number of bytes written.
+    ///
+    /// Must only be called from a single context.
+    pub fn drain(&self, buf: &mut [u8]) -> usize {
+        let mut tail = self.tail.load(Ordering::Relaxed);
+        let mut written = 0;
+
+        for chunk in buf.chunks_exact_mut(Event::SIZE) {
+            let slot = &self.slots[tail as usize % N];
+            // Stop at the first slot which is free, or reserved but not written yet
+            if slot.seq.load(Ordering::Acquire) != tail.wrapping_add(1) {
+                break;
+            }
+
+            chunk[..4].copy_from_slice(&slot.words[0].load(Ordering::Relaxed).to_le_bytes());
+            chunk[4..].copy_from_slice(&slot.words[1].load(Ordering::Relaxed).to_le_bytes());
+            // Free the slot for the event of the next lap
+            slot.seq
+                .store(tail.wrapping_add(N as u32), Ordering::Release);
+
+            tail = tail.wrapping_add(1);
+            written += Event::SIZE;
+        }
+
+        self.tail.store(tail, Ordering::Relaxed);
+        written
+    }
+
+    /// Returns the number of events dropped because the buffer was full
+    pub fn dropped(&self) -> u32 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+/// Record an event in the global trace buffer
+#[inline]
+pub(super) fn record(kind: EventKind, task: u16, timestamp: Timestamp) {
+    TRACE.push(Event {
+        kind,
+        task,
+        timestamp,
+    });
+}
@@ -1,6 +1,16 @@
-use core::fmt::Debug;
+#[cfg(any(feature = "stats", feature = "response-time"))]
+use core::sync::atomic::AtomicU32;
+use core::{
+    fmt::Debug,
+    sync::atomic::{AtomicU16, Ordering},
+};
 
-use crate::types::{Deadline, Timestamp};
+use critical_section::CriticalSection;
+
+use crate::{
+    scheduler::Server,
+    types::{Deadline, Timestamp},
+};
 
 pub trait EdfTaskBinding {
     /// Dispatcher index associated with this task
@@ -9,6 +19,39 @@ pub trait EdfTaskBinding {
     /// The index this task's dispatcher is associated with in the run queue
     const RUN_QUEUE_IDX: u16;
 
+    /// Maximum number of jobs of this task which can be active (released but
+    /// not completed) at once: `ceil(deadline / min inter-arrival time)`, or 1
+    /// if the task doesn't declare a minimum inter-arrival time
+    const MAX_JOBS: u16;
+
+    /// Bit set of the operating modes in which this task is active, bit `i`
+    /// standing for mode `i`
+    const MODES: u32;
+
+    /// Execution budget of every job of this task, in ticks, if enforced
+    const BUDGET: Option<Deadline> = None;
+
+    /// Worst-case execution time of every job of this task, in ticks, if
+    /// declared
+    const WCET: Option<Deadline> = None;
+
+    /// Minimum inter-arrival time of this task, in ticks, if declared
+    const MIN_INTERARRIVAL: Option<Deadline> = None;
+
+    /// Active jobs of this task
+    fn active_jobs() -> &'static ActiveJobs;
+
+    /// Arrival times of the active jobs of this task
+    #[cfg(any(feature = "stats", feature = "response-time"))]
+    fn arrivals() -> &'static Arrivals;
+
+    /// Constant bandwidth server of this task, if it is an aperiodic task
+    /// served by one
+    #[inline]
+    fn server() -> Option<&'static Server> {
+        None
+    }
+
     /// Mask the task's timestamper interrupt, therefore preventing it from
     /// preempting
     fn mask_timestamper_interrupt();
@@ -25,6 +68,136 @@ pub trait EdfTaskBinding {
     unsafe fn unmask_timestamper_interrupt();
 }
 
+/// Number of active jobs of a task. The timestamper keeps its interrupt
+/// unmasked until [`EdfTaskBinding::MAX_JOBS`] jobs are active, so that jobs of
+/// tasks with deadlines longer than their inter-arrival time can overlap.
+/// The interrupt source of such tasks must not be left pending once the
+/// timestamper returns (for example, a software-pended or edge-triggered
+/// interrupt), otherwise it would immediately release another job. The pass
+/// therefore only accepts them with an explicit `overlapping_jobs = true`.
+///
+/// Only updated inside critical sections.
+pub struct ActiveJobs(AtomicU16);
+
+impl ActiveJobs {
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        Self(AtomicU16::new(0))
+    }
+
+    #[inline]
+    pub fn get(&self) -> u16 {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Record the release of a job, and return the number of active jobs
+    #[inline]
+    pub fn release(&self, _cs: &CriticalSection<'_>) -> u16 {
+        let jobs = self.get().saturating_add(1);
+        self.0.store(jobs, Ordering::Relaxed);
+        jobs
+    }
+
+    /// Record the completion of a job
+    #[inline]
+    pub fn complete(&self, _cs: &CriticalSection<'_>) {
+        self.0
+            .store(self.get().saturating_sub(1), Ordering::Relaxed);
+    }
+}
+
+/// Arrival times of the active jobs of a task, oldest first, used to measure
+/// the response time of overlapping jobs. The jobs of a task complete in the
+/// order they were released, since they share a dispatcher and their absolute
+/// deadlines never decrease.
+///
+/// The generated code stores an `Arrivals<[AtomicU32; MAX_JOBS]>` per task,
+/// which is accessed as an `Arrivals`.
+#[cfg(any(feature = "stats", feature = "response-time"))]
+pub struct Arrivals<T: ?Sized = [AtomicU32]> {
+    // Both indices count modulo twice the capacity, to tell a full FIFO from an
+    // empty one. Only written inside critical sections.
+    head: AtomicU16,
+    // Only written by the task's dispatcher
+    tail: AtomicU16,
+    slots: T,
+}
+
+#[cfg(any(feature = "stats", feature = "response-time"))]
+#[allow(clippy::new_without_default)]
+impl<const N: usize> Arrivals<[AtomicU32; N]> {
+    pub const fn new() -> Self {
+        assert!(
+            N > 0 && N <= u16::MAX as usize / 2,
+            "Unsupported number of active jobs"
+        );
+
+        Self {
+            head: AtomicU16::new(0),
+            tail: AtomicU16::new(0),
+            slots: [const { AtomicU32::new(0) }; N],
+        }
+    }
+}
+
+#[cfg(any(feature = "stats", feature = "response-time"))]
+impl Arrivals {
+    #[inline]
+    fn wrap(&self) -> u16 {
+        2 * self.slots.len() as u16
+    }
+
+    /// Returns the number of recorded arrivals
+    #[inline]
+    pub fn len(&self) -> u16 {
+        let head = self.head.load(Ordering::Acquire);
+        let tail = self.tail.load(Ordering::Acquire);
+        (head + self.wrap() - tail) % self.wrap()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Record the arrival of a job. Dropped if more jobs than the capacity are
+    /// active, which the timestamper prevents.
+    #[inline]
+    pub fn push(&self, now: Timestamp, _cs: &CriticalSection<'_>) {
+        if self.len() as usize == self.slots.len() {
+            return;
+        }
+
+        let head = self.head.load(Ordering::Relaxed);
+        self.slots[head as usize % self.slots.len()].store(now, Ordering::Relaxed);
+        self.head.store((head + 1) % self.wrap(), Ordering::Release);
+    }
+
+    /// Returns the arrival time of the oldest active job, which just
+    /// completed. Must be called from the task's dispatcher.
+    #[inline]
+    pub fn pop(&self) -> Option<Timestamp> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let tail = self.tail.load(Ordering::Relaxed);
+        let arrival = self.slots[tail as usize % self.slots.len()].load(Ordering::Relaxed);
+        self.tail.store((tail + 1) % self.wrap(), Ordering::Release);
+        Some(arrival)
+    }
+
+    /// Forget the arrivals of the `jobs` most recent jobs, which were
+    /// cancelled before they started
+    #[inline]
+    pub fn cancel(&self, jobs: u16, _cs: &CriticalSection<'_>) {
+        let jobs = jobs.min(self.len());
+        let head = self.head.load(Ordering::Relaxed);
+        self.head
+            .store((head + self.wrap() - jobs) % self.wrap(), Ordering::Release);
+    }
+}
+
 #[derive(Debug)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct Task {
@@ -56,6 +229,10 @@ impl Task {
         self.rel_deadline = deadline;
     }
 
+    /// Absolute deadlines are compared as plain integers and must not
+    /// overflow, which bounds how long an app can run on a 32-bit time source.
+    /// Only the execution budgets (see [`Budgets`](crate::scheduler::Budgets))
+    /// handle the wrap of the time source.
     #[inline]
     pub(crate) fn into_scheduled(self, now: Timestamp) -> ScheduledTask {
         let (deadline, wrapped) = now.overflowing_add(self.rel_deadline);
@@ -0,0 +1,87 @@
+use crate::types::{Deadline, Timestamp};
+
+/// A free-running, monotonic time source used to timestamp task arrivals.
+///
+/// Timestamps are only ever compared against other timestamps produced by the
+/// same time source, so the tick rate is only used to convert the deadlines
+/// declared in microseconds into ticks.
+pub trait TimeSource {
+    /// Number of ticks per second
+    const TICK_HZ: u32;
+
+    /// Configure and start the time source.
+    ///
+    /// # Safety
+    ///
+    /// Must be called exactly once, during initialization and before any task
+    /// can be scheduled.
+    unsafe fn start();
+
+    /// Returns the current time, in ticks
+    fn now() -> Timestamp;
+}
+
+/// Convert a duration expressed in microseconds into ticks of the time source
+/// `T`.
+///
+/// This is meant to be evaluated at compile time by the generated code, which
+/// turns an out of range deadline into a compilation error.
+#[inline]
+pub const fn us_to_ticks<T: TimeSource>(us: Deadline) -> Deadline {
+    match try_us_to_ticks::<T>(us) {
+        Some(ticks) => ticks,
+        None => panic!("Deadline doesn't fit in the time source's tick range"),
+    }
+}
+
+/// Convert a duration expressed in microseconds into ticks of the time source
+/// `T`, rounding up.
+///
+/// Used for the execution times, which must not be underestimated.
+#[inline]
+pub const fn us_to_ticks_ceil<T: TimeSource>(us: Deadline) -> Deadline {
+    let ticks = (us as u64 * T::TICK_HZ as u64).div_ceil(1_000_000);
+    if ticks <= Deadline::MAX as u64 {
+        ticks as Deadline
+    } else {
+        panic!("Execution time doesn't fit in the time source's tick range")
+    }
+}
+
+/// Convert a duration expressed in microseconds into ticks of the time source
+/// `T`, or return `None` if it doesn't fit in the tick range
+#[inline]
+pub const fn try_us_to_ticks<T: TimeSource>(us: Deadline) -> Option<Deadline> {
+    let ticks = us as u64 * T::TICK_HZ as u64 / 1_000_000;
+    if ticks <= Deadline::MAX as u64 {
+        Some(ticks as Deadline)
+    } else {
+        None
+    }
+}
+
+/// A one-shot timer signalling execution budget overruns, selected through the
+/// `budget_timer` app argument.
+///
+/// Expiries are expressed in ticks of the app's time source. When the timer
+/// expires, its interrupt handler must call the generated `on_budget_timer()`
+/// function, at a priority higher than every dispatcher.
+pub trait BudgetTimer {
+    /// Configure the timer and enable its interrupt.
+    ///
+    /// # Safety
+    ///
+    /// Must be called exactly once, during initialization and before any task
+    /// can be scheduled.
+    unsafe fn start();
+
+    /// Arm the timer to expire at `expiry`, replacing the previous expiry. If
+    /// `expiry` has already passed, the timer must expire immediately.
+    ///
+    /// Expiries wrap around with the time source: `expiry` has passed if
+    /// `now.wrapping_sub(expiry)` is less than half of the time source's range.
+    fn arm(expiry: Timestamp);
+
+    /// Disarm the timer
+    fn disarm();
+}